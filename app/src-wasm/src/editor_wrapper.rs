@@ -1,15 +1,19 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use embroiderly_editor::actions::layers::LayerVisibility;
-use embroiderly_editor::actions::palette::SortPaletteBy;
+use embroiderly_editor::actions::palette::{ReplaceColorScope, SortPaletteBy};
+use embroiderly_editor::actions::pattern::ResizeAnchor;
+use embroiderly_editor::actions::stitches::{StitchKinds, StitchShape, StitchTransform};
 use embroiderly_editor::actions::{
   DisplayAction, FabricAction, GridAction, ImageAction, LayerAction, PaletteAction, PatternAction, PublishAction,
   StitchAction,
 };
-use embroiderly_editor::{Editor, EditorAction, EditorEvent};
+use embroiderly_editor::{Editor, EditorAction, EditorEvent, HistoryLimits, Selection, Symmetry};
 use embroiderly_parsers::PatternFormat;
-use embroiderly_pattern::{EmbroiderlyProject, EmbroiderlyProjectId, Pattern, ReferenceImage, Stitch};
+use embroiderly_pattern::{
+  EmbroiderlyProject, EmbroiderlyProjectId, LayerBlendMode, LayerGroup, Pattern, ReferenceImage, Stitch,
+};
 use embroiderly_web::{opfs, timers};
 use js_sys::Uint8Array;
 use wasm_bindgen::prelude::*;
@@ -17,8 +21,22 @@ use wasm_bindgen::prelude::*;
 use crate::error::{Error, ErrorKind};
 use crate::persistence_manager::PersistenceManager;
 
+/// The history limits of every open pattern, keeping long editing sessions within a reasonable share of the Wasm heap.
+const HISTORY_LIMITS: HistoryLimits = HistoryLimits {
+  max_entries: Some(1000),
+  max_bytes: Some(64 * 1024 * 1024),
+};
+
 thread_local! {
   pub(crate) static EDITOR: RefCell<Option<Editor>> = const { RefCell::new(None) };
+
+  /// The time of the journal entry being replayed, so the restored history keeps the original timestamps.
+  static REPLAY_TIMESTAMP: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// The clock of the editor history.
+fn now() -> u64 {
+  REPLAY_TIMESTAMP.get().unwrap_or_else(|| js_sys::Date::now() as u64)
 }
 
 #[wasm_bindgen(getter_with_clone)]
@@ -35,6 +53,7 @@ impl std::fmt::Display for OpenPatternResult {
 }
 
 #[derive(borsh::BorshSerialize, borsh::BorshDeserialize)]
+#[allow(clippy::large_enum_variant)]
 enum JournalEntry {
   Action(EditorAction),
   Undo,
@@ -44,6 +63,8 @@ enum JournalEntry {
   StartTransaction,
   EndTransaction,
   Checkpoint,
+  JumpTo(u32),
+  SwitchBranch(u32),
 }
 
 #[wasm_bindgen]
@@ -59,7 +80,10 @@ impl EditorWrapper {
   /// Creates a new `EditorWrapper`.
   pub async fn create(callback: js_sys::Function) -> Result<Self, Error> {
     EDITOR.with(|cell| {
-      *cell.borrow_mut() = Some(Editor::new());
+      let mut editor = Editor::new();
+      editor.set_clock(now);
+      editor.set_history_limits(HISTORY_LIMITS);
+      *cell.borrow_mut() = Some(editor);
     });
     Ok(Self {
       callback,
@@ -151,18 +175,130 @@ impl EditorWrapper {
     self.add_stitch_impl(project_id, layer_index, stitch_data).await
   }
 
+  /// Adds many stitches to the pattern layer as a single action.
+  #[wasm_bindgen(js_name = "addStitches")]
+  pub async fn add_stitches(&self, project_id: &str, layer_index: u32, stitches_data: &[u8]) -> Result<(), Error> {
+    self.add_stitches_impl(project_id, layer_index, stitches_data).await
+  }
+
   /// Removes a known stitch from the pattern layer.
   #[wasm_bindgen(js_name = "removeStitch")]
   pub async fn remove_stitch(&self, project_id: &str, layer_index: u32, stitch_data: &[u8]) -> Result<(), Error> {
     self.remove_stitch_impl(project_id, layer_index, stitch_data).await
   }
 
+  /// Removes many known stitches from the pattern layer as a single action.
+  #[wasm_bindgen(js_name = "removeStitches")]
+  pub async fn remove_stitches(&self, project_id: &str, layer_index: u32, stitches_data: &[u8]) -> Result<(), Error> {
+    self.remove_stitches_impl(project_id, layer_index, stitches_data).await
+  }
+
+  /// Removes every stitch of the given kinds from a layer, or from all unlocked layers if no layer is specified.
+  #[wasm_bindgen(js_name = "removeStitchesByKind")]
+  pub async fn remove_stitches_by_kind(
+    &self,
+    project_id: &str,
+    layer_index: Option<u32>,
+    kinds_data: &[u8],
+  ) -> Result<(), Error> {
+    self
+      .remove_stitches_by_kind_impl(project_id, layer_index, kinds_data)
+      .await
+  }
+
+  /// Moves stitches from one layer to another.
+  #[wasm_bindgen(js_name = "moveStitchesToLayer")]
+  pub async fn move_stitches_to_layer(
+    &self,
+    project_id: &str,
+    from_layer_index: u32,
+    to_layer_index: u32,
+    stitches_data: &[u8],
+  ) -> Result<(), Error> {
+    self
+      .move_stitches_to_layer_impl(project_id, from_layer_index, to_layer_index, stitches_data)
+      .await
+  }
+
   /// Removes all stitches (Full, Petite, Half, Quarter) at the given raw pattern coordinates.
   #[wasm_bindgen(js_name = "removeStitchAt")]
   pub async fn remove_stitch_at(&self, project_id: &str, layer_index: u32, x: f32, y: f32) -> Result<(), Error> {
     self.remove_stitch_at_impl(project_id, layer_index, x, y).await
   }
 
+  /// Fills the connected region at the given raw pattern coordinates with full stitches of the given palette item.
+  #[wasm_bindgen(js_name = "fillStitches")]
+  pub async fn fill_stitches(
+    &self,
+    project_id: &str,
+    layer_index: u32,
+    x: f32,
+    y: f32,
+    palindex: u32,
+  ) -> Result<(), Error> {
+    self.fill_stitches_impl(project_id, layer_index, x, y, palindex).await
+  }
+
+  /// Places full stitches along a rectangle or an ellipse inscribed into the box between two corner cells.
+  #[wasm_bindgen(js_name = "addStitchShape")]
+  pub async fn add_stitch_shape(&self, project_id: &str, layer_index: u32, shape_data: &[u8]) -> Result<(), Error> {
+    self.add_stitch_shape_impl(project_id, layer_index, shape_data).await
+  }
+
+  /// Sets or clears the selection in the pattern with the given ID.
+  #[wasm_bindgen(js_name = "setSelection")]
+  pub fn set_selection(&self, project_id: &str, selection_data: &[u8]) -> Result<(), Error> {
+    self.set_selection_impl(project_id, selection_data)
+  }
+
+  /// Enables or disables the mirror drawing mode in the pattern with the given ID.
+  #[wasm_bindgen(js_name = "setSymmetry")]
+  pub fn set_symmetry(&self, project_id: &str, symmetry_data: &[u8]) -> Result<(), Error> {
+    self.set_symmetry_impl(project_id, symmetry_data)
+  }
+
+  /// Copies the selected stitches of the pattern with the given ID to the clipboard.
+  #[wasm_bindgen(js_name = "copySelection")]
+  pub fn copy_selection(&self, project_id: &str) -> Result<(), Error> {
+    self.copy_selection_impl(project_id)
+  }
+
+  /// Copies the selected stitches of the pattern with the given ID to the clipboard and removes them from the pattern.
+  #[wasm_bindgen(js_name = "cutSelection")]
+  pub async fn cut_selection(&self, project_id: &str) -> Result<(), Error> {
+    self.cut_selection_impl(project_id).await
+  }
+
+  /// Moves, mirrors or rotates the selected stitches of the pattern with the given ID.
+  #[wasm_bindgen(js_name = "transformSelection")]
+  pub async fn transform_selection(&self, project_id: &str, transform_data: &[u8]) -> Result<(), Error> {
+    self.transform_selection_impl(project_id, transform_data).await
+  }
+
+  /// Scales the selected stitches of the pattern with the given ID by the given factor.
+  #[wasm_bindgen(js_name = "scaleSelection")]
+  pub async fn scale_selection(&self, project_id: &str, factor: f32) -> Result<(), Error> {
+    self.scale_selection_impl(project_id, factor).await
+  }
+
+  /// Scales the whole pattern with the given ID by the given factor, resizing its fabric to fit.
+  #[wasm_bindgen(js_name = "scalePattern")]
+  pub async fn scale_pattern(&self, project_id: &str, factor: f32) -> Result<(), Error> {
+    self.scale_pattern_impl(project_id, factor).await
+  }
+
+  /// Moves the selected stitches of the pattern with the given ID to the given layer.
+  #[wasm_bindgen(js_name = "moveSelectionToLayer")]
+  pub async fn move_selection_to_layer(&self, project_id: &str, layer_index: u32) -> Result<(), Error> {
+    self.move_selection_to_layer_impl(project_id, layer_index).await
+  }
+
+  /// Pastes the clipboard contents into the pattern layer with the top-left corner at the given cell.
+  #[wasm_bindgen(js_name = "pasteClipboard")]
+  pub async fn paste_clipboard(&self, project_id: &str, layer_index: u32, x: u16, y: u16) -> Result<(), Error> {
+    self.paste_clipboard_impl(project_id, layer_index, x, y).await
+  }
+
   /// Updates fabric properties.
   #[wasm_bindgen(js_name = "updateFabric")]
   pub async fn update_fabric(&self, project_id: &str, fabric_data: &[u8]) -> Result<(), Error> {
@@ -220,12 +356,30 @@ impl EditorWrapper {
     self.set_palette_item_symbol_impl(project_id, symbol_data).await
   }
 
+  /// Replaces a palette item with another one on every stitch within the given scope.
+  #[wasm_bindgen(js_name = "replacePaletteColor")]
+  pub async fn replace_palette_color(&self, project_id: &str, replace_data: &[u8]) -> Result<(), Error> {
+    self.replace_palette_color_impl(project_id, replace_data).await
+  }
+
+  /// Merges palette items into the target one, reassigning their stitches.
+  #[wasm_bindgen(js_name = "mergePaletteItems")]
+  pub async fn merge_palette_items(&self, project_id: &str, palindexes: Vec<u32>, target: u32) -> Result<(), Error> {
+    self.merge_palette_items_impl(project_id, palindexes, target).await
+  }
+
   /// Updates pattern info.
   #[wasm_bindgen(js_name = "updatePatternInfo")]
   pub async fn update_pattern_info(&self, project_id: &str, info_data: &[u8]) -> Result<(), Error> {
     self.update_pattern_info_impl(project_id, info_data).await
   }
 
+  /// Resizes the pattern, keeping the given anchor point in place.
+  #[wasm_bindgen(js_name = "resizePattern")]
+  pub async fn resize_pattern(&self, project_id: &str, width: u16, height: u16, anchor: &str) -> Result<(), Error> {
+    self.resize_pattern_impl(project_id, width, height, anchor).await
+  }
+
   /// Updates display settings.
   #[wasm_bindgen(js_name = "updateDisplaySettings")]
   pub async fn update_display_settings(&self, project_id: &str, settings_data: &[u8]) -> Result<(), Error> {
@@ -263,12 +417,86 @@ impl EditorWrapper {
       .await
   }
 
+  /// Locks or unlocks a layer for drawing.
+  #[wasm_bindgen(js_name = "updateLayerLocked")]
+  pub async fn update_layer_locked(&self, project_id: &str, layer_index: u32, locked: bool) -> Result<(), Error> {
+    self.update_layer_locked_impl(project_id, layer_index, locked).await
+  }
+
+  /// Updates layer opacity.
+  #[wasm_bindgen(js_name = "updateLayerOpacity")]
+  pub async fn update_layer_opacity(&self, project_id: &str, layer_index: u32, opacity: f32) -> Result<(), Error> {
+    self.update_layer_opacity_impl(project_id, layer_index, opacity).await
+  }
+
+  /// Updates how a layer is composed with the layers below it when they are flattened.
+  #[wasm_bindgen(js_name = "updateLayerBlendMode")]
+  pub async fn update_layer_blend_mode(
+    &self,
+    project_id: &str,
+    layer_index: u32,
+    blend_mode: &str,
+  ) -> Result<(), Error> {
+    self
+      .update_layer_blend_mode_impl(project_id, layer_index, blend_mode)
+      .await
+  }
+
+  /// Moves a layer into the given group, or out of any group if it is not specified.
+  #[wasm_bindgen(js_name = "updateLayerGroup")]
+  pub async fn update_layer_group(&self, project_id: &str, layer_index: u32, group: Option<u32>) -> Result<(), Error> {
+    self.update_layer_group_impl(project_id, layer_index, group).await
+  }
+
+  /// Adds a new layer group, optionally nested into another one.
+  #[wasm_bindgen(js_name = "addLayerGroup")]
+  pub async fn add_layer_group(&self, project_id: &str, name: String, parent: Option<u32>) -> Result<(), Error> {
+    self.add_layer_group_impl(project_id, name, parent).await
+  }
+
+  /// Removes a layer group, moving its layers and groups to its parent group.
+  #[wasm_bindgen(js_name = "removeLayerGroup")]
+  pub async fn remove_layer_group(&self, project_id: &str, group_index: u32) -> Result<(), Error> {
+    self.remove_layer_group_impl(project_id, group_index).await
+  }
+
+  /// Updates the name, visibility, or parent of a layer group.
+  #[wasm_bindgen(js_name = "updateLayerGroupProperties")]
+  pub async fn update_layer_group_properties(
+    &self,
+    project_id: &str,
+    group_index: u32,
+    group_data: &[u8],
+  ) -> Result<(), Error> {
+    self
+      .update_layer_group_properties_impl(project_id, group_index, group_data)
+      .await
+  }
+
   /// Moves a layer from `old_position` to `new_position`.
   #[wasm_bindgen(js_name = "moveLayer")]
   pub async fn move_layer(&self, project_id: &str, old_position: u32, new_position: u32) -> Result<(), Error> {
     self.move_layer_impl(project_id, old_position, new_position).await
   }
 
+  /// Adds a copy of the layer at the given index right above it.
+  #[wasm_bindgen(js_name = "duplicateLayer")]
+  pub async fn duplicate_layer(&self, project_id: &str, layer_index: u32) -> Result<(), Error> {
+    self.duplicate_layer_impl(project_id, layer_index).await
+  }
+
+  /// Merges the layer at the given index into the layer right below it.
+  #[wasm_bindgen(js_name = "mergeLayerDown")]
+  pub async fn merge_layer_down(&self, project_id: &str, layer_index: u32) -> Result<(), Error> {
+    self.merge_layer_down_impl(project_id, layer_index).await
+  }
+
+  /// Merges all visible layers into a single one.
+  #[wasm_bindgen(js_name = "flattenLayers")]
+  pub async fn flatten_layers(&self, project_id: &str) -> Result<(), Error> {
+    self.flatten_layers_impl(project_id).await
+  }
+
   /// Updates PDF export options.
   #[wasm_bindgen(js_name = "updatePdfExportOptions")]
   pub async fn update_pdf_export_options(&self, project_id: &str, options_data: &[u8]) -> Result<(), Error> {
@@ -305,6 +533,29 @@ impl EditorWrapper {
     self.redo_impl(project_id, single).await
   }
 
+  /// Returns the borsh-serialized history entries of the pattern with the given ID, from the oldest to the newest.
+  pub fn history(&self, project_id: &str) -> Result<Vec<u8>, Error> {
+    self.history_impl(project_id)
+  }
+
+  /// Undoes or redoes edits in the pattern with the given ID until exactly `position` history entries are applied.
+  #[wasm_bindgen(js_name = "jumpTo")]
+  pub async fn jump_to(&self, project_id: &str, position: u32) -> Result<(), Error> {
+    self.jump_to_impl(project_id, position).await
+  }
+
+  /// Returns the borsh-serialized history branches forking from the current one in the pattern with the given ID.
+  #[wasm_bindgen(js_name = "historyBranches")]
+  pub fn history_branches(&self, project_id: &str) -> Result<Vec<u8>, Error> {
+    self.history_branches_impl(project_id)
+  }
+
+  /// Switches the history of the pattern with the given ID to another branch and redoes all of its edits.
+  #[wasm_bindgen(js_name = "switchHistoryBranch")]
+  pub async fn switch_history_branch(&self, project_id: &str, index: u32) -> Result<(), Error> {
+    self.switch_history_branch_impl(project_id, index).await
+  }
+
   /// Starts a new transaction in the pattern with the given ID.
   #[wasm_bindgen(js_name = "startTransaction")]
   pub async fn start_transaction(&self, project_id: &str) -> Result<(), Error> {
@@ -354,6 +605,19 @@ impl EditorWrapper {
     emit_events(&self.callback, events)
   }
 
+  /// Dispatches the actions against the editor as a single transaction, journaling each of them.
+  async fn dispatch_transaction(&self, project_id: &str, actions: Vec<EditorAction>) -> Result<(), Error> {
+    let id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self.start_transaction_impl(project_id).await?;
+    for action in actions {
+      if let Err(e) = self.dispatch(id, action).await {
+        self.end_transaction_impl(project_id).await?;
+        return Err(e);
+      }
+    }
+    self.end_transaction_impl(project_id).await
+  }
+
   #[tracing::instrument(name = "EditorWrapper::set_auto_save_interval", level = "debug", skip(self))]
   fn set_auto_save_interval_impl(&self, millis: u32) {
     *self.auto_save_interval.borrow_mut() = if millis == 0 {
@@ -505,9 +769,11 @@ impl EditorWrapper {
       editor.checkpoint(&id)
     })?;
 
+    // Replaying the journal rebuilds the undo and redo stacks along with the pattern state.
     let actions = self.persistence.load_journal_entries(id).await?;
-    for action_bytes in actions {
-      let op: JournalEntry = borsh::from_slice(&action_bytes)?;
+    for record in actions {
+      let op: JournalEntry = borsh::from_slice(&record.action)?;
+      REPLAY_TIMESTAMP.set(record.timestamp);
       match op {
         JournalEntry::Action(action) => {
           if let Err(e) = self.run(|editor| editor.dispatch(&id, action)) {
@@ -549,6 +815,27 @@ impl EditorWrapper {
             tracing::warn!("Failed to replay journal checkpoint: {e}");
           }
         }
+        JournalEntry::JumpTo(position) => {
+          if let Err(e) = self.run(|editor| editor.jump_to(&id, position as usize)) {
+            tracing::warn!("Failed to replay journal jump_to: {e}");
+          }
+        }
+        JournalEntry::SwitchBranch(index) => {
+          if let Err(e) = self.run(|editor| editor.switch_history_branch(&id, index as usize)) {
+            tracing::warn!("Failed to replay journal switch_branch: {e}");
+          }
+        }
+      }
+    }
+    REPLAY_TIMESTAMP.set(None);
+
+    // A transaction interrupted by a crash or reload would never end, leaving its actions out of the undo stack
+    // and absorbing all the following ones. Close it and record that, so the next replay closes it at the same point.
+    if self.run(|editor| editor.has_active_transaction(&id))? {
+      self.run(|editor| editor.end_transaction(&id))?;
+      let action_bytes = borsh::to_vec(&JournalEntry::EndTransaction)?;
+      if let Err(e) = self.persistence.append_journal_entry(id, action_bytes).await {
+        tracing::warn!("Failed to journal the end of an interrupted transaction: {e}");
       }
     }
 
@@ -668,20 +955,52 @@ impl EditorWrapper {
     let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
     let stitch: Stitch = borsh::from_slice(stitch_data)?;
 
-    if self.run(|editor| {
-      editor
-        .get_pattern(&project_id)
-        .is_some_and(|embproj| embproj.pattern.contains_stitch(layer_index, &stitch))
-    }) {
-      return Ok(());
-    }
+    // In the mirror drawing mode, the stitch and its counterparts are added as a single action.
+    let stitches = self.run(|editor| {
+      let Some(embproj) = editor.get_pattern(&project_id) else {
+        return vec![stitch];
+      };
+      let stitches = match editor.symmetry(&project_id) {
+        Some(symmetry) => symmetry.mirror(stitch, &embproj.pattern),
+        None => vec![stitch],
+      };
+      stitches
+        .into_iter()
+        .filter(|stitch| !embproj.pattern.contains_stitch(layer_index, stitch))
+        .collect()
+    });
+
+    let action = match stitches.as_slice() {
+      [] => return Ok(()),
+      [stitch] => StitchAction::Add {
+        layer_index,
+        stitch: *stitch,
+        conflicts: None,
+      },
+      _ => StitchAction::AddMany {
+        layer_index,
+        stitches,
+        conflicts: None,
+      },
+    };
+    self.dispatch(project_id, EditorAction::Stitch(action)).await
+  }
 
+  #[tracing::instrument(
+    name = "EditorWrapper::add_stitches",
+    level = "debug",
+    skip(self, stitches_data),
+    err
+  )]
+  async fn add_stitches_impl(&self, project_id: &str, layer_index: u32, stitches_data: &[u8]) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let stitches: Vec<Stitch> = borsh::from_slice(stitches_data)?;
     self
       .dispatch(
         project_id,
-        EditorAction::Stitch(StitchAction::Add {
+        EditorAction::Stitch(StitchAction::AddMany {
           layer_index,
-          stitch,
+          stitches,
           conflicts: None,
         }),
       )
@@ -704,6 +1023,74 @@ impl EditorWrapper {
       .await
   }
 
+  #[tracing::instrument(
+    name = "EditorWrapper::remove_stitches",
+    level = "debug",
+    skip(self, stitches_data),
+    err
+  )]
+  async fn remove_stitches_impl(&self, project_id: &str, layer_index: u32, stitches_data: &[u8]) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let target_stitches: Vec<Stitch> = borsh::from_slice(stitches_data)?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Stitch(StitchAction::RemoveMany {
+          layer_index,
+          target_stitches,
+          actual_stitches: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(
+    name = "EditorWrapper::remove_stitches_by_kind",
+    level = "debug",
+    skip(self, kinds_data),
+    err
+  )]
+  async fn remove_stitches_by_kind_impl(
+    &self,
+    project_id: &str,
+    layer_index: Option<u32>,
+    kinds_data: &[u8],
+  ) -> Result<(), Error> {
+    let id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let kinds: StitchKinds = borsh::from_slice(kinds_data)?;
+    let actions = self.run(|editor| editor.remove_stitches_by_kind_actions(&id, layer_index, kinds))?;
+    self.dispatch_transaction(project_id, actions).await
+  }
+
+  #[tracing::instrument(
+    name = "EditorWrapper::move_stitches_to_layer",
+    level = "debug",
+    skip(self, stitches_data),
+    err
+  )]
+  async fn move_stitches_to_layer_impl(
+    &self,
+    project_id: &str,
+    from_layer_index: u32,
+    to_layer_index: u32,
+    stitches_data: &[u8],
+  ) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let stitches: Vec<Stitch> = borsh::from_slice(stitches_data)?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Stitch(StitchAction::MoveToLayer {
+          from_layer_index,
+          to_layer_index,
+          stitches,
+          moved_stitches: None,
+          conflicts: None,
+        }),
+      )
+      .await
+  }
+
   #[tracing::instrument(name = "EditorWrapper::remove_stitch_at", level = "debug", skip(self), err)]
   async fn remove_stitch_at_impl(&self, project_id: &str, layer_index: u32, x: f32, y: f32) -> Result<(), Error> {
     let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
@@ -720,6 +1107,197 @@ impl EditorWrapper {
       .await
   }
 
+  #[tracing::instrument(name = "EditorWrapper::fill_stitches", level = "debug", skip(self), err)]
+  async fn fill_stitches_impl(
+    &self,
+    project_id: &str,
+    layer_index: u32,
+    x: f32,
+    y: f32,
+    palindex: u32,
+  ) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Stitch(StitchAction::Fill {
+          layer_index,
+          x,
+          y,
+          palindex,
+          filled_stitches: None,
+          replaced_stitches: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(
+    name = "EditorWrapper::add_stitch_shape",
+    level = "debug",
+    skip(self, shape_data),
+    err
+  )]
+  async fn add_stitch_shape_impl(&self, project_id: &str, layer_index: u32, shape_data: &[u8]) -> Result<(), Error> {
+    #[derive(borsh::BorshDeserialize)]
+    struct AddShapePayload {
+      shape: StitchShape,
+      start: (u16, u16),
+      end: (u16, u16),
+      filled: bool,
+      palindex: u32,
+    }
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let AddShapePayload {
+      shape,
+      start,
+      end,
+      filled,
+      palindex,
+    } = borsh::from_slice(shape_data)?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Stitch(StitchAction::AddShape {
+          layer_index,
+          shape,
+          start,
+          end,
+          filled,
+          palindex,
+          added_stitches: None,
+          conflicts: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(
+    name = "EditorWrapper::set_selection",
+    level = "debug",
+    skip(self, selection_data),
+    err
+  )]
+  fn set_selection_impl(&self, project_id: &str, selection_data: &[u8]) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let selection: Option<Selection> = borsh::from_slice(selection_data)?;
+    Ok(self.run(|editor| editor.set_selection(&project_id, selection))?)
+  }
+
+  #[tracing::instrument(
+    name = "EditorWrapper::set_symmetry",
+    level = "debug",
+    skip(self, symmetry_data),
+    err
+  )]
+  fn set_symmetry_impl(&self, project_id: &str, symmetry_data: &[u8]) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let symmetry: Option<Symmetry> = borsh::from_slice(symmetry_data)?;
+    Ok(self.run(|editor| editor.set_symmetry(&project_id, symmetry))?)
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::copy_selection", level = "debug", skip(self), err)]
+  fn copy_selection_impl(&self, project_id: &str) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    Ok(self.run(|editor| editor.copy(&project_id))?)
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::cut_selection", level = "debug", skip(self), err)]
+  async fn cut_selection_impl(&self, project_id: &str) -> Result<(), Error> {
+    let id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let actions = self.run(|editor| {
+      editor.copy(&id)?;
+      editor.remove_selection_actions(&id)
+    })?;
+    self.dispatch_transaction(project_id, actions).await
+  }
+
+  #[tracing::instrument(
+    name = "EditorWrapper::transform_selection",
+    level = "debug",
+    skip(self, transform_data),
+    err
+  )]
+  async fn transform_selection_impl(&self, project_id: &str, transform_data: &[u8]) -> Result<(), Error> {
+    let id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let transform: StitchTransform = borsh::from_slice(transform_data)?;
+    let actions = self.run(|editor| editor.transform_selection_actions(&id, transform))?;
+    self.dispatch_transaction(project_id, actions).await?;
+
+    // Let the selection follow the transformed stitches.
+    self.run(|editor| {
+      let selection = editor.selection(&id).cloned().map(|selection| Selection {
+        area: selection.area.transformed(transform),
+        ..selection
+      });
+      editor.set_selection(&id, selection)
+    })?;
+    Ok(())
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::move_selection_to_layer", level = "debug", skip(self), err)]
+  async fn move_selection_to_layer_impl(&self, project_id: &str, layer_index: u32) -> Result<(), Error> {
+    let id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let actions = self.run(|editor| editor.move_selection_to_layer_actions(&id, layer_index))?;
+    self.dispatch_transaction(project_id, actions).await?;
+
+    // Let the selection follow the moved stitches.
+    self.run(|editor| {
+      let selection = editor.selection(&id).cloned().map(|selection| Selection {
+        layer_index: selection.layer_index.map(|_| layer_index),
+        ..selection
+      });
+      editor.set_selection(&id, selection)
+    })?;
+    Ok(())
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::scale_selection", level = "debug", skip(self), err)]
+  async fn scale_selection_impl(&self, project_id: &str, factor: f32) -> Result<(), Error> {
+    let id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let actions = self.run(|editor| editor.scale_selection_actions(&id, factor))?;
+    self.dispatch_transaction(project_id, actions).await?;
+
+    // Let the selection follow the scaled stitches.
+    self.run(|editor| {
+      let selection = editor.selection(&id).cloned().map(|selection| Selection {
+        area: selection.area.scaled(factor),
+        ..selection
+      });
+      editor.set_selection(&id, selection)
+    })?;
+    Ok(())
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::scale_pattern", level = "debug", skip(self), err)]
+  async fn scale_pattern_impl(&self, project_id: &str, factor: f32) -> Result<(), Error> {
+    let id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let actions = self.run(|editor| editor.scale_pattern_actions(&id, factor))?;
+    self.dispatch_transaction(project_id, actions).await
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::paste_clipboard", level = "debug", skip(self), err)]
+  async fn paste_clipboard_impl(&self, project_id: &str, layer_index: u32, x: u16, y: u16) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let clipboard = self
+      .run(|editor| editor.clipboard().cloned())
+      .ok_or(embroiderly_editor::Error::ClipboardEmpty)?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Stitch(StitchAction::Paste {
+          layer_index,
+          x,
+          y,
+          clipboard,
+          pasted_stitches: None,
+          conflicts: None,
+          added_items: None,
+        }),
+      )
+      .await
+  }
+
   #[tracing::instrument(name = "EditorWrapper::update_fabric", level = "debug", skip(self, fabric_data), err)]
   async fn update_fabric_impl(&self, project_id: &str, fabric_data: &[u8]) -> Result<(), Error> {
     let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
@@ -865,6 +1443,51 @@ impl EditorWrapper {
       .await
   }
 
+  #[tracing::instrument(
+    name = "EditorWrapper::replace_palette_color",
+    level = "debug",
+    skip(self, replace_data),
+    err
+  )]
+  async fn replace_palette_color_impl(&self, project_id: &str, replace_data: &[u8]) -> Result<(), Error> {
+    #[derive(borsh::BorshDeserialize)]
+    struct ReplaceColorPayload {
+      from: u32,
+      to: u32,
+      scope: ReplaceColorScope,
+    }
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let ReplaceColorPayload { from, to, scope } = borsh::from_slice(replace_data)?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Palette(PaletteAction::ReplaceColor {
+          from,
+          to,
+          scope,
+          replaced_stitches: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::merge_palette_items", level = "debug", skip(self), err)]
+  async fn merge_palette_items_impl(&self, project_id: &str, palindexes: Vec<u32>, target: u32) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Palette(PaletteAction::MergeItems {
+          palindexes,
+          target,
+          saved_palitems: None,
+          old_positions: None,
+          merged_stitches: None,
+        }),
+      )
+      .await
+  }
+
   #[tracing::instrument(
     name = "EditorWrapper::update_pattern_info",
     level = "debug",
@@ -882,6 +1505,39 @@ impl EditorWrapper {
       .await
   }
 
+  #[tracing::instrument(name = "EditorWrapper::resize_pattern", level = "debug", skip(self), err)]
+  async fn resize_pattern_impl(&self, project_id: &str, width: u16, height: u16, anchor: &str) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let anchor = match anchor {
+      "TopLeft" => ResizeAnchor::TopLeft,
+      "Top" => ResizeAnchor::Top,
+      "TopRight" => ResizeAnchor::TopRight,
+      "Left" => ResizeAnchor::Left,
+      "Center" => ResizeAnchor::Center,
+      "Right" => ResizeAnchor::Right,
+      "BottomLeft" => ResizeAnchor::BottomLeft,
+      "Bottom" => ResizeAnchor::Bottom,
+      "BottomRight" => ResizeAnchor::BottomRight,
+      _ => {
+        return Err(
+          Error::new(ErrorKind::Unexpected).with_source(anyhow::anyhow!("Unknown ResizeAnchor variant: {anchor}")),
+        );
+      }
+    };
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Pattern(PatternAction::Resize {
+          width,
+          height,
+          anchor,
+          old_fabric: None,
+          removed_stitches: None,
+        }),
+      )
+      .await
+  }
+
   #[tracing::instrument(
     name = "EditorWrapper::update_display_settings",
     level = "debug",
@@ -965,6 +1621,138 @@ impl EditorWrapper {
       .await
   }
 
+  #[tracing::instrument(name = "EditorWrapper::update_layer_locked", level = "debug", skip(self), err)]
+  async fn update_layer_locked_impl(&self, project_id: &str, layer_index: u32, locked: bool) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::UpdateLocked {
+          layer_index,
+          locked,
+          old_locked: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::update_layer_opacity", level = "debug", skip(self), err)]
+  async fn update_layer_opacity_impl(&self, project_id: &str, layer_index: u32, opacity: f32) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::UpdateOpacity {
+          layer_index,
+          opacity,
+          old_opacity: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::update_layer_blend_mode", level = "debug", skip(self), err)]
+  async fn update_layer_blend_mode_impl(
+    &self,
+    project_id: &str,
+    layer_index: u32,
+    blend_mode: &str,
+  ) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let blend_mode = match blend_mode {
+      "Overwrite" => LayerBlendMode::Overwrite,
+      "FillEmpty" => LayerBlendMode::FillEmpty,
+      "Mask" => LayerBlendMode::Mask,
+      _ => {
+        return Err(
+          Error::new(ErrorKind::Unexpected)
+            .with_source(anyhow::anyhow!("Unknown LayerBlendMode variant: {blend_mode}")),
+        );
+      }
+    };
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::UpdateBlendMode {
+          layer_index,
+          blend_mode,
+          old_blend_mode: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::update_layer_group", level = "debug", skip(self), err)]
+  async fn update_layer_group_impl(&self, project_id: &str, layer_index: u32, group: Option<u32>) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::UpdateGroup {
+          layer_index,
+          group,
+          old_group: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::add_layer_group", level = "debug", skip(self), err)]
+  async fn add_layer_group_impl(&self, project_id: &str, name: String, parent: Option<u32>) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::AddGroup {
+          name,
+          parent,
+          added_index: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::remove_layer_group", level = "debug", skip(self), err)]
+  async fn remove_layer_group_impl(&self, project_id: &str, group_index: u32) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::RemoveGroup {
+          group_index,
+          removed_group: None,
+          members: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(
+    name = "EditorWrapper::update_layer_group_properties",
+    level = "debug",
+    skip(self, group_data),
+    err
+  )]
+  async fn update_layer_group_properties_impl(
+    &self,
+    project_id: &str,
+    group_index: u32,
+    group_data: &[u8],
+  ) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let group: LayerGroup = borsh::from_slice(group_data)?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::UpdateGroupProperties {
+          group_index,
+          group,
+          old_group: None,
+        }),
+      )
+      .await
+  }
+
   #[tracing::instrument(name = "EditorWrapper::move_layer", level = "debug", skip(self), err)]
   async fn move_layer_impl(&self, project_id: &str, old_position: u32, new_position: u32) -> Result<(), Error> {
     let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
@@ -980,6 +1768,49 @@ impl EditorWrapper {
       .await
   }
 
+  #[tracing::instrument(name = "EditorWrapper::duplicate_layer", level = "debug", skip(self), err)]
+  async fn duplicate_layer_impl(&self, project_id: &str, layer_index: u32) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::Duplicate {
+          layer_index,
+          added_index: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::merge_layer_down", level = "debug", skip(self), err)]
+  async fn merge_layer_down_impl(&self, project_id: &str, layer_index: u32) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::MergeDown {
+          layer_index,
+          target_index: None,
+          merged_layer: None,
+          added_stitches: None,
+          conflicts: None,
+          old_positions: None,
+        }),
+      )
+      .await
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::flatten_layers", level = "debug", skip(self), err)]
+  async fn flatten_layers_impl(&self, project_id: &str) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    self
+      .dispatch(
+        project_id,
+        EditorAction::Layer(LayerAction::Flatten { old_layers: None }),
+      )
+      .await
+  }
+
   #[tracing::instrument(
     name = "EditorWrapper::update_pdf_export_options",
     level = "debug",
@@ -1120,6 +1951,67 @@ impl EditorWrapper {
     emit_events(&self.callback, events)
   }
 
+  #[tracing::instrument(name = "EditorWrapper::history", level = "debug", skip(self), err)]
+  fn history_impl(&self, project_id: &str) -> Result<Vec<u8>, Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let items = self.run(|editor| editor.history(&project_id))?;
+    Ok(borsh::to_vec(&items)?)
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::jump_to", level = "debug", skip(self), err)]
+  async fn jump_to_impl(&self, project_id: &str, position: u32) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+
+    let old_position = self.run(|editor| {
+      let items = editor.history(&project_id)?;
+      Ok::<_, embroiderly_editor::Error>(items.iter().filter(|item| !item.undone).count())
+    })?;
+    let mut events = self.run(|editor| editor.jump_to(&project_id, position as usize))?;
+
+    if !events.is_empty() {
+      let action_bytes = borsh::to_vec(&JournalEntry::JumpTo(position))?;
+      if let Err(e) = self.persistence.append_journal_entry(project_id, action_bytes).await {
+        if let Err(jump_err) = self.run(|editor| editor.jump_to(&project_id, old_position)) {
+          tracing::error!("Failed to roll back jump after journal failure: {jump_err}");
+        }
+        return Err(e);
+      }
+    }
+
+    if !self.run(|editor| editor.has_unsaved_changes(&project_id))? {
+      events.push(EditorEvent::PatternCheckpoint(project_id));
+    }
+
+    emit_events(&self.callback, events)
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::history_branches", level = "debug", skip(self), err)]
+  fn history_branches_impl(&self, project_id: &str) -> Result<Vec<u8>, Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+    let branches = self.run(|editor| editor.history_branches(&project_id))?;
+    Ok(borsh::to_vec(&branches)?)
+  }
+
+  #[tracing::instrument(name = "EditorWrapper::switch_history_branch", level = "debug", skip(self), err)]
+  async fn switch_history_branch_impl(&self, project_id: &str, index: u32) -> Result<(), Error> {
+    let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
+
+    // Switching branches is hard to roll back, so the switch is journaled first and only performed once recorded.
+    let branches = self.run(|editor| editor.history_branches(&project_id))?;
+    if index as usize >= branches.len() {
+      return Err(embroiderly_editor::Error::HistoryBranchNotFound(index as usize).into());
+    }
+    let action_bytes = borsh::to_vec(&JournalEntry::SwitchBranch(index))?;
+    self.persistence.append_journal_entry(project_id, action_bytes).await?;
+
+    let mut events = self.run(|editor| editor.switch_history_branch(&project_id, index as usize))?;
+    if !self.run(|editor| editor.has_unsaved_changes(&project_id))? {
+      events.push(EditorEvent::PatternCheckpoint(project_id));
+    }
+
+    emit_events(&self.callback, events)
+  }
+
   #[tracing::instrument(name = "EditorWrapper::start_transaction", level = "debug", skip(self), err)]
   async fn start_transaction_impl(&self, project_id: &str) -> Result<(), Error> {
     let project_id = project_id.parse::<EmbroiderlyProjectId>()?;
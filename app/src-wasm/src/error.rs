@@ -26,6 +26,12 @@ pub enum ErrorKind {
 
   // Layer errors.
   CannotRemoveLastLayer,
+  CannotMergeDownBottomLayer,
+  LayerLocked,
+
+  // Selection errors.
+  NothingSelected,
+  ClipboardEmpty,
 
   // File handle errors.
   NoFileHandle,
@@ -49,6 +55,11 @@ impl ErrorKind {
       Self::SymbolFontNotFound(name) => format!("Symbol font not found: {name}."),
 
       Self::CannotRemoveLastLayer => String::from("Cannot remove the last layer."),
+      Self::CannotMergeDownBottomLayer => String::from("Cannot merge down the bottom layer."),
+      Self::LayerLocked => String::from("Layer is locked."),
+
+      Self::NothingSelected => String::from("Nothing is selected."),
+      Self::ClipboardEmpty => String::from("Clipboard is empty."),
 
       Self::NoFileHandle => String::from("Pattern has no associated file handle."),
 
@@ -152,9 +163,22 @@ impl From<embroiderly_editor::Error> for Error {
     match err {
       embroiderly_editor::Error::PatternNotFound(_) => Self::new(ErrorKind::PatternNotFound),
       embroiderly_editor::Error::CannotRemoveLastLayer => Self::new(ErrorKind::CannotRemoveLastLayer),
-      embroiderly_editor::Error::ActionNotPerformed | embroiderly_editor::Error::StitchNotFound => {
-        Self::new(ErrorKind::Unexpected).with_source(err)
-      }
+      embroiderly_editor::Error::CannotMergeDownBottomLayer => Self::new(ErrorKind::CannotMergeDownBottomLayer),
+      embroiderly_editor::Error::LayerLocked(_) => Self::new(ErrorKind::LayerLocked),
+      embroiderly_editor::Error::NothingSelected => Self::new(ErrorKind::NothingSelected),
+      embroiderly_editor::Error::ClipboardEmpty => Self::new(ErrorKind::ClipboardEmpty),
+      embroiderly_editor::Error::ActionNotPerformed
+      | embroiderly_editor::Error::StitchNotFound
+      | embroiderly_editor::Error::PaletteItemNotFound(_)
+      | embroiderly_editor::Error::DuplicatePaletteItem(_)
+      | embroiderly_editor::Error::CannotMergePaletteItemIntoItself(_)
+      | embroiderly_editor::Error::InvalidPatternSize(..)
+      | embroiderly_editor::Error::InvalidScaleFactor(_)
+      | embroiderly_editor::Error::ScaleFactorTooLarge(_)
+      | embroiderly_editor::Error::LayerGroupNotFound(_)
+      | embroiderly_editor::Error::InvalidLayerOpacity(_)
+      | embroiderly_editor::Error::CyclicLayerGroup(_)
+      | embroiderly_editor::Error::HistoryBranchNotFound(_) => Self::new(ErrorKind::Unexpected).with_source(err),
     }
   }
 }
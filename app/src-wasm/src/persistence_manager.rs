@@ -1,14 +1,14 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 
-use embroiderly_pattern::EmbroiderlyProjectId;
+use embroiderly_pattern::{EmbroiderlyProject, EmbroiderlyProjectId};
 use embroiderly_web::{idb, opfs};
 use wasm_bindgen::prelude::*;
 
 use crate::error::Error;
 
 const DB_NAME: &str = "embroiderly";
-const DB_VERSION: u32 = 1;
+const DB_VERSION: u32 = 2;
 
 const PROJECT_STORE: &str = "projects";
 const JOURNAL_STORE: &str = "journal";
@@ -51,9 +51,19 @@ impl TryFrom<JsValue> for ProjectEntry {
   }
 }
 
+/// A journal action loaded for replay.
+pub struct JournalRecord {
+  pub action: Vec<u8>,
+  /// The time the action was recorded at, in milliseconds since the Unix epoch, if known.
+  pub timestamp: Option<u64>,
+}
+
 struct JournalEntry {
   project_id: String,
   action: Vec<u8>,
+  /// The time the entry was recorded at, in milliseconds since the Unix epoch.
+  /// Missing in entries written by older versions.
+  timestamp: Option<f64>,
 }
 
 impl From<JournalEntry> for JsValue {
@@ -62,6 +72,7 @@ impl From<JournalEntry> for JsValue {
     let set = |k: &str, v: Self| js_sys::Reflect::set(&obj, &k.into(), &v).unwrap();
     set("project_id", entry.project_id.into());
     set("action", js_sys::Uint8Array::from(entry.action.as_slice()).into());
+    set("timestamp", entry.timestamp.map_or(Self::undefined(), Self::from_f64));
     obj.into()
   }
 }
@@ -77,7 +88,12 @@ impl TryFrom<JsValue> for JournalEntry {
       .and_then(|v| v.dyn_into::<js_sys::Uint8Array>().ok())
       .map(|arr| arr.to_vec())
       .ok_or_else(|| anyhow::anyhow!("journal entry missing action"))?;
-    Ok(Self { project_id, action })
+    let timestamp = get("timestamp").and_then(|v| v.as_f64());
+    Ok(Self {
+      project_id,
+      action,
+      timestamp,
+    })
   }
 }
 
@@ -106,6 +122,8 @@ impl PersistenceManager {
           journal_store
             .build_index(JOURNAL_BY_PROJECT_INDEX, "project_id")
             .create()?;
+        } else if evt.old_version() < 2 {
+          migrate_snapshots_v1(evt.transaction()).await?;
         }
         Ok(())
       })
@@ -269,7 +287,7 @@ impl PersistenceManager {
     Ok(())
   }
 
-  /// Appends a single journal action for the given project ID.
+  /// Appends a single journal action for the given project ID, stamped with the current time.
   #[tracing::instrument(
     name = "PersistenceManager::append_journal_entry",
     level = "debug",
@@ -280,6 +298,7 @@ impl PersistenceManager {
     let entry = JsValue::from(JournalEntry {
       project_id: project_id.to_string(),
       action,
+      timestamp: Some(js_sys::Date::now()),
     });
     self
       .db
@@ -296,7 +315,7 @@ impl PersistenceManager {
 
   /// Loads all journal actions for the given project ID in insertion order.
   #[tracing::instrument(name = "PersistenceManager::load_journal_entries", level = "debug", skip(self), err)]
-  pub async fn load_journal_entries(&self, project_id: EmbroiderlyProjectId) -> Result<Vec<Vec<u8>>, Error> {
+  pub async fn load_journal_entries(&self, project_id: EmbroiderlyProjectId) -> Result<Vec<JournalRecord>, Error> {
     let project_id = JsValue::from_str(&project_id.to_string());
     let rows = self
       .db
@@ -311,7 +330,11 @@ impl PersistenceManager {
 
     let mut actions = Vec::with_capacity(rows.len());
     for row in rows {
-      actions.push(JournalEntry::try_from(row)?.action);
+      let entry = JournalEntry::try_from(row)?;
+      actions.push(JournalRecord {
+        action: entry.action,
+        timestamp: entry.timestamp.map(|timestamp| timestamp as u64),
+      });
     }
 
     Ok(actions)
@@ -341,3 +364,45 @@ impl PersistenceManager {
     Ok(())
   }
 }
+
+/// Re-encodes the project snapshots stored before layers could be locked, grouped, or blended.
+///
+/// The journals are kept as is, since actions are journaled before they are performed
+/// and so never carry a layer in the old layout.
+/// Snapshots that can't be migrated are dropped along with their journals.
+async fn migrate_snapshots_v1(tx: &idb::Transaction<anyhow::Error>) -> idb::Result<(), anyhow::Error> {
+  let projects = tx.object_store(PROJECT_STORE)?;
+  let journal = tx.object_store(JOURNAL_STORE)?;
+  for value in projects.get_all(None).await? {
+    let Ok(mut entry) = ProjectEntry::try_from(value) else {
+      continue;
+    };
+    // Entries saved only to remember the file handle have no snapshot.
+    if entry.data.is_empty() {
+      continue;
+    }
+
+    match EmbroiderlyProject::from_borsh_v1(&entry.data).and_then(|embproj| borsh::to_vec(&embproj)) {
+      Ok(data) => {
+        entry.data = data;
+        projects.put(&JsValue::from(entry)).await?;
+      }
+      Err(e) => {
+        tracing::warn!(
+          "Dropping the snapshot of project {} that can't be migrated: {e}",
+          entry.project_id
+        );
+        let project_id = JsValue::from_str(&entry.project_id);
+        let index = journal.index(JOURNAL_BY_PROJECT_INDEX)?;
+        for key in index
+          .get_all_keys_in(project_id.clone()..=project_id.clone(), None)
+          .await?
+        {
+          journal.delete(&key).await?;
+        }
+        projects.delete(&project_id).await?;
+      }
+    }
+  }
+  Ok(())
+}
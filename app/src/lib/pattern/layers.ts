@@ -75,10 +75,21 @@ export class LayerVisibility {
   }
 }
 
+/** Specifies how a layer is composed with the layers below it when they are flattened. */
+export enum LayerBlendMode {
+  Overwrite = "Overwrite",
+  FillEmpty = "FillEmpty",
+  Mask = "Mask",
+}
+
 export class Layer {
   readonly index: number;
   name: string;
   visible: boolean;
+  locked: boolean;
+  opacity: number;
+  group?: number;
+  blendMode: LayerBlendMode;
 
   fullstitches: FullStitch[];
   fullstitchesVisible: boolean;
@@ -103,6 +114,10 @@ export class Layer {
     this.index = index;
     this.name = data?.name ?? "";
     this.visible = data?.visible ?? true;
+    this.locked = data?.locked ?? false;
+    this.opacity = data?.opacity ?? 1;
+    this.group = data?.group ?? undefined;
+    this.blendMode = data?.blendMode ?? LayerBlendMode.Overwrite;
 
     this.fullstitches = data?.fullstitches?.map((s) => new FullStitch(s)) ?? [];
     this.fullstitchesVisible = data?.fullstitchesVisible ?? true;
@@ -127,6 +142,10 @@ export class Layer {
   static readonly schema = b.struct({
     name: b.string(),
     visible: b.bool(),
+    locked: b.bool(),
+    opacity: b.f32(),
+    group: b.option(b.u32()),
+    blendMode: b.nativeEnum(LayerBlendMode),
 
     fullstitches: b.vec(FullStitch.schema),
     fullstitchesVisible: b.bool(),
@@ -206,26 +225,54 @@ export class Layer {
   }
 }
 
+/** A named group of custom layers, which may be nested into another group. */
+export class LayerGroup {
+  name: string;
+  visible: boolean;
+  parent?: number;
+
+  constructor(data?: Partial<b.infer<typeof LayerGroup.schema>>) {
+    this.name = data?.name ?? "";
+    this.visible = data?.visible ?? true;
+    this.parent = data?.parent ?? undefined;
+  }
+
+  static readonly schema = b.struct({
+    name: b.string(),
+    visible: b.bool(),
+    parent: b.option(b.u32()),
+  });
+
+  static serialize(data: LayerGroup) {
+    return LayerGroup.schema.serialize({ name: data.name, visible: data.visible, parent: data.parent ?? null });
+  }
+}
+
 export class Layers {
   #items: Layer[];
   #positions: number[];
+  #groups: LayerGroup[];
 
   constructor(data?: b.infer<typeof Layers.schema> | Layer[] | Layers) {
     if (data instanceof Layers) {
       this.#items = data.#items;
       this.#positions = data.#positions;
+      this.#groups = data.#groups;
     } else if (Array.isArray(data)) {
       this.#items = data;
       this.#positions = data.map((_, i) => i);
+      this.#groups = [];
     } else {
       this.#items = data?.items.map((item, index) => new Layer(index, item)) ?? [];
       this.#positions = data?.positions ?? [];
+      this.#groups = data?.groups.map((group) => new LayerGroup(group)) ?? [];
     }
   }
 
   static readonly schema = b.struct({
     items: b.vec(Layer.schema),
     positions: b.vec(b.u32()),
+    groups: b.vec(LayerGroup.schema),
   });
 
   // === Access Methods ===
@@ -300,6 +347,145 @@ export class Layers {
 
     return removed!;
   }
+
+  // === Group Methods ===
+
+  /** Layer groups in actual order. */
+  get groups(): readonly LayerGroup[] {
+    return this.#groups;
+  }
+
+  /** Returns a layer group by its actual index. */
+  getGroup(index: number): LayerGroup | undefined {
+    return this.#groups[index];
+  }
+
+  /** Replaces a layer group by its actual index. */
+  setGroup(index: number, group: LayerGroup): void {
+    this.#groups[index] = group;
+  }
+
+  /** Inserts a layer group at a specific actual index. Updates group references accordingly. */
+  insertGroup(index: number, group: LayerGroup): void {
+    this.#shiftGroupReferences((group) => (group >= index ? group + 1 : group));
+    this.#groups.splice(index, 0, group);
+  }
+
+  /** Removes a layer group by its actual index, moving its layers and groups to its parent group. */
+  removeGroup(index: number): LayerGroup {
+    const [removed] = this.#groups.splice(index, 1);
+
+    for (const layer of this.#items) {
+      if (layer.group === index) layer.group = removed!.parent;
+    }
+    for (const group of this.#groups) {
+      if (group.parent === index) group.parent = removed!.parent;
+    }
+    this.#shiftGroupReferences((group) => (group > index ? group - 1 : group));
+
+    return removed!;
+  }
+
+  /** Returns `true` if the layer and all its ancestor groups are visible. */
+  isLayerVisible(index: number): boolean {
+    const layer = this.#items[index];
+    if (!layer?.visible) return false;
+
+    let current = layer.group;
+    // Bound the walk by the number of groups to guard against cyclic references.
+    for (let i = 0; current !== undefined && i <= this.#groups.length; i++) {
+      const group = this.#groups[current];
+      if (!group) return true;
+      if (!group.visible) return false;
+      current = group.parent;
+    }
+    return true;
+  }
+
+  #shiftGroupReferences(shift: (group: number) => number): void {
+    for (const layer of this.#items) {
+      if (layer.group !== undefined) layer.group = shift(layer.group);
+    }
+    for (const group of this.#groups) {
+      if (group.parent !== undefined) group.parent = shift(group.parent);
+    }
+  }
+}
+
+export class AddedLayerGroupData {
+  index: number;
+  group: LayerGroup;
+
+  constructor(data: b.infer<typeof AddedLayerGroupData.schema>) {
+    this.index = data.index;
+    this.group = new LayerGroup(data.group);
+  }
+
+  static readonly schema = b.struct({
+    index: b.u32(),
+    group: LayerGroup.schema,
+  });
+
+  static deserialize(data: Uint8Array) {
+    return new AddedLayerGroupData(AddedLayerGroupData.schema.deserialize(data));
+  }
+}
+
+export class UpdatedLayerGroupData {
+  groupIndex: number;
+  group: LayerGroup;
+
+  constructor(data: b.infer<typeof UpdatedLayerGroupData.schema>) {
+    this.groupIndex = data.groupIndex;
+    this.group = new LayerGroup(data.group);
+  }
+
+  static readonly schema = b.struct({
+    groupIndex: b.u32(),
+    group: LayerGroup.schema,
+  });
+
+  static deserialize(data: Uint8Array) {
+    return new UpdatedLayerGroupData(UpdatedLayerGroupData.schema.deserialize(data));
+  }
+}
+
+export class UpdatedLayerBlendModeData {
+  layerIndex: number;
+  blendMode: LayerBlendMode;
+
+  constructor(data: b.infer<typeof UpdatedLayerBlendModeData.schema>) {
+    this.layerIndex = data.layerIndex;
+    this.blendMode = data.blendMode;
+  }
+
+  static readonly schema = b.struct({
+    layerIndex: b.u32(),
+    blendMode: b.nativeEnum(LayerBlendMode),
+  });
+
+  static deserialize(data: Uint8Array) {
+    return new UpdatedLayerBlendModeData(UpdatedLayerBlendModeData.schema.deserialize(data));
+  }
+}
+
+export class UpdatedLayerGroupMembershipData {
+  layerIndex: number;
+  group?: number;
+
+  constructor(data: b.infer<typeof UpdatedLayerGroupMembershipData.schema>) {
+    this.layerIndex = data.layerIndex;
+    this.group = data.group ?? undefined;
+  }
+
+  static readonly schema = b.struct({
+    layerIndex: b.u32(),
+    group: b.option(b.u32()),
+  });
+
+  static deserialize(data: Uint8Array) {
+    return new UpdatedLayerGroupMembershipData(UpdatedLayerGroupMembershipData.schema.deserialize(data));
+  }
 }
 
 export class AddedLayerData {
@@ -340,6 +526,44 @@ export class RenamedLayerData {
   }
 }
 
+export class UpdatedLayerLockedData {
+  layerIndex: number;
+  locked: boolean;
+
+  constructor(data: b.infer<typeof UpdatedLayerLockedData.schema>) {
+    this.layerIndex = data.layerIndex;
+    this.locked = data.locked;
+  }
+
+  static readonly schema = b.struct({
+    layerIndex: b.u32(),
+    locked: b.bool(),
+  });
+
+  static deserialize(data: Uint8Array) {
+    return new UpdatedLayerLockedData(UpdatedLayerLockedData.schema.deserialize(data));
+  }
+}
+
+export class UpdatedLayerOpacityData {
+  layerIndex: number;
+  opacity: number;
+
+  constructor(data: b.infer<typeof UpdatedLayerOpacityData.schema>) {
+    this.layerIndex = data.layerIndex;
+    this.opacity = data.opacity;
+  }
+
+  static readonly schema = b.struct({
+    layerIndex: b.u32(),
+    opacity: b.f32(),
+  });
+
+  static deserialize(data: Uint8Array) {
+    return new UpdatedLayerOpacityData(UpdatedLayerOpacityData.schema.deserialize(data));
+  }
+}
+
 export class UpdatedLayerVisibilityData {
   layerIndex: number;
   visibility: LayerVisibility;
@@ -235,6 +235,13 @@ export class Pattern extends EventTarget {
     this.dispatchEvent(new CustomEvent(PatternEvent.UpdateLayerVisibility, { detail: { layerIndex, visibility } }));
   }
 
+  /** Updates layer opacity. */
+  updateLayerOpacity(layerIndex: number, opacity: number) {
+    const layer = this.#layers.get(layerIndex);
+    if (layer) layer.opacity = opacity;
+    this.dispatchEvent(new CustomEvent(PatternEvent.UpdateLayerOpacity, { detail: { layerIndex, opacity } }));
+  }
+
   /** Reorders layers. */
   moveLayer(positions: number[]) {
     this.#layers.positions = positions;
@@ -313,5 +320,6 @@ export const enum PatternEvent {
   RemoveLayer = "layers:remove",
   RenameLayer = "layers:rename",
   UpdateLayerVisibility = "layers:update_visibility",
+  UpdateLayerOpacity = "layers:update_opacity",
   MoveLayer = "layers:move",
 }
@@ -465,6 +465,12 @@ export class PatternView extends Container {
     if (lc && layer) this.#syncLayerContainers(lc, layer);
   }
 
+  updateLayerOpacity(layerIndex: number) {
+    const lc = this.#layerContainers.get(layerIndex);
+    const layer = this.#layers.get(layerIndex);
+    if (lc && layer) this.#syncLayerContainers(lc, layer);
+  }
+
   reorderLayers() {
     this.#rebuildSceneGraph();
   }
@@ -551,6 +557,8 @@ export class PatternView extends Container {
     lc.straightstitches.visible = l && layer.straightstitchesVisible;
     lc.frenchknots.visible = l && layer.frenchknotsVisible;
     lc.beads.visible = l && layer.beadsVisible;
+
+    for (const container of Object.values(lc)) container.alpha = layer.opacity;
   }
 
   async setReferenceImage(
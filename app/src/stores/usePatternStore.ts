@@ -193,6 +193,37 @@ export const usePatternStore = defineStore("embroiderly-pattern", () => {
     pattern.value.moveLayer(positions);
     triggerRef(pattern);
   });
+  events.on("layers:update_locked", ({ layerIndex, locked }) => {
+    const layer = pattern.value.layers.get(layerIndex);
+    if (layer) layer.locked = locked;
+    triggerRef(pattern);
+  });
+  events.on("layers:update_opacity", ({ layerIndex, opacity }) => {
+    pattern.value.updateLayerOpacity(layerIndex, opacity);
+    triggerRef(pattern);
+  });
+  events.on("layers:update_blend_mode", ({ layerIndex, blendMode }) => {
+    const layer = pattern.value.layers.get(layerIndex);
+    if (layer) layer.blendMode = blendMode;
+    triggerRef(pattern);
+  });
+  events.on("layers:update_group", ({ layerIndex, group }) => {
+    const layer = pattern.value.layers.get(layerIndex);
+    if (layer) layer.group = group;
+    triggerRef(pattern);
+  });
+  events.on("layers:add_group", ({ index, group }) => {
+    pattern.value.layers.insertGroup(index, group);
+    triggerRef(pattern);
+  });
+  events.on("layers:remove_group", (groupIndex) => {
+    pattern.value.layers.removeGroup(groupIndex);
+    triggerRef(pattern);
+  });
+  events.on("layers:update_group_properties", ({ groupIndex, group }) => {
+    pattern.value.layers.setGroup(groupIndex, group);
+    triggerRef(pattern);
+  });
 
   async function addStitch(layerIndex: number, stitch: Stitch) {
     if (pattern.value.isNil) return;
@@ -3,6 +3,7 @@ import { stringify as stringifyUuid } from "uuid";
 
 import {
   AddedLayerData,
+  AddedLayerGroupData,
   AddedPaletteItemData,
   DisplaySettings,
   Fabric,
@@ -14,6 +15,11 @@ import {
   ReferenceImageSettings,
   RenamedLayerData,
   SetSymbolData,
+  UpdatedLayerBlendModeData,
+  UpdatedLayerGroupData,
+  UpdatedLayerGroupMembershipData,
+  UpdatedLayerLockedData,
+  UpdatedLayerOpacityData,
   UpdatedLayerVisibilityData,
   deserializeStitchesEvent,
 } from "~/lib/pattern/";
@@ -58,6 +64,16 @@ const HANDLERS: Array<(bus: EditorEventBus, payload: Uint8Array) => void> = [
   (bus, payload) => bus.emit("layers:rename", RenamedLayerData.deserialize(payload)),
   (bus, payload) => bus.emit("layers:update_visibility", UpdatedLayerVisibilityData.deserialize(payload)),
   (bus, payload) => bus.emit("layers:move", b.vec(b.u32()).deserialize(payload)),
+  (bus, payload) => bus.emit("layers:update_locked", UpdatedLayerLockedData.deserialize(payload)),
+  (bus, payload) => bus.emit("layers:update_opacity", UpdatedLayerOpacityData.deserialize(payload)),
+  (bus, payload) => bus.emit("layers:update_blend_mode", UpdatedLayerBlendModeData.deserialize(payload)),
+  (bus, payload) => bus.emit("layers:update_group", UpdatedLayerGroupMembershipData.deserialize(payload)),
+  (bus, payload) => {
+    const { index, group } = AddedLayerGroupData.deserialize(payload);
+    bus.emit("layers:add_group", { index, group });
+  },
+  (bus, payload) => bus.emit("layers:remove_group", b.u32().deserialize(payload)),
+  (bus, payload) => bus.emit("layers:update_group_properties", UpdatedLayerGroupData.deserialize(payload)),
 
   (bus, payload) => bus.emit("publish:update-pdf", PdfExportOptions.deserialize(payload)),
 
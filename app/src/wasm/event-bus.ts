@@ -5,6 +5,8 @@ import type {
   Fabric,
   Grid,
   Layer,
+  LayerBlendMode,
+  LayerGroup,
   LayerVisibility,
   PaletteItem,
   PaletteSettings,
@@ -40,6 +42,13 @@ export interface EditorEvents {
   "layers:rename": [{ layerIndex: number; name: string }];
   "layers:update_visibility": [{ layerIndex: number; visibility: LayerVisibility }];
   "layers:move": [number[]];
+  "layers:update_locked": [{ layerIndex: number; locked: boolean }];
+  "layers:update_opacity": [{ layerIndex: number; opacity: number }];
+  "layers:update_blend_mode": [{ layerIndex: number; blendMode: LayerBlendMode }];
+  "layers:update_group": [{ layerIndex: number; group?: number }];
+  "layers:add_group": [{ index: number; group: LayerGroup }];
+  "layers:remove_group": [number];
+  "layers:update_group_properties": [{ groupIndex: number; group: LayerGroup }];
 
   "publish:update-pdf": [PdfExportOptions];
 
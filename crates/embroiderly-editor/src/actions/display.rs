@@ -16,6 +16,30 @@ pub enum DisplayAction {
 }
 
 impl DisplayAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::Update { .. } => "Change display settings",
+    }
+  }
+
+  /// Merges the next update into this one, keeping the settings this update has replaced.
+  pub fn coalesce(&mut self, next: &Self) -> bool {
+    match (self, next) {
+      (
+        Self::Update { display_settings, .. },
+        Self::Update {
+          display_settings: next_settings,
+          ..
+        },
+      ) => {
+        display_settings.clone_from(next_settings);
+        true
+      }
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::Update {
@@ -17,6 +17,14 @@ pub enum FabricAction {
 }
 
 impl FabricAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::Update { .. } => "Change fabric",
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::Update {
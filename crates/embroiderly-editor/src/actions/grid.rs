@@ -13,6 +13,14 @@ pub enum GridAction {
 }
 
 impl GridAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::Update { .. } => "Change grid",
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::Update { grid, old_grid } => {
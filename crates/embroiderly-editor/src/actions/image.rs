@@ -20,6 +20,32 @@ pub enum ImageAction {
 }
 
 impl ImageAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::SetReferenceImage { .. } => "Set reference image",
+      Self::UpdateSettings { .. } => "Change reference image settings",
+    }
+  }
+
+  /// Merges the next settings update into this one, keeping the settings this update has replaced.
+  pub const fn coalesce(&mut self, next: &Self) -> bool {
+    match (self, next) {
+      (
+        Self::UpdateSettings { settings, .. },
+        Self::UpdateSettings {
+          settings: next_settings,
+          ..
+        },
+      ) => {
+        *settings = *next_settings;
+        true
+      }
+      _ => false,
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::SetReferenceImage { image, old_image } => {
@@ -1,4 +1,4 @@
-use embroiderly_pattern::{EmbroiderlyProject, Layer};
+use embroiderly_pattern::{EmbroiderlyProject, Layer, LayerBlendMode, LayerGroup, Layers, Pattern, Stitch};
 
 use crate::EditorEvent;
 use crate::error::{Error, Result};
@@ -82,9 +82,109 @@ pub enum LayerAction {
     new_position: u32,
     old_positions: Option<Vec<u32>>,
   },
+  /// Locks or unlocks the layer for drawing.
+  UpdateLocked {
+    layer_index: u32,
+    locked: bool,
+    old_locked: Option<bool>,
+  },
+  /// Changes the opacity of the layer, clamping it to the `0.0..=1.0` range.
+  UpdateOpacity {
+    layer_index: u32,
+    opacity: f32,
+    old_opacity: Option<f32>,
+  },
+  /// Changes how the layer is composed with the layers below it when they are flattened.
+  UpdateBlendMode {
+    layer_index: u32,
+    blend_mode: LayerBlendMode,
+    old_blend_mode: Option<LayerBlendMode>,
+  },
+  /// Adds a copy of the layer right above it.
+  Duplicate {
+    layer_index: u32,
+    added_index: Option<u32>,
+  },
+  /// Merges the layer into the one right below it and removes it.
+  MergeDown {
+    layer_index: u32,
+    /// The actual index of the layer the stitches have been merged into, before the merged layer was removed.
+    target_index: Option<u32>,
+    merged_layer: Option<Layer>,
+    /// Stitches of the merged layer that have been placed into the target layer.
+    added_stitches: Option<Vec<Stitch>>,
+    /// Stitches of the target layer replaced or erased by the merged ones.
+    conflicts: Option<Vec<Stitch>>,
+    old_positions: Option<Vec<u32>>,
+  },
+  /// Merges all visible layers into the bottom visible one and removes the rest of them.
+  Flatten {
+    old_layers: Option<Layers>,
+  },
+  /// Moves the layer into the given group, or out of any group if it is `None`.
+  UpdateGroup {
+    layer_index: u32,
+    group: Option<u32>,
+    old_group: Option<Option<u32>>,
+  },
+  /// Adds a new empty layer group.
+  AddGroup {
+    name: String,
+    parent: Option<u32>,
+    added_index: Option<u32>,
+  },
+  /// Removes the layer group, moving its layers and groups to its parent group.
+  RemoveGroup {
+    group_index: u32,
+    removed_group: Option<LayerGroup>,
+    /// Actual indexes of the layers and groups which belonged to the removed group.
+    members: Option<(Vec<u32>, Vec<u32>)>,
+  },
+  /// Updates the name, visibility, or parent of the layer group.
+  UpdateGroupProperties {
+    group_index: u32,
+    group: LayerGroup,
+    old_group: Option<LayerGroup>,
+  },
 }
 
 impl LayerAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::Add { .. } => "Add layer",
+      Self::Remove { .. } => "Remove layer",
+      Self::Rename { .. } => "Rename layer",
+      Self::UpdateVisibility { .. } => "Change layer visibility",
+      Self::Move { .. } => "Move layer",
+      Self::UpdateLocked { .. } => "Lock or unlock layer",
+      Self::UpdateOpacity { .. } => "Change layer opacity",
+      Self::UpdateBlendMode { .. } => "Change layer blend mode",
+      Self::Duplicate { .. } => "Duplicate layer",
+      Self::MergeDown { .. } => "Merge layer down",
+      Self::Flatten { .. } => "Flatten layers",
+      Self::UpdateGroup { .. } => "Move layer to group",
+      Self::AddGroup { .. } => "Add layer group",
+      Self::RemoveGroup { .. } => "Remove layer group",
+      Self::UpdateGroupProperties { .. } => "Change layer group",
+    }
+  }
+
+  /// Returns the actual indexes of the layers whose stitches the action changes in the given pattern.
+  #[must_use]
+  pub fn layer_indexes(&self, pattern: &Pattern) -> Vec<u32> {
+    match self {
+      Self::MergeDown { layer_index, .. } => std::iter::once(*layer_index)
+        .chain(merge_down_target(&pattern.layers, *layer_index))
+        .collect(),
+      Self::Flatten { .. } => flatten_targets(&pattern.layers)
+        .map(|(target, removed)| std::iter::once(target).chain(removed).collect())
+        .unwrap_or_default(),
+      _ => Vec::new(),
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::Add { added_index } => {
@@ -151,6 +251,259 @@ impl LayerAction {
           EditorEvent::PatternChanged(embproj.id),
         ])
       }
+      Self::UpdateLocked {
+        layer_index,
+        locked,
+        old_locked,
+      } => {
+        let layer = &mut embproj.pattern.layers[*layer_index];
+        old_locked.get_or_insert(layer.locked);
+        layer.locked = *locked;
+        Ok(vec![
+          EditorEvent::LayerUpdateLocked {
+            layer_index: *layer_index,
+            locked: *locked,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::UpdateOpacity {
+        layer_index,
+        opacity,
+        old_opacity,
+      } => {
+        if !opacity.is_finite() {
+          return Err(Error::InvalidLayerOpacity(*opacity));
+        }
+        let layer = &mut embproj.pattern.layers[*layer_index];
+        old_opacity.get_or_insert(layer.opacity);
+        layer.opacity = opacity.clamp(0.0, 1.0);
+        Ok(vec![
+          EditorEvent::LayerUpdateOpacity {
+            layer_index: *layer_index,
+            opacity: layer.opacity,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::UpdateBlendMode {
+        layer_index,
+        blend_mode,
+        old_blend_mode,
+      } => {
+        let layer = &mut embproj.pattern.layers[*layer_index];
+        old_blend_mode.get_or_insert(layer.blend_mode);
+        layer.blend_mode = *blend_mode;
+        Ok(vec![
+          EditorEvent::LayerUpdateBlendMode {
+            layer_index: *layer_index,
+            blend_mode: *blend_mode,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::UpdateGroup {
+        layer_index,
+        group,
+        old_group,
+      } => {
+        ensure_group_exists(&embproj.pattern.layers, *group)?;
+        let layer = &mut embproj.pattern.layers[*layer_index];
+        old_group.get_or_insert(layer.group);
+        layer.group = *group;
+        Ok(vec![
+          EditorEvent::LayerUpdateGroup {
+            layer_index: *layer_index,
+            group: *group,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::AddGroup {
+        name,
+        parent,
+        added_index,
+      } => {
+        ensure_group_exists(&embproj.pattern.layers, *parent)?;
+        let group = LayerGroup {
+          parent: *parent,
+          ..LayerGroup::new(name.clone())
+        };
+        let index = embproj.pattern.layers.push_group(group.clone());
+        added_index.get_or_insert(index);
+        Ok(vec![
+          EditorEvent::LayerGroupAdd { index, group },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::RemoveGroup {
+        group_index,
+        removed_group,
+        members,
+      } => {
+        let layers = &mut embproj.pattern.layers;
+        ensure_group_exists(layers, Some(*group_index))?;
+        let layer_members = (0..layers.len() as u32)
+          .filter(|&index| layers[index].group == Some(*group_index))
+          .collect();
+        let group_members = (0..layers.groups().len() as u32)
+          .filter(|&index| {
+            layers
+              .get_group(index)
+              .is_some_and(|group| group.parent == Some(*group_index))
+          })
+          .collect();
+        let group = layers.remove_group(*group_index);
+        removed_group.get_or_insert(group);
+        members.get_or_insert((layer_members, group_members));
+        Ok(vec![
+          EditorEvent::LayerGroupRemove(*group_index),
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::UpdateGroupProperties {
+        group_index,
+        group,
+        old_group,
+      } => {
+        let layers = &mut embproj.pattern.layers;
+        ensure_group_exists(layers, Some(*group_index))?;
+        ensure_group_exists(layers, group.parent)?;
+        if group
+          .parent
+          .is_some_and(|parent| layers.is_group_within(parent, *group_index))
+        {
+          return Err(Error::CyclicLayerGroup(*group_index));
+        }
+
+        let current = layers
+          .get_group_mut(*group_index)
+          .ok_or(Error::LayerGroupNotFound(*group_index))?;
+        let prev = std::mem::replace(current, group.clone());
+        old_group.get_or_insert(prev);
+        Ok(vec![
+          EditorEvent::LayerGroupUpdate {
+            group_index: *group_index,
+            group: group.clone(),
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::Duplicate {
+        layer_index,
+        added_index,
+      } => {
+        let layers = &mut embproj.pattern.layers;
+        let layer = layers[*layer_index].clone();
+        let position = layers
+          .positions()
+          .iter()
+          .position(|&index| index == *layer_index)
+          .unwrap_or_default();
+
+        // A new layer appears at the top, so move it right above the original one.
+        let index = layers.push(layer.clone());
+        let positions = layers.move_layer(0, position as u32);
+        added_index.get_or_insert(index);
+
+        Ok(vec![
+          EditorEvent::LayerAdd { index, layer },
+          EditorEvent::LayerMove(positions),
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::MergeDown {
+        layer_index,
+        target_index,
+        merged_layer,
+        added_stitches,
+        conflicts,
+        old_positions,
+      } => {
+        let layers = &mut embproj.pattern.layers;
+        let positions = layers.positions().to_vec();
+        let target = merge_down_target(layers, *layer_index).ok_or(Error::CannotMergeDownBottomLayer)?;
+
+        // The stitches are composed the same way as when flattening the layers.
+        let upper = &layers[*layer_index];
+        let mut merged = layers[target].clone();
+        for stitch in upper.get_stitches() {
+          merged.compose_stitch(stitch, upper.blend_mode);
+        }
+        let stitches: Vec<Stitch> = merged
+          .get_stitches()
+          .into_iter()
+          .filter(|stitch| !layers[target].contains_stitch(stitch))
+          .collect();
+        let replaced: Vec<Stitch> = layers[target]
+          .get_stitches()
+          .into_iter()
+          .filter(|stitch| !merged.contains_stitch(stitch))
+          .collect();
+        layers[target] = merged;
+        let layer = layers.remove(*layer_index);
+
+        target_index.get_or_insert(target);
+        merged_layer.get_or_insert(layer);
+        added_stitches.get_or_insert_with(|| stitches.clone());
+        conflicts.get_or_insert_with(|| replaced.clone());
+        old_positions.get_or_insert(positions);
+
+        Ok(vec![
+          EditorEvent::StitchesAdd {
+            layer_index: target,
+            stitches,
+          },
+          EditorEvent::StitchesRemove {
+            layer_index: target,
+            stitches: replaced,
+          },
+          EditorEvent::LayerRemove(*layer_index),
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::Flatten { old_layers } => {
+        let layers = &embproj.pattern.layers;
+        old_layers.get_or_insert_with(|| layers.clone());
+
+        // There is nothing to merge.
+        let Some((target, removed)) = flatten_targets(layers) else {
+          return Ok(vec![]);
+        };
+
+        // The target layer keeps its own properties and only gets the merged stitches.
+        let merged = embproj.pattern.merge_visible_layers();
+        let flattened = Layer {
+          fullstitches: merged.fullstitches,
+          partstitches: merged.partstitches,
+          linestitches: merged.linestitches,
+          nodestitches: merged.nodestitches,
+          specialstitches: merged.specialstitches,
+          ..layers[target].clone()
+        };
+        let old_stitches = layers[target].get_stitches();
+        let new_stitches = flattened.get_stitches();
+        embproj.pattern.layers[target] = flattened;
+
+        let mut events = vec![
+          EditorEvent::StitchesRemove {
+            layer_index: target,
+            stitches: old_stitches,
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: target,
+            stitches: new_stitches,
+          },
+        ];
+
+        for &index in removed.iter().rev() {
+          embproj.pattern.layers.remove(index);
+          events.push(EditorEvent::LayerRemove(index));
+        }
+
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
     }
   }
 
@@ -214,6 +567,234 @@ impl LayerAction {
           EditorEvent::PatternChanged(embproj.id),
         ])
       }
+      Self::UpdateLocked {
+        layer_index,
+        old_locked,
+        ..
+      } => {
+        let old = old_locked.take().ok_or(Error::ActionNotPerformed)?;
+        embproj.pattern.layers[*layer_index].locked = old;
+        Ok(vec![
+          EditorEvent::LayerUpdateLocked {
+            layer_index: *layer_index,
+            locked: old,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::UpdateOpacity {
+        layer_index,
+        old_opacity,
+        ..
+      } => {
+        let old = old_opacity.take().ok_or(Error::ActionNotPerformed)?;
+        embproj.pattern.layers[*layer_index].opacity = old;
+        Ok(vec![
+          EditorEvent::LayerUpdateOpacity {
+            layer_index: *layer_index,
+            opacity: old,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::UpdateBlendMode {
+        layer_index,
+        old_blend_mode,
+        ..
+      } => {
+        let old = old_blend_mode.take().ok_or(Error::ActionNotPerformed)?;
+        embproj.pattern.layers[*layer_index].blend_mode = old;
+        Ok(vec![
+          EditorEvent::LayerUpdateBlendMode {
+            layer_index: *layer_index,
+            blend_mode: old,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::UpdateGroup {
+        layer_index, old_group, ..
+      } => {
+        let old = old_group.take().ok_or(Error::ActionNotPerformed)?;
+        embproj.pattern.layers[*layer_index].group = old;
+        Ok(vec![
+          EditorEvent::LayerUpdateGroup {
+            layer_index: *layer_index,
+            group: old,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::AddGroup { added_index, .. } => {
+        let index = added_index.take().ok_or(Error::ActionNotPerformed)?;
+        embproj.pattern.layers.remove_group(index);
+        Ok(vec![
+          EditorEvent::LayerGroupRemove(index),
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::RemoveGroup {
+        group_index,
+        removed_group,
+        members,
+      } => {
+        let group = removed_group.take().ok_or(Error::ActionNotPerformed)?;
+        let (layer_members, group_members) = members.take().ok_or(Error::ActionNotPerformed)?;
+        let layers = &mut embproj.pattern.layers;
+        layers.insert_group(*group_index, group.clone());
+
+        let mut events = vec![EditorEvent::LayerGroupAdd {
+          index: *group_index,
+          group,
+        }];
+        for layer_index in layer_members {
+          layers[layer_index].group = Some(*group_index);
+          events.push(EditorEvent::LayerUpdateGroup {
+            layer_index,
+            group: Some(*group_index),
+          });
+        }
+        for member_index in group_members {
+          if let Some(member) = layers.get_group_mut(member_index) {
+            member.parent = Some(*group_index);
+            events.push(EditorEvent::LayerGroupUpdate {
+              group_index: member_index,
+              group: member.clone(),
+            });
+          }
+        }
+
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
+      Self::UpdateGroupProperties {
+        group_index, old_group, ..
+      } => {
+        let old = old_group.take().ok_or(Error::ActionNotPerformed)?;
+        let current = embproj
+          .pattern
+          .layers
+          .get_group_mut(*group_index)
+          .ok_or(Error::LayerGroupNotFound(*group_index))?;
+        *current = old.clone();
+        Ok(vec![
+          EditorEvent::LayerGroupUpdate {
+            group_index: *group_index,
+            group: old,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::Duplicate { added_index, .. } => {
+        let index = added_index.take().ok_or(Error::ActionNotPerformed)?;
+        embproj.pattern.layers.remove(index);
+        Ok(vec![
+          EditorEvent::LayerRemove(index),
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::MergeDown {
+        layer_index,
+        target_index,
+        merged_layer,
+        added_stitches,
+        conflicts,
+        old_positions,
+      } => {
+        let target = target_index.take().ok_or(Error::ActionNotPerformed)?;
+        let layer = merged_layer.take().ok_or(Error::ActionNotPerformed)?;
+        let stitches = added_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        let replaced = conflicts.take().ok_or(Error::ActionNotPerformed)?;
+        let positions = old_positions.take().ok_or(Error::ActionNotPerformed)?;
+
+        let layers = &mut embproj.pattern.layers;
+        layers.insert(*layer_index, layer.clone());
+        layers.set_positions(positions.clone());
+
+        layers[target].remove_stitches(stitches.clone());
+        layers[target].add_stitches(replaced.clone());
+
+        Ok(vec![
+          EditorEvent::LayerAdd {
+            index: *layer_index,
+            layer,
+          },
+          EditorEvent::LayerMove(positions),
+          EditorEvent::StitchesRemove {
+            layer_index: target,
+            stitches,
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: target,
+            stitches: replaced,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::Flatten { old_layers } => {
+        let old = old_layers.take().ok_or(Error::ActionNotPerformed)?;
+        let Some((target, removed)) = flatten_targets(&old) else {
+          return Ok(vec![]);
+        };
+        let flattened = std::mem::replace(&mut embproj.pattern.layers, old);
+        let flattened_index = target - removed.iter().filter(|&&index| index < target).count() as u32;
+
+        // Restore the removed layers first, so the target layer gets its original index back.
+        let layers = &embproj.pattern.layers;
+        let mut events: Vec<EditorEvent> = removed
+          .iter()
+          .map(|&index| EditorEvent::LayerAdd {
+            index,
+            layer: layers[index].clone(),
+          })
+          .collect();
+        events.push(EditorEvent::StitchesRemove {
+          layer_index: target,
+          stitches: flattened[flattened_index].get_stitches(),
+        });
+        events.push(EditorEvent::StitchesAdd {
+          layer_index: target,
+          stitches: layers[target].get_stitches(),
+        });
+        events.push(EditorEvent::LayerMove(layers.positions().to_vec()));
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
     }
   }
 }
+
+/// Returns an error if the layer group is given but doesn't exist.
+fn ensure_group_exists(layers: &Layers, group: Option<u32>) -> Result<()> {
+  match group {
+    Some(index) if layers.get_group(index).is_none() => Err(Error::LayerGroupNotFound(index)),
+    _ => Ok(()),
+  }
+}
+
+/// Returns the actual index of the layer right below the given one, or `None` if it is the bottom one.
+fn merge_down_target(layers: &Layers, layer_index: u32) -> Option<u32> {
+  let positions = layers.positions();
+  positions
+    .iter()
+    .position(|&index| index == layer_index)
+    .and_then(|position| positions.get(position + 1).copied())
+}
+
+/// Returns the actual index of the bottom visible layer and the sorted actual indexes of the other visible layers,
+/// or `None` if there are fewer than two visible layers.
+fn flatten_targets(layers: &Layers) -> Option<(u32, Vec<u32>)> {
+  let mut visible = layers
+    .positions()
+    .iter()
+    .rev()
+    .copied()
+    .filter(|&index| layers.is_layer_visible(index));
+  let target = visible.next()?;
+  let mut removed: Vec<u32> = visible.collect();
+  if removed.is_empty() {
+    return None;
+  }
+  removed.sort_unstable();
+  Some((target, removed))
+}
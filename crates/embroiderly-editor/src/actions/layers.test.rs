@@ -1,7 +1,10 @@
-use embroiderly_pattern::EmbroiderlyProject;
+use embroiderly_pattern::{
+  Coord, EmbroiderlyProject, FullStitch, FullStitchKind, Layer, LayerBlendMode, LayerGroup, Stitch,
+};
 
 use crate::actions::LayerAction;
 use crate::actions::layers::LayerVisibility;
+use crate::error::Error;
 use crate::{EditorAction, EditorEvent};
 
 fn create_pattern_project() -> EmbroiderlyProject {
@@ -194,6 +197,129 @@ fn test_update_layer_visibility_action() {
   }
 }
 
+#[test]
+fn test_update_layer_locked_action() {
+  let mut embproj = create_pattern_project();
+  let mut action = EditorAction::Layer(LayerAction::UpdateLocked {
+    layer_index: 0,
+    locked: true,
+    old_locked: None,
+  });
+
+  // Test performing the action.
+  {
+    assert!(!embproj.pattern.layers[0].locked);
+    let events = action.perform(&mut embproj).unwrap();
+    assert!(embproj.pattern.layers[0].locked);
+    assert!(matches!(
+      events[0],
+      EditorEvent::LayerUpdateLocked {
+        layer_index: 0,
+        locked: true
+      }
+    ));
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the action.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    assert!(!embproj.pattern.layers[0].locked);
+    assert!(matches!(
+      events[0],
+      EditorEvent::LayerUpdateLocked {
+        layer_index: 0,
+        locked: false
+      }
+    ));
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+}
+
+#[test]
+fn test_update_layer_opacity_action() {
+  let mut embproj = create_pattern_project();
+  let mut action = EditorAction::Layer(LayerAction::UpdateOpacity {
+    layer_index: 0,
+    opacity: 1.5,
+    old_opacity: None,
+  });
+  embproj.pattern.layers[0].opacity = 0.25;
+
+  // Test performing the action.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.layers[0].opacity, 1.0);
+
+    let EditorEvent::LayerUpdateOpacity { layer_index, opacity } = &events[0] else {
+      panic!("expected LayerUpdateOpacity");
+    };
+    assert_eq!(layer_index, &0);
+    assert_eq!(opacity, &1.0);
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the action.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.layers[0].opacity, 0.25);
+
+    let EditorEvent::LayerUpdateOpacity { opacity, .. } = &events[0] else {
+      panic!("expected LayerUpdateOpacity");
+    };
+    assert_eq!(opacity, &0.25);
+  }
+}
+
+#[test]
+fn test_update_layer_opacity_action_rejects_non_finite_values() {
+  let mut embproj = create_pattern_project();
+  for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+    let mut action = EditorAction::Layer(LayerAction::UpdateOpacity {
+      layer_index: 0,
+      opacity: value,
+      old_opacity: None,
+    });
+    assert!(matches!(
+      action.perform(&mut embproj),
+      Err(Error::InvalidLayerOpacity(_))
+    ));
+  }
+  assert_eq!(embproj.pattern.layers[0].opacity, 1.0);
+}
+
+#[test]
+fn test_update_layer_blend_mode_action() {
+  let mut embproj = create_pattern_project();
+  let mut action = EditorAction::Layer(LayerAction::UpdateBlendMode {
+    layer_index: 0,
+    blend_mode: LayerBlendMode::Mask,
+    old_blend_mode: None,
+  });
+
+  let events = action.perform(&mut embproj).unwrap();
+  assert_eq!(embproj.pattern.layers[0].blend_mode, LayerBlendMode::Mask);
+  assert!(matches!(
+    events[0],
+    EditorEvent::LayerUpdateBlendMode {
+      layer_index: 0,
+      blend_mode: LayerBlendMode::Mask
+    }
+  ));
+  assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+
+  let events = action.revoke(&mut embproj).unwrap();
+  assert_eq!(embproj.pattern.layers[0].blend_mode, LayerBlendMode::Overwrite);
+  assert!(matches!(
+    events[0],
+    EditorEvent::LayerUpdateBlendMode {
+      layer_index: 0,
+      blend_mode: LayerBlendMode::Overwrite
+    }
+  ));
+}
+
 #[test]
 fn test_move_layer_action() {
   let mut embproj = create_pattern_project();
@@ -231,3 +357,414 @@ fn test_move_layer_action() {
     assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
   }
 }
+
+fn full_stitch(x: f32, palindex: u32) -> Stitch {
+  Stitch::Full(FullStitch {
+    x: Coord::new(x).unwrap(),
+    y: Coord::new(0.0).unwrap(),
+    palindex,
+    kind: FullStitchKind::Full,
+  })
+}
+
+/// Creates a pattern project with three layers, where the layer 1 is at the top and the layer 2 is in the middle.
+fn create_layered_pattern_project() -> EmbroiderlyProject {
+  let mut embproj = create_pattern_project();
+  embproj.pattern.layers.push(Layer::new("Layer 2"));
+  embproj.pattern.layers.push(Layer::new("Layer 3"));
+  embproj.pattern.layers.set_positions(vec![1, 2, 0]);
+  embproj
+    .pattern
+    .add_stitches(0, vec![full_stitch(0.0, 0), full_stitch(1.0, 0)]);
+  embproj.pattern.add_stitches(1, vec![full_stitch(1.0, 1)]);
+  embproj
+    .pattern
+    .add_stitches(2, vec![full_stitch(0.0, 2), full_stitch(2.0, 2)]);
+  embproj
+}
+
+#[test]
+fn test_merge_down_layer_action() {
+  let mut embproj = create_layered_pattern_project();
+  let mut action = EditorAction::Layer(LayerAction::MergeDown {
+    layer_index: 2,
+    target_index: None,
+    merged_layer: None,
+    added_stitches: None,
+    conflicts: None,
+    old_positions: None,
+  });
+
+  // Test performing the action.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.layers.len(), 2);
+    assert_eq!(embproj.pattern.layers.positions(), &[1, 0]);
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full_stitch(0.0, 2), full_stitch(1.0, 0), full_stitch(2.0, 2)]
+    );
+
+    let EditorEvent::StitchesRemove { layer_index, stitches } = &events[1] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(layer_index, &0);
+    assert_eq!(stitches, &vec![full_stitch(0.0, 0)]);
+    assert!(matches!(events[2], EditorEvent::LayerRemove(2)));
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the action.
+  {
+    action.revoke(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.layers.len(), 3);
+    assert_eq!(embproj.pattern.layers.positions(), &[1, 2, 0]);
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full_stitch(0.0, 0), full_stitch(1.0, 0)]
+    );
+    assert_eq!(
+      embproj.pattern.layers[2].get_stitches(),
+      vec![full_stitch(0.0, 2), full_stitch(2.0, 2)]
+    );
+  }
+}
+
+#[test]
+fn test_merge_down_layer_action_respects_blend_mode() {
+  let merge_down = || {
+    EditorAction::Layer(LayerAction::MergeDown {
+      layer_index: 2,
+      target_index: None,
+      merged_layer: None,
+      added_stitches: None,
+      conflicts: None,
+      old_positions: None,
+    })
+  };
+
+  // The stitches of a filling layer are placed only where the target layer is empty.
+  let mut embproj = create_layered_pattern_project();
+  embproj.pattern.layers[2].blend_mode = LayerBlendMode::FillEmpty;
+  let mut action = merge_down();
+  let events = action.perform(&mut embproj).unwrap();
+  assert_eq!(
+    embproj.pattern.layers[0].get_stitches(),
+    vec![full_stitch(0.0, 0), full_stitch(1.0, 0), full_stitch(2.0, 2)]
+  );
+  assert!(matches!(&events[0], EditorEvent::StitchesAdd { stitches, .. } if stitches == &vec![full_stitch(2.0, 2)]));
+  assert!(matches!(&events[1], EditorEvent::StitchesRemove { stitches, .. } if stitches.is_empty()));
+
+  action.revoke(&mut embproj).unwrap();
+  assert_eq!(
+    embproj.pattern.layers[0].get_stitches(),
+    vec![full_stitch(0.0, 0), full_stitch(1.0, 0)]
+  );
+
+  // The stitches of a masking layer only erase the stitches of the target layer.
+  let mut embproj = create_layered_pattern_project();
+  embproj.pattern.layers[2].blend_mode = LayerBlendMode::Mask;
+  let mut action = merge_down();
+  action.perform(&mut embproj).unwrap();
+  assert_eq!(embproj.pattern.layers[0].get_stitches(), vec![full_stitch(1.0, 0)]);
+
+  action.revoke(&mut embproj).unwrap();
+  assert_eq!(
+    embproj.pattern.layers[0].get_stitches(),
+    vec![full_stitch(0.0, 0), full_stitch(1.0, 0)]
+  );
+  assert_eq!(
+    embproj.pattern.layers[2].get_stitches(),
+    vec![full_stitch(0.0, 2), full_stitch(2.0, 2)]
+  );
+}
+
+#[test]
+fn test_merge_down_bottom_layer_action() {
+  let mut embproj = create_layered_pattern_project();
+  let mut action = EditorAction::Layer(LayerAction::MergeDown {
+    layer_index: 0,
+    target_index: None,
+    merged_layer: None,
+    added_stitches: None,
+    conflicts: None,
+    old_positions: None,
+  });
+
+  assert!(matches!(
+    action.perform(&mut embproj),
+    Err(Error::CannotMergeDownBottomLayer)
+  ));
+  assert_eq!(embproj.pattern.layers.len(), 3);
+}
+
+#[test]
+fn test_flatten_layers_action() {
+  let mut embproj = create_layered_pattern_project();
+  embproj.pattern.layers[2].visible = false;
+  let mut action = EditorAction::Layer(LayerAction::Flatten { old_layers: None });
+
+  // Test performing the action.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.layers.len(), 2);
+    assert_eq!(embproj.pattern.layers.positions(), &[1, 0]);
+    assert_eq!(embproj.pattern.layers[0].name, "");
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full_stitch(0.0, 0), full_stitch(1.0, 1)]
+    );
+    // The hidden layer is kept as is.
+    assert_eq!(embproj.pattern.layers[1].name, "Layer 3");
+
+    assert!(matches!(events[2], EditorEvent::LayerRemove(1)));
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the action.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.layers.len(), 3);
+    assert_eq!(embproj.pattern.layers.positions(), &[1, 2, 0]);
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full_stitch(0.0, 0), full_stitch(1.0, 0)]
+    );
+    assert_eq!(embproj.pattern.layers[1].get_stitches(), vec![full_stitch(1.0, 1)]);
+
+    let EditorEvent::LayerAdd { index, layer } = &events[0] else {
+      panic!("expected LayerAdd");
+    };
+    assert_eq!(index, &1);
+    assert_eq!(layer.name, "Layer 2");
+  }
+}
+
+#[test]
+fn test_flatten_layers_action_merges_hidden_stitch_kinds() {
+  let mut embproj = create_layered_pattern_project();
+  embproj.pattern.layers[2].visible = false;
+  // Hiding a stitch kind only affects the display, so the stitches still get merged.
+  embproj.pattern.layers[1].fullstitches_visible = false;
+  // The target layer keeps its own settings.
+  embproj.pattern.layers[0].petitestitches_visible = false;
+  embproj.pattern.layers[0].blend_mode = LayerBlendMode::FillEmpty;
+  let mut action = EditorAction::Layer(LayerAction::Flatten { old_layers: None });
+
+  action.perform(&mut embproj).unwrap();
+  let layer = &embproj.pattern.layers[0];
+  assert_eq!(layer.get_stitches(), vec![full_stitch(0.0, 0), full_stitch(1.0, 1)]);
+  assert!(layer.fullstitches_visible);
+  assert!(!layer.petitestitches_visible);
+  assert_eq!(layer.blend_mode, LayerBlendMode::FillEmpty);
+
+  action.revoke(&mut embproj).unwrap();
+  assert_eq!(embproj.pattern.layers.len(), 3);
+  assert!(!embproj.pattern.layers[1].fullstitches_visible);
+  assert_eq!(embproj.pattern.layers[1].get_stitches(), vec![full_stitch(1.0, 1)]);
+}
+
+#[test]
+fn test_duplicate_layer_action() {
+  let mut embproj = create_layered_pattern_project();
+  let mut action = EditorAction::Layer(LayerAction::Duplicate {
+    layer_index: 2,
+    added_index: None,
+  });
+
+  // Test performing the action.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.layers.len(), 4);
+    assert_eq!(embproj.pattern.layers.positions(), &[1, 3, 2, 0]);
+    assert_eq!(embproj.pattern.layers[3].name, "Layer 3");
+    assert_eq!(
+      embproj.pattern.layers[3].get_stitches(),
+      embproj.pattern.layers[2].get_stitches()
+    );
+
+    let EditorEvent::LayerAdd { index, .. } = &events[0] else {
+      panic!("expected LayerAdd");
+    };
+    assert_eq!(index, &3);
+    assert!(matches!(&events[1], EditorEvent::LayerMove(positions) if positions == &[1, 3, 2, 0]));
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the action.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.layers.len(), 3);
+    assert_eq!(embproj.pattern.layers.positions(), &[1, 2, 0]);
+    assert!(matches!(events[0], EditorEvent::LayerRemove(3)));
+  }
+}
+
+#[test]
+fn test_layer_group_actions() {
+  let mut embproj = create_pattern_project();
+  embproj.pattern.layers.push(Layer::new("Layer 2"));
+
+  let mut add_outer = EditorAction::Layer(LayerAction::AddGroup {
+    name: String::from("Outer"),
+    parent: None,
+    added_index: None,
+  });
+  let events = add_outer.perform(&mut embproj).unwrap();
+  let EditorEvent::LayerGroupAdd { index, group } = &events[0] else {
+    panic!("expected LayerGroupAdd");
+  };
+  assert_eq!(index, &0);
+  assert_eq!(group.name, "Outer");
+
+  let mut add_inner = EditorAction::Layer(LayerAction::AddGroup {
+    name: String::from("Inner"),
+    parent: Some(0),
+    added_index: None,
+  });
+  add_inner.perform(&mut embproj).unwrap();
+
+  let mut update_group = EditorAction::Layer(LayerAction::UpdateGroup {
+    layer_index: 1,
+    group: Some(1),
+    old_group: None,
+  });
+  let events = update_group.perform(&mut embproj).unwrap();
+  assert!(matches!(
+    events[0],
+    EditorEvent::LayerUpdateGroup {
+      layer_index: 1,
+      group: Some(1)
+    }
+  ));
+  assert_eq!(embproj.pattern.layers[1].group, Some(1));
+
+  // Hiding the outer group hides the nested layer.
+  let mut hide_outer = EditorAction::Layer(LayerAction::UpdateGroupProperties {
+    group_index: 0,
+    group: LayerGroup {
+      visible: false,
+      ..LayerGroup::new("Outer")
+    },
+    old_group: None,
+  });
+  hide_outer.perform(&mut embproj).unwrap();
+  assert!(!embproj.pattern.layers.is_layer_visible(1));
+  assert!(embproj.pattern.layers.is_layer_visible(0));
+
+  // Removing the inner group moves its layer to the outer group.
+  let mut remove_inner = EditorAction::Layer(LayerAction::RemoveGroup {
+    group_index: 1,
+    removed_group: None,
+    members: None,
+  });
+  let events = remove_inner.perform(&mut embproj).unwrap();
+  assert!(matches!(events[0], EditorEvent::LayerGroupRemove(1)));
+  assert_eq!(embproj.pattern.layers.groups().len(), 1);
+  assert_eq!(embproj.pattern.layers[1].group, Some(0));
+
+  remove_inner.revoke(&mut embproj).unwrap();
+  assert_eq!(embproj.pattern.layers.groups().len(), 2);
+  assert_eq!(embproj.pattern.layers.get_group(1).unwrap().name, "Inner");
+  assert_eq!(embproj.pattern.layers[1].group, Some(1));
+
+  hide_outer.revoke(&mut embproj).unwrap();
+  assert!(embproj.pattern.layers.is_layer_visible(1));
+
+  update_group.revoke(&mut embproj).unwrap();
+  assert_eq!(embproj.pattern.layers[1].group, None);
+
+  add_inner.revoke(&mut embproj).unwrap();
+  add_outer.revoke(&mut embproj).unwrap();
+  assert!(embproj.pattern.layers.groups().is_empty());
+}
+
+#[test]
+fn test_layer_group_actions_reject_missing_groups() {
+  let mut embproj = create_pattern_project();
+  embproj.pattern.layers.push_group(LayerGroup::new("Outer"));
+
+  let mut add_group = EditorAction::Layer(LayerAction::AddGroup {
+    name: String::from("Inner"),
+    parent: Some(1),
+    added_index: None,
+  });
+  assert!(matches!(
+    add_group.perform(&mut embproj),
+    Err(Error::LayerGroupNotFound(1))
+  ));
+
+  let mut update_group = EditorAction::Layer(LayerAction::UpdateGroup {
+    layer_index: 0,
+    group: Some(2),
+    old_group: None,
+  });
+  assert!(matches!(
+    update_group.perform(&mut embproj),
+    Err(Error::LayerGroupNotFound(2))
+  ));
+  assert_eq!(embproj.pattern.layers[0].group, None);
+
+  let mut update_properties = EditorAction::Layer(LayerAction::UpdateGroupProperties {
+    group_index: 3,
+    group: LayerGroup::new("Missing"),
+    old_group: None,
+  });
+  assert!(matches!(
+    update_properties.perform(&mut embproj),
+    Err(Error::LayerGroupNotFound(3))
+  ));
+
+  let mut update_properties = EditorAction::Layer(LayerAction::UpdateGroupProperties {
+    group_index: 0,
+    group: LayerGroup {
+      parent: Some(4),
+      ..LayerGroup::new("Outer")
+    },
+    old_group: None,
+  });
+  assert!(matches!(
+    update_properties.perform(&mut embproj),
+    Err(Error::LayerGroupNotFound(4))
+  ));
+  assert_eq!(embproj.pattern.layers.groups().len(), 1);
+}
+
+#[test]
+fn test_layer_group_actions_reject_cycles() {
+  let mut embproj = create_pattern_project();
+  let outer = embproj.pattern.layers.push_group(LayerGroup::new("Outer"));
+  let inner = embproj.pattern.layers.push_group(LayerGroup {
+    parent: Some(outer),
+    ..LayerGroup::new("Inner")
+  });
+  let nest = |group_index: u32, parent: u32| {
+    EditorAction::Layer(LayerAction::UpdateGroupProperties {
+      group_index,
+      group: LayerGroup {
+        parent: Some(parent),
+        ..LayerGroup::new("Nested")
+      },
+      old_group: None,
+    })
+  };
+
+  // A group cannot be its own parent.
+  assert!(matches!(
+    nest(outer, outer).perform(&mut embproj),
+    Err(Error::CyclicLayerGroup(0))
+  ));
+  // Nor can it be nested into its own descendant.
+  assert!(matches!(
+    nest(outer, inner).perform(&mut embproj),
+    Err(Error::CyclicLayerGroup(0))
+  ));
+  assert_eq!(embproj.pattern.layers.get_group(outer).unwrap().parent, None);
+
+  // Moving a group under its sibling is fine.
+  let sibling = embproj.pattern.layers.push_group(LayerGroup::new("Sibling"));
+  nest(inner, sibling).perform(&mut embproj).unwrap();
+  assert_eq!(embproj.pattern.layers.get_group(inner).unwrap().parent, Some(sibling));
+}
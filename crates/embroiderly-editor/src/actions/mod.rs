@@ -47,6 +47,36 @@ pub enum EditorAction {
 }
 
 impl EditorAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::Stitch(a) => a.label(),
+      Self::Fabric(a) => a.label(),
+      Self::Palette(a) => a.label(),
+      Self::Pattern(a) => a.label(),
+      Self::Display(a) => a.label(),
+      Self::Grid(a) => a.label(),
+      Self::Layer(a) => a.label(),
+      Self::Publish(a) => a.label(),
+      Self::Image(a) => a.label(),
+
+      #[cfg(test)]
+      Self::Mock => "Mock",
+    }
+  }
+
+  /// Merges the next action into this one if they are consecutive changes of the same value,
+  /// so that they are undone in a single step. Both actions must have been performed.
+  /// Returns `true` if the actions were merged.
+  pub fn coalesce(&mut self, next: &Self) -> bool {
+    match (self, next) {
+      (Self::Display(a), Self::Display(b)) => a.coalesce(b),
+      (Self::Image(a), Self::Image(b)) => a.coalesce(b),
+      _ => false,
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::Stitch(a) => a.perform(embproj),
@@ -1,7 +1,8 @@
-use embroiderly_pattern::{EmbroiderlyProject, PaletteItem, PaletteSettings, Stitch, Symbol};
+use embroiderly_pattern::{EmbroiderlyProject, PaletteItem, PaletteSettings, Pattern, Stitch, Symbol};
 
 use crate::EditorEvent;
 use crate::error::{Error, Result};
+use crate::selection::{Selection, set_stitch_palindex, stitch_palindex};
 
 #[cfg(test)]
 #[path = "palette.test.rs"]
@@ -14,6 +15,53 @@ pub enum SortPaletteBy {
   BrandAndNumber,
 }
 
+/// Specifies which stitches are affected by a color replacement.
+#[derive(Debug, Clone, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub enum ReplaceColorScope {
+  /// All stitches of the pattern.
+  Pattern,
+  /// Stitches on the visible layers only.
+  VisibleLayers,
+  /// Stitches within the selection only.
+  Selection(Selection),
+}
+
+impl ReplaceColorScope {
+  /// Returns the stitches within the scope that use the given palette item, grouped by their layer indexes.
+  fn stitches_by_palindex(&self, pattern: &Pattern, palindex: u32) -> Vec<(u32, Vec<Stitch>)> {
+    let stitches: Vec<(u32, Vec<Stitch>)> = match self {
+      Self::Pattern | Self::VisibleLayers => pattern
+        .layers
+        .positions()
+        .iter()
+        .rev()
+        .filter_map(|&layer_index| {
+          let layer = pattern.layers.get(layer_index)?;
+          if matches!(self, Self::VisibleLayers) && !pattern.layers.is_layer_visible(layer_index) {
+            return None;
+          }
+          Some((layer_index, layer.get_stitches_by_palindex(palindex)))
+        })
+        .collect(),
+      Self::Selection(selection) => selection
+        .stitches(pattern)
+        .into_iter()
+        .map(|(layer_index, stitches)| {
+          let stitches = stitches
+            .into_iter()
+            .filter(|stitch| stitch_palindex(stitch) == palindex)
+            .collect();
+          (layer_index, stitches)
+        })
+        .collect(),
+    };
+    stitches
+      .into_iter()
+      .filter(|(_, stitches)| !stitches.is_empty())
+      .collect()
+  }
+}
+
 #[derive(Debug, Clone, borsh::BorshSerialize, borsh::BorshDeserialize)]
 pub enum PaletteAction {
   AddItem {
@@ -42,9 +90,65 @@ pub enum PaletteAction {
     symbol: Option<Symbol>,
     old_symbol: Option<Option<Symbol>>,
   },
+  ReplaceColor {
+    from: u32,
+    to: u32,
+    scope: ReplaceColorScope,
+    replaced_stitches: Option<Vec<(u32, Vec<Stitch>)>>,
+  },
+  /// Merges palette items into the `target` one, reassigning their stitches and removing the merged items.
+  MergeItems {
+    palindexes: Vec<u32>,
+    target: u32,
+    saved_palitems: Option<Vec<PaletteItem>>,
+    old_positions: Option<Vec<u32>>,
+    merged_stitches: Option<Vec<Vec<Stitch>>>,
+  },
 }
 
 impl PaletteAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::AddItem { .. } => "Add palette item",
+      Self::RemoveItems { .. } => "Remove palette items",
+      Self::UpdateDisplaySettings { .. } => "Change palette display settings",
+      Self::Sort { .. } => "Sort palette",
+      Self::Reorder { .. } => "Reorder palette",
+      Self::SetSymbol { .. } => "Change symbol",
+      Self::ReplaceColor { .. } => "Replace color",
+      Self::MergeItems { .. } => "Merge palette items",
+    }
+  }
+
+  /// Returns the actual indexes of the layers whose stitches the action changes in the given pattern.
+  #[must_use]
+  pub fn layer_indexes(&self, pattern: &Pattern) -> Vec<u32> {
+    let layers_with_palindexes = |palindexes: &[u32]| {
+      pattern
+        .layers
+        .iter()
+        .enumerate()
+        .filter(|(_, layer)| {
+          palindexes
+            .iter()
+            .any(|&palindex| !layer.get_stitches_by_palindex(palindex).is_empty())
+        })
+        .map(|(layer_index, _)| layer_index as u32)
+        .collect()
+    };
+    match self {
+      Self::RemoveItems { palindexes, .. } | Self::MergeItems { palindexes, .. } => layers_with_palindexes(palindexes),
+      Self::ReplaceColor { from, to, scope, .. } if from != to => scope
+        .stitches_by_palindex(pattern, *from)
+        .into_iter()
+        .map(|(layer_index, _)| layer_index)
+        .collect(),
+      _ => Vec::new(),
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::AddItem { palitem } => {
@@ -142,6 +246,115 @@ impl PaletteAction {
           EditorEvent::PatternChanged(embproj.id),
         ])
       }
+      Self::ReplaceColor {
+        from,
+        to,
+        scope,
+        replaced_stitches,
+      } => {
+        let palette_len = embproj.pattern.palette.len() as u32;
+        if let Some(&palindex) = [&*from, &*to].into_iter().find(|&&palindex| palindex >= palette_len) {
+          return Err(Error::PaletteItemNotFound(palindex));
+        }
+
+        let replaced = replaced_stitches
+          .get_or_insert_with(|| {
+            if from == to {
+              return Vec::new();
+            }
+            scope.stitches_by_palindex(&embproj.pattern, *from)
+          })
+          .clone();
+        if replaced.is_empty() {
+          return Ok(vec![]);
+        }
+
+        let mut events = Vec::new();
+        for (layer_index, stitches) in replaced {
+          let recolored = recolor_stitches(&stitches, *to);
+          embproj.pattern.remove_stitches(layer_index, stitches.clone());
+          embproj.pattern.add_stitches(layer_index, recolored.clone());
+          events.push(EditorEvent::StitchesRemove { layer_index, stitches });
+          events.push(EditorEvent::StitchesAdd {
+            layer_index,
+            stitches: recolored,
+          });
+        }
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
+      Self::MergeItems {
+        palindexes,
+        target,
+        saved_palitems,
+        old_positions,
+        merged_stitches,
+      } => {
+        // Validate the indexes up front, so that an invalid merge leaves the pattern untouched.
+        let palette_len = embproj.pattern.palette.len() as u32;
+        if let Some(&palindex) = palindexes
+          .iter()
+          .chain([&*target])
+          .find(|&&palindex| palindex >= palette_len)
+        {
+          return Err(Error::PaletteItemNotFound(palindex));
+        }
+        if palindexes.contains(target) {
+          return Err(Error::CannotMergePaletteItemIntoItself(*target));
+        }
+        palindexes.sort_unstable();
+        if let Some(pair) = palindexes.windows(2).find(|pair| pair[0] == pair[1]) {
+          return Err(Error::DuplicatePaletteItem(pair[0]));
+        }
+
+        old_positions.get_or_insert_with(|| embproj.pattern.palette.positions().to_vec());
+        let mut palitems = Vec::with_capacity(palindexes.len());
+        for &palindex in palindexes.iter().rev() {
+          palitems.push(embproj.pattern.palette.remove(palindex));
+        }
+        // Reverse to restore in the order of `palindexes`.
+        palitems.reverse();
+        saved_palitems.get_or_insert(palitems);
+
+        // Removing the stitches also shifts the palette indexes of the remaining ones, including the target's.
+        let new_target = *target - palindexes.iter().filter(|&palindex| palindex < target).count() as u32;
+        let stitches: Vec<_> = embproj
+          .pattern
+          .layers
+          .iter_mut()
+          .map(|layer| {
+            let stitches = layer.remove_stitches_by_palindexes(palindexes);
+            layer.add_stitches(recolor_stitches(&stitches, new_target));
+            stitches
+          })
+          .collect();
+        merged_stitches.get_or_insert_with(|| stitches.clone());
+
+        if palindexes.is_empty() {
+          return Ok(vec![]);
+        }
+
+        let mut events = Vec::new();
+        for (layer_index, stitches) in stitches.iter().enumerate() {
+          if !stitches.is_empty() {
+            events.push(EditorEvent::StitchesRemove {
+              layer_index: layer_index as u32,
+              stitches: stitches.clone(),
+            });
+          }
+        }
+        events.push(EditorEvent::PaletteRemoveItems(palindexes.clone()));
+        for (layer_index, stitches) in stitches.iter().enumerate() {
+          if !stitches.is_empty() {
+            events.push(EditorEvent::StitchesAdd {
+              layer_index: layer_index as u32,
+              stitches: recolor_stitches(stitches, new_target),
+            });
+          }
+        }
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
     }
   }
 
@@ -227,6 +440,91 @@ impl PaletteAction {
           EditorEvent::PatternChanged(embproj.id),
         ])
       }
+      Self::ReplaceColor {
+        to, replaced_stitches, ..
+      } => {
+        let replaced = replaced_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        if replaced.is_empty() {
+          return Ok(vec![]);
+        }
+
+        let mut events = Vec::new();
+        for (layer_index, stitches) in replaced {
+          let recolored = recolor_stitches(&stitches, *to);
+          embproj.pattern.remove_stitches(layer_index, recolored.clone());
+          embproj.pattern.add_stitches(layer_index, stitches.clone());
+          events.push(EditorEvent::StitchesRemove {
+            layer_index,
+            stitches: recolored,
+          });
+          events.push(EditorEvent::StitchesAdd { layer_index, stitches });
+        }
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
+      Self::MergeItems {
+        palindexes,
+        target,
+        saved_palitems,
+        old_positions,
+        merged_stitches,
+      } => {
+        let palitems = saved_palitems.take().ok_or(Error::ActionNotPerformed)?;
+        let positions = old_positions.take().ok_or(Error::ActionNotPerformed)?;
+        let stitches = merged_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        if palindexes.is_empty() {
+          return Ok(vec![]);
+        }
+
+        let new_target = *target - palindexes.iter().filter(|&palindex| palindex < target).count() as u32;
+        let mut events = Vec::new();
+        for (layer, stitches) in embproj.pattern.layers.iter_mut().zip(stitches.iter()) {
+          layer.remove_stitches(recolor_stitches(stitches, new_target));
+        }
+        for (layer_index, stitches) in stitches.iter().enumerate() {
+          if !stitches.is_empty() {
+            events.push(EditorEvent::StitchesRemove {
+              layer_index: layer_index as u32,
+              stitches: recolor_stitches(stitches, new_target),
+            });
+          }
+        }
+
+        for (index, &palindex) in palindexes.iter().enumerate() {
+          embproj.pattern.palette.insert(palindex, palitems[index].clone());
+          events.push(EditorEvent::PaletteAddItem {
+            palitem: palitems[index].clone(),
+            palindex,
+          });
+        }
+        embproj.pattern.palette.set_positions(positions.clone());
+        events.push(EditorEvent::PaletteReorder(positions));
+
+        let palette_len = embproj.pattern.palette.len() as u32;
+        for (layer_index, (layer, stitches)) in embproj.pattern.layers.iter_mut().zip(stitches).enumerate() {
+          if !stitches.is_empty() {
+            events.push(EditorEvent::StitchesAdd {
+              layer_index: layer_index as u32,
+              stitches: stitches.clone(),
+            });
+          }
+          layer.restore_stitches(stitches, palindexes, palette_len);
+        }
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
     }
   }
 }
+
+/// Returns copies of the stitches with their palette item replaced by the given one.
+fn recolor_stitches(stitches: &[Stitch], palindex: u32) -> Vec<Stitch> {
+  stitches
+    .iter()
+    .copied()
+    .map(|mut stitch| {
+      set_stitch_palindex(&mut stitch, palindex);
+      stitch
+    })
+    .collect()
+}
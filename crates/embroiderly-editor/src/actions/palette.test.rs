@@ -1,9 +1,12 @@
-use embroiderly_pattern::{EmbroiderlyProject, PaletteItem, PaletteSettings};
+use embroiderly_pattern::{
+  Bounds, Coord, EmbroiderlyProject, FullStitch, FullStitchKind, Layer, PaletteItem, PaletteSettings, Pattern, Stitch,
+};
 use rand::seq::SliceRandom;
 
 use crate::actions::PaletteAction;
-use crate::actions::palette::SortPaletteBy;
-use crate::{EditorAction, EditorEvent};
+use crate::actions::palette::{ReplaceColorScope, SortPaletteBy};
+use crate::error::Error;
+use crate::{EditorAction, EditorEvent, Selection, SelectionArea};
 
 fn create_pattern_project() -> EmbroiderlyProject {
   let file_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../testdata/patterns/rainbow.oxs");
@@ -499,3 +502,278 @@ fn test_replace_symbol_action() {
     assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
   }
 }
+
+fn full_stitch(x: f32, y: f32, palindex: u32) -> Stitch {
+  Stitch::Full(FullStitch {
+    x: Coord::new(x).unwrap(),
+    y: Coord::new(y).unwrap(),
+    palindex,
+    kind: FullStitchKind::Full,
+  })
+}
+
+fn create_two_layer_project() -> EmbroiderlyProject {
+  let mut embproj = EmbroiderlyProject::new(Pattern::default());
+  for (number, color) in [("310", "000000"), ("321", "C72B3B"), ("B5200", "FFFFFF")] {
+    embproj.pattern.palette.push(PaletteItem {
+      brand: String::from("DMC"),
+      number: String::from(number),
+      name: String::new(),
+      color: String::from(color),
+      blends: None,
+      symbol: None,
+    });
+  }
+  embproj.pattern.layers.push(Layer::new("Second"));
+  embproj.pattern.add_stitches(
+    0,
+    vec![
+      full_stitch(0.0, 0.0, 0),
+      full_stitch(5.0, 5.0, 0),
+      full_stitch(1.0, 0.0, 1),
+    ],
+  );
+  embproj
+    .pattern
+    .add_stitches(1, vec![full_stitch(0.0, 0.0, 0), full_stitch(1.0, 1.0, 2)]);
+  embproj
+}
+
+#[test]
+fn test_replace_color_in_pattern() {
+  let mut embproj = create_two_layer_project();
+  let mut action = EditorAction::Palette(PaletteAction::ReplaceColor {
+    from: 0,
+    to: 2,
+    scope: ReplaceColorScope::Pattern,
+    replaced_stitches: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    assert_eq!(events.len(), 5);
+
+    assert!(embproj.pattern.contains_stitch(0, &full_stitch(0.0, 0.0, 2)));
+    assert!(embproj.pattern.contains_stitch(0, &full_stitch(5.0, 5.0, 2)));
+    assert!(embproj.pattern.contains_stitch(1, &full_stitch(0.0, 0.0, 2)));
+    assert_eq!(embproj.pattern.layers[0].get_stitches_by_palindex(0), vec![]);
+    assert_eq!(embproj.pattern.layers[1].get_stitches_by_palindex(0), vec![]);
+
+    // Stitches of other palette items are kept as is.
+    assert_eq!(
+      embproj.pattern.get_stitch(0, &full_stitch(1.0, 0.0, 0)),
+      Some(full_stitch(1.0, 0.0, 1))
+    );
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the command.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    assert_eq!(events.len(), 5);
+
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches_by_palindex(0),
+      vec![full_stitch(0.0, 0.0, 0), full_stitch(5.0, 5.0, 0)]
+    );
+    assert_eq!(
+      embproj.pattern.layers[1].get_stitches_by_palindex(0),
+      vec![full_stitch(0.0, 0.0, 0)]
+    );
+    assert_eq!(
+      embproj.pattern.layers[1].get_stitches_by_palindex(2),
+      vec![full_stitch(1.0, 1.0, 2)]
+    );
+  }
+}
+
+#[test]
+fn test_replace_color_in_visible_layers() {
+  let mut embproj = create_two_layer_project();
+  embproj.pattern.layers[1].visible = false;
+  let mut action = EditorAction::Palette(PaletteAction::ReplaceColor {
+    from: 0,
+    to: 1,
+    scope: ReplaceColorScope::VisibleLayers,
+    replaced_stitches: None,
+  });
+
+  action.perform(&mut embproj).unwrap();
+  assert_eq!(embproj.pattern.layers[0].get_stitches_by_palindex(0), vec![]);
+  assert_eq!(
+    embproj.pattern.layers[1].get_stitches_by_palindex(0),
+    vec![full_stitch(0.0, 0.0, 0)]
+  );
+}
+
+#[test]
+fn test_replace_color_in_selection() {
+  let mut embproj = create_two_layer_project();
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(0, 0, 2, 2)), Some(0));
+  let mut action = EditorAction::Palette(PaletteAction::ReplaceColor {
+    from: 0,
+    to: 1,
+    scope: ReplaceColorScope::Selection(selection),
+    replaced_stitches: None,
+  });
+
+  action.perform(&mut embproj).unwrap();
+  assert_eq!(
+    embproj.pattern.layers[0].get_stitches_by_palindex(0),
+    vec![full_stitch(5.0, 5.0, 0)]
+  );
+  assert_eq!(
+    embproj.pattern.layers[1].get_stitches_by_palindex(0),
+    vec![full_stitch(0.0, 0.0, 0)]
+  );
+}
+
+#[test]
+fn test_replace_color_with_itself_does_nothing() {
+  let mut embproj = create_two_layer_project();
+  let mut action = EditorAction::Palette(PaletteAction::ReplaceColor {
+    from: 0,
+    to: 0,
+    scope: ReplaceColorScope::Pattern,
+    replaced_stitches: None,
+  });
+
+  assert!(action.perform(&mut embproj).unwrap().is_empty());
+  assert!(action.revoke(&mut embproj).unwrap().is_empty());
+}
+
+#[test]
+fn test_replace_color_rejects_invalid_indexes() {
+  let mut embproj = create_two_layer_project();
+  let replace = |from: u32, to: u32| {
+    EditorAction::Palette(PaletteAction::ReplaceColor {
+      from,
+      to,
+      scope: ReplaceColorScope::Pattern,
+      replaced_stitches: None,
+    })
+  };
+
+  assert!(matches!(
+    replace(0, 3).perform(&mut embproj),
+    Err(Error::PaletteItemNotFound(3))
+  ));
+  assert!(matches!(
+    replace(5, 1).perform(&mut embproj),
+    Err(Error::PaletteItemNotFound(5))
+  ));
+
+  // Nothing has been recolored.
+  assert_eq!(
+    embproj.pattern.layers[0].get_stitches_by_palindex(0),
+    vec![full_stitch(0.0, 0.0, 0), full_stitch(5.0, 5.0, 0)]
+  );
+  assert_eq!(
+    embproj.pattern.layers[1].get_stitches_by_palindex(0),
+    vec![full_stitch(0.0, 0.0, 0)]
+  );
+}
+
+#[test]
+fn test_merge_palette_items() {
+  let mut embproj = create_two_layer_project();
+  embproj.pattern.palette.set_positions(vec![2, 0, 1]);
+  let palitems: Vec<_> = embproj.pattern.palette.iter().cloned().collect();
+  let mut action = EditorAction::Palette(PaletteAction::MergeItems {
+    palindexes: vec![0],
+    target: 2,
+    saved_palitems: None,
+    old_positions: None,
+    merged_stitches: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    assert_eq!(events.len(), 6);
+
+    let EditorEvent::PaletteRemoveItems(palindexes) = &events[2] else {
+      panic!("expected PaletteRemoveItems");
+    };
+    assert_eq!(palindexes, &[0]);
+
+    // The target item has shifted to the index 1.
+    assert_eq!(embproj.pattern.palette.len(), 2);
+    assert_eq!(embproj.pattern.palette[1].number, "B5200");
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches_by_palindex(1),
+      vec![full_stitch(0.0, 0.0, 1), full_stitch(5.0, 5.0, 1)]
+    );
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches_by_palindex(0),
+      vec![full_stitch(1.0, 0.0, 0)]
+    );
+    assert_eq!(
+      embproj.pattern.layers[1].get_stitches_by_palindex(1),
+      vec![full_stitch(0.0, 0.0, 1), full_stitch(1.0, 1.0, 1)]
+    );
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the command.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    assert_eq!(events.len(), 7);
+
+    assert_eq!(embproj.pattern.palette.iter().cloned().collect::<Vec<_>>(), palitems);
+    assert_eq!(embproj.pattern.palette.positions(), &[2, 0, 1]);
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches_by_palindex(0),
+      vec![full_stitch(0.0, 0.0, 0), full_stitch(5.0, 5.0, 0)]
+    );
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches_by_palindex(1),
+      vec![full_stitch(1.0, 0.0, 1)]
+    );
+    assert_eq!(
+      embproj.pattern.layers[1].get_stitches_by_palindex(0),
+      vec![full_stitch(0.0, 0.0, 0)]
+    );
+    assert_eq!(
+      embproj.pattern.layers[1].get_stitches_by_palindex(2),
+      vec![full_stitch(1.0, 1.0, 2)]
+    );
+  }
+}
+
+#[test]
+fn test_merge_palette_items_rejects_invalid_indexes() {
+  let mut embproj = create_two_layer_project();
+  let merge = |palindexes: Vec<u32>, target: u32| {
+    EditorAction::Palette(PaletteAction::MergeItems {
+      palindexes,
+      target,
+      saved_palitems: None,
+      old_positions: None,
+      merged_stitches: None,
+    })
+  };
+
+  assert!(matches!(
+    merge(vec![0, 3], 1).perform(&mut embproj),
+    Err(Error::PaletteItemNotFound(3))
+  ));
+  assert!(matches!(
+    merge(vec![0], 5).perform(&mut embproj),
+    Err(Error::PaletteItemNotFound(5))
+  ));
+  assert!(matches!(
+    merge(vec![2, 0, 2], 1).perform(&mut embproj),
+    Err(Error::DuplicatePaletteItem(2))
+  ));
+  assert!(matches!(
+    merge(vec![0, 1], 1).perform(&mut embproj),
+    Err(Error::CannotMergePaletteItemIntoItself(1))
+  ));
+
+  // Nothing has been merged.
+  assert_eq!(embproj.pattern.palette.len(), 3);
+}
@@ -1,21 +1,104 @@
-use embroiderly_pattern::{EmbroiderlyProject, PatternInfo};
+use embroiderly_pattern::{Bounds, EmbroiderlyProject, Fabric, Pattern, PatternInfo, Stitch};
 
 use crate::EditorEvent;
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::selection::{is_stitch_within_bounds, translate_stitch};
 
 #[cfg(test)]
 #[path = "pattern.test.rs"]
 mod tests;
 
+/// Specifies which point of the pattern stays in place when it is resized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub enum ResizeAnchor {
+  TopLeft,
+  Top,
+  TopRight,
+  Left,
+  Center,
+  Right,
+  BottomLeft,
+  Bottom,
+  BottomRight,
+}
+
+impl ResizeAnchor {
+  /// Returns the offset by which the stitches are shifted when the pattern is resized from the old size to the new one.
+  #[must_use]
+  pub fn offset(self, old_size: (u16, u16), new_size: (u16, u16)) -> (i32, i32) {
+    let dw = i32::from(new_size.0) - i32::from(old_size.0);
+    let dh = i32::from(new_size.1) - i32::from(old_size.1);
+    let dx = match self {
+      Self::TopLeft | Self::Left | Self::BottomLeft => 0,
+      Self::Top | Self::Center | Self::Bottom => dw / 2,
+      Self::TopRight | Self::Right | Self::BottomRight => dw,
+    };
+    let dy = match self {
+      Self::TopLeft | Self::Top | Self::TopRight => 0,
+      Self::Left | Self::Center | Self::Right => dh / 2,
+      Self::BottomLeft | Self::Bottom | Self::BottomRight => dh,
+    };
+    (dx, dy)
+  }
+}
+
 #[derive(Debug, Clone, borsh::BorshSerialize, borsh::BorshDeserialize)]
 pub enum PatternAction {
   UpdateInfo {
     info: PatternInfo,
     old_info: Option<PatternInfo>,
   },
+  /// Resizes the pattern, shifting the stitches according to the anchor and removing the ones that no longer fit.
+  Resize {
+    width: u16,
+    height: u16,
+    anchor: ResizeAnchor,
+    old_fabric: Option<Fabric>,
+    removed_stitches: Option<Vec<Vec<Stitch>>>,
+  },
 }
 
 impl PatternAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::UpdateInfo { .. } => "Change pattern info",
+      Self::Resize { .. } => "Resize pattern",
+    }
+  }
+
+  /// Returns the actual indexes of the layers whose stitches the action changes in the given pattern.
+  #[must_use]
+  pub fn layer_indexes(&self, pattern: &Pattern) -> Vec<u32> {
+    match self {
+      Self::Resize {
+        width, height, anchor, ..
+      } => {
+        let fabric = &pattern.fabric;
+        let (bounds, offset) = retained_area(*anchor, (fabric.width, fabric.height), (*width, *height));
+        pattern
+          .layers
+          .iter()
+          .enumerate()
+          .filter(|(_, layer)| {
+            // The stitches are either shifted or removed if they fall outside the retained area.
+            let stitches = layer.get_stitches();
+            if offset == (0, 0) {
+              stitches
+                .iter()
+                .any(|stitch| !is_stitch_within_bounds(stitch, bounds, &pattern.special_stitch_models))
+            } else {
+              !stitches.is_empty()
+            }
+          })
+          .map(|(layer_index, _)| layer_index as u32)
+          .collect()
+      }
+      Self::UpdateInfo { .. } => Vec::new(),
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::UpdateInfo { info, old_info } => {
@@ -26,6 +109,59 @@ impl PatternAction {
           EditorEvent::PatternChanged(embproj.id),
         ])
       }
+      Self::Resize {
+        width,
+        height,
+        anchor,
+        old_fabric,
+        removed_stitches,
+      } => {
+        if *width == 0 || *height == 0 {
+          return Err(Error::InvalidPatternSize(*width, *height));
+        }
+
+        let fabric = Fabric {
+          width: *width,
+          height: *height,
+          ..embproj.pattern.fabric.clone()
+        };
+        let prev = std::mem::replace(&mut embproj.pattern.fabric, fabric.clone());
+        let (bounds, (dx, dy)) = retained_area(*anchor, (prev.width, prev.height), (*width, *height));
+
+        let mut events = vec![EditorEvent::FabricUpdate(fabric)];
+        let mut removed = Vec::with_capacity(embproj.pattern.layers.len());
+        for (layer_index, layer) in embproj.pattern.layers.iter_mut().enumerate() {
+          let layer_index = layer_index as u32;
+          let mut layer_removed = layer.remove_stitches_outside_bounds(bounds, &embproj.pattern.special_stitch_models);
+          removed.push(layer_removed.clone());
+
+          if dx != 0 || dy != 0 {
+            let stitches = layer.get_stitches();
+            let shifted = shift_stitches(&stitches, dx, dy);
+            layer.remove_stitches(stitches.clone());
+            layer.add_stitches(shifted.clone());
+            layer_removed.extend(stitches);
+            events.push(EditorEvent::StitchesRemove {
+              layer_index,
+              stitches: layer_removed,
+            });
+            events.push(EditorEvent::StitchesAdd {
+              layer_index,
+              stitches: shifted,
+            });
+          } else if !layer_removed.is_empty() {
+            events.push(EditorEvent::StitchesRemove {
+              layer_index,
+              stitches: layer_removed,
+            });
+          }
+        }
+
+        old_fabric.get_or_insert(prev);
+        removed_stitches.get_or_insert(removed);
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
     }
   }
 
@@ -39,6 +175,66 @@ impl PatternAction {
           EditorEvent::PatternChanged(embproj.id),
         ])
       }
+      Self::Resize {
+        width,
+        height,
+        anchor,
+        old_fabric,
+        removed_stitches,
+      } => {
+        let old = old_fabric.take().ok_or(crate::error::Error::ActionNotPerformed)?;
+        let removed = removed_stitches.take().ok_or(crate::error::Error::ActionNotPerformed)?;
+        let (dx, dy) = anchor.offset((old.width, old.height), (*width, *height));
+        embproj.pattern.fabric = old.clone();
+
+        let mut events = vec![EditorEvent::FabricUpdate(old)];
+        for ((layer_index, layer), layer_removed) in embproj.pattern.layers.iter_mut().enumerate().zip(removed) {
+          let layer_index = layer_index as u32;
+          let mut added = Vec::new();
+          if dx != 0 || dy != 0 {
+            let stitches = layer.get_stitches();
+            let restored = shift_stitches(&stitches, -dx, -dy);
+            layer.remove_stitches(stitches.clone());
+            layer.add_stitches(restored.clone());
+            events.push(EditorEvent::StitchesRemove { layer_index, stitches });
+            added.extend(restored);
+          }
+
+          layer.add_stitches(layer_removed.clone());
+          added.extend(layer_removed);
+          if !added.is_empty() {
+            events.push(EditorEvent::StitchesAdd {
+              layer_index,
+              stitches: added,
+            });
+          }
+        }
+
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
     }
   }
 }
+
+/// Returns copies of the stitches moved by the given number of cells.
+fn shift_stitches(stitches: &[Stitch], dx: i32, dy: i32) -> Vec<Stitch> {
+  stitches
+    .iter()
+    .map(|&stitch| translate_stitch(stitch, dx as f32, dy as f32))
+    .collect()
+}
+
+/// Returns the area of the old pattern that remains within the resized one
+/// and the offset by which the stitches are shifted.
+fn retained_area(anchor: ResizeAnchor, old_size: (u16, u16), new_size: (u16, u16)) -> (Bounds, (i32, i32)) {
+  let (dx, dy) = anchor.offset(old_size, new_size);
+  let (x, y) = ((-dx).max(0), (-dy).max(0));
+  let bounds = Bounds::new(
+    x as u16,
+    y as u16,
+    ((i32::from(new_size.0) - dx).min(i32::from(old_size.0)) - x) as u16,
+    ((i32::from(new_size.1) - dy).min(i32::from(old_size.1)) - y) as u16,
+  );
+  (bounds, (dx, dy))
+}
@@ -1,6 +1,10 @@
-use embroiderly_pattern::{EmbroiderlyProject, PatternInfo};
+use embroiderly_pattern::{
+  Coord, EmbroiderlyProject, FullStitch, FullStitchKind, LineStitch, LineStitchKind, PatternInfo, Stitch,
+};
 
 use crate::actions::PatternAction;
+use crate::actions::pattern::ResizeAnchor;
+use crate::error::Error;
 use crate::{EditorAction, EditorEvent};
 
 #[test]
@@ -41,3 +45,143 @@ fn test_update_pattern_info() {
     assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
   }
 }
+
+fn full_stitch(x: f32, y: f32) -> Stitch {
+  Stitch::Full(FullStitch {
+    x: Coord::new(x).unwrap(),
+    y: Coord::new(y).unwrap(),
+    palindex: 0,
+    kind: FullStitchKind::Full,
+  })
+}
+
+#[test]
+fn test_resize_anchor_offset() {
+  assert_eq!(ResizeAnchor::TopLeft.offset((10, 10), (30, 20)), (0, 0));
+  assert_eq!(ResizeAnchor::Center.offset((10, 10), (30, 20)), (10, 5));
+  assert_eq!(ResizeAnchor::BottomRight.offset((10, 10), (30, 20)), (20, 10));
+  assert_eq!(ResizeAnchor::Center.offset((10, 10), (5, 5)), (-2, -2));
+}
+
+#[test]
+fn test_resize_pattern_adds_margin() {
+  let mut embproj = EmbroiderlyProject::default();
+  let line = Stitch::Line(LineStitch {
+    x: (Coord::new(1.0).unwrap(), Coord::new(3.0).unwrap()),
+    y: (Coord::new(1.0).unwrap(), Coord::new(2.0).unwrap()),
+    palindex: 0,
+    kind: LineStitchKind::Back,
+  });
+  embproj.pattern.add_stitches(0, vec![full_stitch(0.0, 0.0), line]);
+  let (width, height) = (embproj.pattern.fabric.width, embproj.pattern.fabric.height);
+
+  let mut action = EditorAction::Pattern(PatternAction::Resize {
+    width: width + 20,
+    height: height + 20,
+    anchor: ResizeAnchor::Center,
+    old_fabric: None,
+    removed_stitches: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    assert!(matches!(&events[0], EditorEvent::FabricUpdate(fabric) if fabric.width == width + 20));
+    assert_eq!(embproj.pattern.fabric.height, height + 20);
+
+    let shifted_line = Stitch::Line(LineStitch {
+      x: (Coord::new(11.0).unwrap(), Coord::new(13.0).unwrap()),
+      y: (Coord::new(11.0).unwrap(), Coord::new(12.0).unwrap()),
+      palindex: 0,
+      kind: LineStitchKind::Back,
+    });
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full_stitch(10.0, 10.0), shifted_line]
+    );
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the command.
+  {
+    action.revoke(&mut embproj).unwrap();
+    assert_eq!(
+      (embproj.pattern.fabric.width, embproj.pattern.fabric.height),
+      (width, height)
+    );
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full_stitch(0.0, 0.0), line]
+    );
+  }
+}
+
+#[test]
+fn test_resize_pattern_crops_stitches() {
+  let mut embproj = EmbroiderlyProject::default();
+  embproj.pattern.fabric.width = 10;
+  embproj.pattern.fabric.height = 10;
+  embproj.pattern.add_stitches(
+    0,
+    vec![full_stitch(0.0, 0.0), full_stitch(5.0, 5.0), full_stitch(9.0, 9.0)],
+  );
+
+  let mut action = EditorAction::Pattern(PatternAction::Resize {
+    width: 6,
+    height: 6,
+    anchor: ResizeAnchor::BottomRight,
+    old_fabric: None,
+    removed_stitches: None,
+  });
+
+  // Test executing the command.
+  {
+    action.perform(&mut embproj).unwrap();
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full_stitch(1.0, 1.0), full_stitch(5.0, 5.0)]
+    );
+  }
+
+  // Test revoking the command.
+  {
+    action.revoke(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.fabric.width, 10);
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full_stitch(0.0, 0.0), full_stitch(5.0, 5.0), full_stitch(9.0, 9.0)]
+    );
+  }
+}
+
+#[test]
+fn test_resize_pattern_rejects_zero_size() {
+  let mut embproj = EmbroiderlyProject::default();
+  embproj.pattern.fabric.width = 10;
+  embproj.pattern.fabric.height = 10;
+  embproj
+    .pattern
+    .add_stitches(0, vec![full_stitch(0.0, 0.0), full_stitch(9.0, 9.0)]);
+
+  for (width, height) in [(0, 10), (10, 0), (0, 0)] {
+    let mut action = EditorAction::Pattern(PatternAction::Resize {
+      width,
+      height,
+      anchor: ResizeAnchor::TopLeft,
+      old_fabric: None,
+      removed_stitches: None,
+    });
+    assert!(matches!(
+      action.perform(&mut embproj),
+      Err(Error::InvalidPatternSize(w, h)) if w == width && h == height
+    ));
+  }
+
+  // The pattern is left untouched.
+  assert_eq!((embproj.pattern.fabric.width, embproj.pattern.fabric.height), (10, 10));
+  assert_eq!(
+    embproj.pattern.layers[0].get_stitches(),
+    vec![full_stitch(0.0, 0.0), full_stitch(9.0, 9.0)]
+  );
+}
@@ -16,6 +16,14 @@ pub enum PublishAction {
 }
 
 impl PublishAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::UpdatePdfExportOptions { .. } => "Change PDF export options",
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::UpdatePdfExportOptions { options, old_options } => {
@@ -1,12 +1,398 @@
-use embroiderly_pattern::{EmbroiderlyProject, Stitch};
+use embroiderly_pattern::{
+  Bounds, Coord, EmbroiderlyProject, FullStitch, FullStitchKind, Layer, LineStitchKind, NodeStitchKind, PartStitch,
+  PartStitchDirection, PartStitchKind, SpecialStitch, SpecialStitchModel, Stitch,
+};
 
-use crate::EditorEvent;
 use crate::error::{Error, Result};
+use crate::selection::{is_stitch_within_bounds, set_stitch_palindex, stitch_palindex, translate_stitch};
+use crate::{Clipboard, EditorEvent};
 
 #[cfg(test)]
 #[path = "stitches.test.rs"]
 mod tests;
 
+/// Specifies the shape drawn by the [`StitchAction::AddShape`] action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub enum StitchShape {
+  Rectangle,
+  Ellipse,
+}
+
+impl StitchShape {
+  /// Returns the cells covered by the shape inscribed into the box between the two given corner cells (inclusive).
+  /// If `filled` is `false`, only the cells along the shape's outline are returned.
+  #[must_use]
+  pub fn cells(self, start: (u16, u16), end: (u16, u16), filled: bool) -> Vec<(u16, u16)> {
+    let (min_x, max_x) = (start.0.min(end.0), start.0.max(end.0));
+    let (min_y, max_y) = (start.1.min(end.1), start.1.max(end.1));
+
+    let inside = |x: u16, y: u16| match self {
+      Self::Rectangle => (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y),
+      Self::Ellipse => {
+        // Test the cell center against the ellipse inscribed into the bounding box.
+        let (rx, ry) = ((max_x - min_x + 1) as f32 / 2.0, (max_y - min_y + 1) as f32 / 2.0);
+        let (cx, cy) = (min_x as f32 + rx, min_y as f32 + ry);
+        let (dx, dy) = ((x as f32 + 0.5 - cx) / rx, (y as f32 + 0.5 - cy) / ry);
+        dx.mul_add(dx, dy * dy) <= 1.0
+      }
+    };
+
+    let mut cells = Vec::new();
+    for y in min_y..=max_y {
+      for x in min_x..=max_x {
+        if !inside(x, y) {
+          continue;
+        }
+
+        let on_outline = x == min_x
+          || x == max_x
+          || y == min_y
+          || y == max_y
+          || !inside(x - 1, y)
+          || !inside(x + 1, y)
+          || !inside(x, y - 1)
+          || !inside(x, y + 1);
+        if filled || on_outline {
+          cells.push((x, y));
+        }
+      }
+    }
+    cells
+  }
+}
+
+/// Specifies how the [`StitchAction::Transform`] action transforms stitches within their bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub enum StitchTransform {
+  /// Moves the stitches by the given number of cells.
+  Move { dx: i32, dy: i32 },
+  /// Mirrors the stitches from left to right.
+  MirrorHorizontal,
+  /// Mirrors the stitches from top to bottom.
+  MirrorVertical,
+  /// Rotates the stitches clockwise by the given number of quarter turns around the center of their bounds.
+  Rotate(u8),
+}
+
+impl StitchTransform {
+  /// Returns the top-left corner of the transformed bounds.
+  /// It may lie outside the pattern, so it isn't clamped.
+  fn origin(self, bounds: Bounds) -> (f32, f32) {
+    let (x, y) = (f32::from(bounds.x), f32::from(bounds.y));
+    let (width, height) = (f32::from(bounds.width), f32::from(bounds.height));
+    match self {
+      Self::Move { dx, dy } => (x + dx as f32, y + dy as f32),
+      // Keep the center in place, snapping the rotated bounds to whole cells.
+      Self::Rotate(turns) if turns % 2 == 1 => (
+        x + ((width - height) / 2.0).floor(),
+        y + ((height - width) / 2.0).floor(),
+      ),
+      _ => (x, y),
+    }
+  }
+
+  /// Returns the bounds occupied by the transformed bounds, clamped to the pattern origin.
+  #[must_use]
+  pub fn transform_bounds(self, bounds: Bounds) -> Bounds {
+    let (x, y) = self.origin(bounds);
+    let (width, height) = match self {
+      Self::Rotate(turns) if turns % 2 == 1 => (bounds.height, bounds.width),
+      _ => (bounds.width, bounds.height),
+    };
+    Bounds::new(x.max(0.0) as u16, y.max(0.0) as u16, width, height)
+  }
+
+  /// Returns the transformed point.
+  #[must_use]
+  pub fn transform_point(self, bounds: Bounds, x: f32, y: f32) -> (f32, f32) {
+    let (width, height) = (f32::from(bounds.width), f32::from(bounds.height));
+    let (u, v) = (x - f32::from(bounds.x), y - f32::from(bounds.y));
+    let (u, v) = match self {
+      Self::Move { .. } => (u, v),
+      Self::MirrorHorizontal => (width - u, v),
+      Self::MirrorVertical => (u, height - v),
+      Self::Rotate(turns) => match turns % 4 {
+        0 => (u, v),
+        1 => (height - v, u),
+        2 => (width - u, height - v),
+        _ => (v, width - u),
+      },
+    };
+    let (x, y) = self.origin(bounds);
+    (x + u, y + v)
+  }
+
+  /// Returns `true` if the transform swaps the forward and backward diagonals.
+  const fn flips_diagonals(self) -> bool {
+    match self {
+      Self::Move { .. } => false,
+      Self::MirrorHorizontal | Self::MirrorVertical => true,
+      Self::Rotate(turns) => turns % 2 == 1,
+    }
+  }
+
+  /// Returns the transformed stitch.
+  /// Special stitches are placed by the box of their model, which is looked up in `models`.
+  #[must_use]
+  pub fn transform_stitch(self, bounds: Bounds, stitch: Stitch, models: &[SpecialStitchModel]) -> Stitch {
+    let point = |x: Coord, y: Coord| {
+      let (x, y) = self.transform_point(bounds, *x, *y);
+      (Coord::new(x).unwrap(), Coord::new(y).unwrap())
+    };
+    // Transforms a square cell part by its opposite corners, returning the new top-left corner.
+    let square = |x: Coord, y: Coord, size: f32| {
+      let (x1, y1) = self.transform_point(bounds, *x, *y);
+      let (x2, y2) = self.transform_point(bounds, *x + size, *y + size);
+      (Coord::new(x1.min(x2)).unwrap(), Coord::new(y1.min(y2)).unwrap())
+    };
+
+    match stitch {
+      Stitch::Full(mut fullstitch) => {
+        let size = match fullstitch.kind {
+          FullStitchKind::Full => 1.0,
+          FullStitchKind::Petite => 0.5,
+        };
+        (fullstitch.x, fullstitch.y) = square(fullstitch.x, fullstitch.y, size);
+        Stitch::Full(fullstitch)
+      }
+      Stitch::Part(mut partstitch) => {
+        match partstitch.kind {
+          PartStitchKind::Half => {
+            (partstitch.x, partstitch.y) = square(partstitch.x, partstitch.y, 1.0);
+            if self.flips_diagonals() {
+              partstitch.direction = match partstitch.direction {
+                PartStitchDirection::Forward => PartStitchDirection::Backward,
+                PartStitchDirection::Backward => PartStitchDirection::Forward,
+              };
+            }
+          }
+          PartStitchKind::Quarter => {
+            // The direction of a quarter stitch is defined by its position within the cell.
+            (partstitch.x, partstitch.y) = square(partstitch.x, partstitch.y, 0.5);
+            partstitch.direction = PartStitchDirection::from((partstitch.x, partstitch.y));
+          }
+        }
+        Stitch::Part(partstitch)
+      }
+      Stitch::Line(mut line) => {
+        let (x0, y0) = point(line.x.0, line.y.0);
+        let (x1, y1) = point(line.x.1, line.y.1);
+        (line.x, line.y) = ((x0, x1), (y0, y1));
+        Stitch::Line(line)
+      }
+      Stitch::Node(mut node) => {
+        (node.x, node.y) = point(node.x, node.y);
+        if let Self::Rotate(turns) = self {
+          node.rotated ^= turns % 2 == 1;
+        }
+        Stitch::Node(node)
+      }
+      Stitch::Special(mut special) => {
+        let Some(model) = models.get(special.modindex as usize) else {
+          (special.x, special.y) = point(special.x, special.y);
+          return Stitch::Special(self.transform_special_orientation(special));
+        };
+
+        // The model's box is transformed as a whole and the stitch is then anchored so that its new box matches it.
+        let (min_x, min_y, max_x, max_y) = special.aabb(model);
+        let (x1, y1) = self.transform_point(bounds, min_x, min_y);
+        let (x2, y2) = self.transform_point(bounds, max_x, max_y);
+
+        let special = self.transform_special_orientation(special);
+        let (offset_x, offset_y, _, _) = SpecialStitch {
+          x: Coord::new(0.0).unwrap(),
+          y: Coord::new(0.0).unwrap(),
+          ..special
+        }
+        .aabb(model);
+        Stitch::Special(SpecialStitch {
+          x: Coord::new(x1.min(x2) - offset_x).unwrap(),
+          y: Coord::new(y1.min(y2) - offset_y).unwrap(),
+          ..special
+        })
+      }
+    }
+  }
+
+  /// Returns the special stitch with its rotation and flip state adjusted to the transform.
+  /// The anchor point is left untouched.
+  fn transform_special_orientation(self, mut special: SpecialStitch) -> SpecialStitch {
+    match self {
+      Self::Move { .. } => {}
+      Self::MirrorHorizontal => {
+        special.rotation = (360 - special.rotation % 360) % 360;
+        special.flip.0 = !special.flip.0;
+      }
+      Self::MirrorVertical => {
+        special.rotation = (360 - special.rotation % 360) % 360;
+        special.flip.1 = !special.flip.1;
+      }
+      Self::Rotate(turns) => {
+        special.rotation = (special.rotation + 90 * u16::from(turns % 4)) % 360;
+      }
+    }
+    special
+  }
+}
+
+/// Specifies the kinds of stitches removed by the [`StitchAction::RemoveByKind`] action.
+#[derive(
+  Debug,
+  Default,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq,
+  borsh::BorshSerialize,
+  borsh::BorshDeserialize
+)]
+pub struct StitchKinds {
+  pub fullstitches: bool,
+  pub petitestitches: bool,
+
+  pub halfstitches: bool,
+  pub quarterstitches: bool,
+
+  pub backstitches: bool,
+  pub straightstitches: bool,
+
+  pub frenchknots: bool,
+  pub beads: bool,
+
+  pub specialstitches: bool,
+}
+
+impl StitchKinds {
+  /// Returns `true` if the kind of the given stitch is in the set.
+  #[must_use]
+  pub const fn contains(&self, stitch: &Stitch) -> bool {
+    match stitch {
+      Stitch::Full(fullstitch) => match fullstitch.kind {
+        FullStitchKind::Full => self.fullstitches,
+        FullStitchKind::Petite => self.petitestitches,
+      },
+      Stitch::Part(partstitch) => match partstitch.kind {
+        PartStitchKind::Half => self.halfstitches,
+        PartStitchKind::Quarter => self.quarterstitches,
+      },
+      Stitch::Line(line) => match line.kind {
+        LineStitchKind::Back => self.backstitches,
+        LineStitchKind::Straight => self.straightstitches,
+      },
+      Stitch::Node(node) => match node.kind {
+        NodeStitchKind::FrenchKnot => self.frenchknots,
+        NodeStitchKind::Bead => self.beads,
+      },
+      Stitch::Special(_) => self.specialstitches,
+    }
+  }
+}
+
+/// The largest width or height, in cells, the stitches can be scaled to.
+pub const MAX_SCALED_SIZE: u16 = 2_000;
+
+/// Returns the bounds scaled by the given factor around their top-left corner.
+#[must_use]
+pub fn scale_bounds(bounds: Bounds, factor: f32) -> Bounds {
+  let scale = |len: u16| (f32::from(len) * factor).round().max(1.0) as u16;
+  Bounds::new(bounds.x, bounds.y, scale(bounds.width), scale(bounds.height))
+}
+
+/// Returns the bounds scaled by the given factor, checking that the factor is a positive finite number
+/// and the scaled bounds do not exceed [`MAX_SCALED_SIZE`].
+pub fn checked_scale_bounds(bounds: Bounds, factor: f32) -> Result<Bounds> {
+  if !factor.is_finite() || factor <= 0.0 {
+    return Err(Error::InvalidScaleFactor(factor));
+  }
+  let scaled = scale_bounds(bounds, factor);
+  if scaled.width.max(scaled.height) > MAX_SCALED_SIZE {
+    return Err(Error::ScaleFactorTooLarge(factor));
+  }
+  Ok(scaled)
+}
+
+/// Returns the stitches scaled by the given factor around the top-left corner of their bounds.
+///
+/// Cell-based stitches are resampled using the nearest neighbour, so they may be duplicated or dropped.
+/// Line, node, and special stitches are moved, snapping their points to the nearest half of a cell.
+pub fn scale_stitches(bounds: Bounds, factor: f32, stitches: &[Stitch]) -> Result<Vec<Stitch>> {
+  let (ox, oy) = (f32::from(bounds.x), f32::from(bounds.y));
+  let coord = |value: f32| Coord::new(value).map_err(|_| Error::InvalidScaleFactor(factor));
+  let point = |x: Coord, y: Coord| {
+    let snap = |value: f32| (value * 2.0).round() / 2.0;
+    Ok((
+      coord(snap((*x - ox).mul_add(factor, ox)))?,
+      coord(snap((*y - oy).mul_add(factor, oy)))?,
+    ))
+  };
+  // Returns the target cells whose nearest source cell is the given one, on a grid with the given cell size.
+  let resample = |x: Coord, y: Coord, size: f32| {
+    let range = |value: f32, origin: f32| {
+      let cell = ((value - origin) / size).round();
+      let start = (cell * factor).ceil() as i32;
+      let end = ((cell + 1.0) * factor).ceil() as i32;
+      (start..end).map(move |cell| (cell as f32).mul_add(size, origin))
+    };
+    let xs: Vec<f32> = range(*x, ox).collect();
+    range(*y, oy).flat_map(move |y| xs.clone().into_iter().map(move |x| Ok((coord(x)?, coord(y)?))))
+  };
+
+  // The scaled stitches may overlap each other, so they are resolved the same way as on a layer.
+  let mut layer = Layer::default();
+  for &stitch in stitches {
+    match stitch {
+      Stitch::Full(fullstitch) => {
+        let size = match fullstitch.kind {
+          FullStitchKind::Full => 1.0,
+          FullStitchKind::Petite => 0.5,
+        };
+        for cell in resample(fullstitch.x, fullstitch.y, size) {
+          let (x, y) = cell?;
+          layer.add_stitch(Stitch::Full(FullStitch { x, y, ..fullstitch }));
+        }
+      }
+      Stitch::Part(partstitch) => {
+        let size = match partstitch.kind {
+          PartStitchKind::Half => 1.0,
+          PartStitchKind::Quarter => 0.5,
+        };
+        for cell in resample(partstitch.x, partstitch.y, size) {
+          let (x, y) = cell?;
+          let direction = match partstitch.kind {
+            PartStitchKind::Half => partstitch.direction,
+            // The direction of a quarter stitch is defined by its position within the cell.
+            PartStitchKind::Quarter => PartStitchDirection::from((x, y)),
+          };
+          layer.add_stitch(Stitch::Part(PartStitch {
+            x,
+            y,
+            direction,
+            ..partstitch
+          }));
+        }
+      }
+      Stitch::Line(mut line) => {
+        let (x0, y0) = point(line.x.0, line.y.0)?;
+        let (x1, y1) = point(line.x.1, line.y.1)?;
+        // Lines shrunk to a point are dropped.
+        if (x0, y0) != (x1, y1) {
+          (line.x, line.y) = ((x0, x1), (y0, y1));
+          layer.add_stitch(Stitch::Line(line));
+        }
+      }
+      Stitch::Node(mut node) => {
+        (node.x, node.y) = point(node.x, node.y)?;
+        layer.add_stitch(Stitch::Node(node));
+      }
+      Stitch::Special(mut special) => {
+        (special.x, special.y) = point(special.x, special.y)?;
+        layer.add_stitch(Stitch::Special(special));
+      }
+    }
+  }
+  Ok(layer.get_stitches())
+}
+
 #[derive(Debug, Clone, borsh::BorshSerialize, borsh::BorshDeserialize)]
 pub enum StitchAction {
   Add {
@@ -25,9 +411,127 @@ pub enum StitchAction {
     y: f32,
     removed_stitches: Option<Vec<Stitch>>,
   },
+  Fill {
+    layer_index: u32,
+    x: f32,
+    y: f32,
+    palindex: u32,
+    filled_stitches: Option<Vec<Stitch>>,
+    replaced_stitches: Option<Vec<Stitch>>,
+  },
+  AddShape {
+    layer_index: u32,
+    shape: StitchShape,
+    start: (u16, u16),
+    end: (u16, u16),
+    filled: bool,
+    palindex: u32,
+    added_stitches: Option<Vec<Stitch>>,
+    conflicts: Option<Vec<Stitch>>,
+  },
+  AddMany {
+    layer_index: u32,
+    stitches: Vec<Stitch>,
+    /// Conflicts of every added stitch, in the same order as `stitches`.
+    conflicts: Option<Vec<Vec<Stitch>>>,
+  },
+  RemoveMany {
+    layer_index: u32,
+    target_stitches: Vec<Stitch>,
+    actual_stitches: Option<Vec<Stitch>>,
+  },
+  Paste {
+    layer_index: u32,
+    x: u16,
+    y: u16,
+    clipboard: Clipboard,
+    pasted_stitches: Option<Vec<Stitch>>,
+    /// Conflicts of every pasted stitch, in the same order as `pasted_stitches`.
+    conflicts: Option<Vec<Vec<Stitch>>>,
+    /// The number of palette items and special stitch models appended to the pattern.
+    added_items: Option<(u32, u32)>,
+  },
+  Transform {
+    layer_index: u32,
+    stitches: Vec<Stitch>,
+    bounds: Bounds,
+    transform: StitchTransform,
+    removed_stitches: Option<Vec<Stitch>>,
+    transformed_stitches: Option<Vec<Stitch>>,
+    /// Conflicts of every transformed stitch, in the same order as `transformed_stitches`.
+    conflicts: Option<Vec<Vec<Stitch>>>,
+  },
+  /// Scales the stitches by the given factor around the top-left corner of their bounds.
+  Scale {
+    layer_index: u32,
+    stitches: Vec<Stitch>,
+    bounds: Bounds,
+    factor: f32,
+    removed_stitches: Option<Vec<Stitch>>,
+    scaled_stitches: Option<Vec<Stitch>>,
+    /// Conflicts of every scaled stitch, in the same order as `scaled_stitches`.
+    conflicts: Option<Vec<Vec<Stitch>>>,
+  },
+  /// Moves the stitches from one layer to another, replacing the conflicting stitches of the target layer.
+  MoveToLayer {
+    from_layer_index: u32,
+    to_layer_index: u32,
+    stitches: Vec<Stitch>,
+    moved_stitches: Option<Vec<Stitch>>,
+    /// Conflicts of every moved stitch on the target layer, in the same order as `moved_stitches`.
+    conflicts: Option<Vec<Vec<Stitch>>>,
+  },
+  /// Removes every stitch of the given kinds from the layer.
+  RemoveByKind {
+    layer_index: u32,
+    kinds: StitchKinds,
+    removed_stitches: Option<Vec<Stitch>>,
+  },
 }
 
 impl StitchAction {
+  /// Returns a human-readable description of the action.
+  #[must_use]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::Add { .. } => "Add stitch",
+      Self::Remove { .. } => "Remove stitch",
+      Self::RemoveAt { .. } => "Remove stitches at position",
+      Self::Fill { .. } => "Fill area",
+      Self::AddShape { .. } => "Draw shape",
+      Self::AddMany { .. } => "Add stitches",
+      Self::RemoveMany { .. } => "Remove stitches",
+      Self::Paste { .. } => "Paste stitches",
+      Self::Transform { .. } => "Transform stitches",
+      Self::Scale { .. } => "Scale stitches",
+      Self::MoveToLayer { .. } => "Move stitches to layer",
+      Self::RemoveByKind { .. } => "Remove stitches by kind",
+    }
+  }
+
+  /// Returns the actual indexes of the layers whose stitches the action changes.
+  #[must_use]
+  pub fn layer_indexes(&self) -> Vec<u32> {
+    match self {
+      Self::Add { layer_index, .. }
+      | Self::AddMany { layer_index, .. }
+      | Self::Remove { layer_index, .. }
+      | Self::RemoveMany { layer_index, .. }
+      | Self::RemoveByKind { layer_index, .. }
+      | Self::RemoveAt { layer_index, .. }
+      | Self::Fill { layer_index, .. }
+      | Self::AddShape { layer_index, .. }
+      | Self::Paste { layer_index, .. }
+      | Self::Transform { layer_index, .. }
+      | Self::Scale { layer_index, .. } => vec![*layer_index],
+      Self::MoveToLayer {
+        from_layer_index,
+        to_layer_index,
+        ..
+      } => vec![*from_layer_index, *to_layer_index],
+    }
+  }
+
   pub fn perform(&mut self, embproj: &mut EmbroiderlyProject) -> Result<Vec<EditorEvent>> {
     match self {
       Self::Add {
@@ -81,6 +585,385 @@ impl StitchAction {
           return Ok(vec![]);
         }
 
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: removed,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::Fill {
+        layer_index,
+        x,
+        y,
+        palindex,
+        filled_stitches,
+        replaced_stitches,
+      } => {
+        let (filled, replaced) = embproj.pattern.fill_region(*layer_index, *x, *y, *palindex);
+        filled_stitches.get_or_insert_with(|| filled.clone());
+        replaced_stitches.get_or_insert_with(|| replaced.clone());
+
+        if filled.is_empty() {
+          return Ok(vec![]);
+        }
+
+        Ok(vec![
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: filled,
+          },
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: replaced,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::AddShape {
+        layer_index,
+        shape,
+        start,
+        end,
+        filled,
+        palindex,
+        added_stitches,
+        conflicts,
+      } => {
+        let (width, height) = (embproj.pattern.fabric.width, embproj.pattern.fabric.height);
+        let added: Vec<Stitch> = shape
+          .cells(*start, *end, *filled)
+          .into_iter()
+          .filter(|&(x, y)| x < width && y < height)
+          .map(|(x, y)| {
+            Stitch::Full(FullStitch {
+              x: Coord::from(x),
+              y: Coord::from(y),
+              palindex: *palindex,
+              kind: FullStitchKind::Full,
+            })
+          })
+          .collect();
+        let removed: Vec<Stitch> = added
+          .iter()
+          .flat_map(|&stitch| embproj.pattern.add_stitch(*layer_index, stitch))
+          .collect();
+        added_stitches.get_or_insert_with(|| added.clone());
+        conflicts.get_or_insert_with(|| removed.clone());
+
+        if added.is_empty() {
+          return Ok(vec![]);
+        }
+
+        Ok(vec![
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: added,
+          },
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: removed,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::AddMany {
+        layer_index,
+        stitches,
+        conflicts,
+      } => {
+        let removed: Vec<Vec<Stitch>> = stitches
+          .iter()
+          .map(|&stitch| embproj.pattern.add_stitch(*layer_index, stitch))
+          .collect();
+        let events = vec![
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: stitches.clone(),
+          },
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: removed.iter().flatten().copied().collect(),
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ];
+        conflicts.get_or_insert(removed);
+        Ok(events)
+      }
+      Self::RemoveMany {
+        layer_index,
+        target_stitches,
+        actual_stitches,
+      } => {
+        let removed: Vec<Stitch> = target_stitches
+          .iter()
+          .filter_map(|&stitch| embproj.pattern.remove_stitch(*layer_index, stitch))
+          .collect();
+        actual_stitches.get_or_insert_with(|| removed.clone());
+
+        if removed.is_empty() {
+          return Ok(vec![]);
+        }
+
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: removed,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::Paste {
+        layer_index,
+        x,
+        y,
+        clipboard,
+        pasted_stitches,
+        conflicts,
+        added_items,
+      } => {
+        let pattern = &mut embproj.pattern;
+        let mut events = Vec::new();
+
+        // Reuse the palette items the target pattern already has and append the missing ones.
+        let palindexes: Vec<u32> = clipboard
+          .palette
+          .iter()
+          .map(|palitem| {
+            if let Some(palindex) = pattern.palette.iter().position(|item| item == palitem) {
+              return palindex as u32;
+            }
+            let palindex = pattern.palette.push(palitem.clone());
+            events.push(EditorEvent::PaletteAddItem {
+              palitem: palitem.clone(),
+              palindex,
+            });
+            palindex
+          })
+          .collect();
+        let palitems_added = events.len() as u32;
+
+        let models_len = pattern.special_stitch_models.len();
+        let modindexes: Vec<u32> = clipboard
+          .special_stitch_models
+          .iter()
+          .map(|model| {
+            let modindex = pattern
+              .special_stitch_models
+              .iter()
+              .position(|m| m.unique_name == model.unique_name)
+              .unwrap_or_else(|| {
+                pattern.special_stitch_models.push(model.clone());
+                pattern.special_stitch_models.len() - 1
+              });
+            modindex as u32
+          })
+          .collect();
+        let models_added = (pattern.special_stitch_models.len() - models_len) as u32;
+
+        let bounds = Bounds::new(0, 0, pattern.fabric.width, pattern.fabric.height);
+        let stitches: Vec<Stitch> = clipboard
+          .stitches
+          .iter()
+          .map(|&stitch| {
+            let mut stitch = translate_stitch(stitch, f32::from(*x), f32::from(*y));
+            let palindex = palindexes[stitch_palindex(&stitch) as usize];
+            set_stitch_palindex(&mut stitch, palindex);
+            if let Stitch::Special(special) = &mut stitch {
+              special.modindex = modindexes[special.modindex as usize];
+            }
+            stitch
+          })
+          .filter(|stitch| is_stitch_within_bounds(stitch, bounds, &pattern.special_stitch_models))
+          .collect();
+        let removed: Vec<Vec<Stitch>> = stitches
+          .iter()
+          .map(|&stitch| pattern.add_stitch(*layer_index, stitch))
+          .collect();
+
+        pasted_stitches.get_or_insert_with(|| stitches.clone());
+        conflicts.get_or_insert_with(|| removed.clone());
+        added_items.get_or_insert((palitems_added, models_added));
+
+        if stitches.is_empty() && events.is_empty() {
+          return Ok(vec![]);
+        }
+
+        events.extend([
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches,
+          },
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: removed.into_iter().flatten().collect(),
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ]);
+        Ok(events)
+      }
+      Self::Transform {
+        layer_index,
+        stitches,
+        bounds,
+        transform,
+        removed_stitches,
+        transformed_stitches,
+        conflicts,
+      } => {
+        let pattern = &mut embproj.pattern;
+        let removed: Vec<Stitch> = stitches
+          .iter()
+          .filter_map(|&stitch| pattern.remove_stitch(*layer_index, stitch))
+          .collect();
+
+        // Stitches moved outside the fabric are dropped.
+        let fabric_bounds = Bounds::new(0, 0, pattern.fabric.width, pattern.fabric.height);
+        let transformed: Vec<Stitch> = removed
+          .iter()
+          .map(|&stitch| transform.transform_stitch(*bounds, stitch, &pattern.special_stitch_models))
+          .filter(|stitch| is_stitch_within_bounds(stitch, fabric_bounds, &pattern.special_stitch_models))
+          .collect();
+        let replaced: Vec<Vec<Stitch>> = transformed
+          .iter()
+          .map(|&stitch| pattern.add_stitch(*layer_index, stitch))
+          .collect();
+
+        removed_stitches.get_or_insert_with(|| removed.clone());
+        transformed_stitches.get_or_insert_with(|| transformed.clone());
+        conflicts.get_or_insert_with(|| replaced.clone());
+
+        if removed.is_empty() {
+          return Ok(vec![]);
+        }
+
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: removed,
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: transformed,
+          },
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: replaced.into_iter().flatten().collect(),
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::Scale {
+        layer_index,
+        stitches,
+        bounds,
+        factor,
+        removed_stitches,
+        scaled_stitches,
+        conflicts,
+      } => {
+        checked_scale_bounds(*bounds, *factor)?;
+
+        let pattern = &mut embproj.pattern;
+        let removed: Vec<Stitch> = stitches
+          .iter()
+          .filter_map(|&stitch| pattern.remove_stitch(*layer_index, stitch))
+          .collect();
+
+        // Stitches scaled outside the fabric are dropped.
+        let fabric_bounds = Bounds::new(0, 0, pattern.fabric.width, pattern.fabric.height);
+        let scaled: Vec<Stitch> = scale_stitches(*bounds, *factor, &removed)?
+          .into_iter()
+          .filter(|stitch| is_stitch_within_bounds(stitch, fabric_bounds, &pattern.special_stitch_models))
+          .collect();
+        let replaced: Vec<Vec<Stitch>> = scaled
+          .iter()
+          .map(|&stitch| pattern.add_stitch(*layer_index, stitch))
+          .collect();
+
+        removed_stitches.get_or_insert_with(|| removed.clone());
+        scaled_stitches.get_or_insert_with(|| scaled.clone());
+        conflicts.get_or_insert_with(|| replaced.clone());
+
+        if removed.is_empty() {
+          return Ok(vec![]);
+        }
+
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: removed,
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: scaled,
+          },
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: replaced.into_iter().flatten().collect(),
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::MoveToLayer {
+        from_layer_index,
+        to_layer_index,
+        stitches,
+        moved_stitches,
+        conflicts,
+      } => {
+        let pattern = &mut embproj.pattern;
+        let moved: Vec<Stitch> = stitches
+          .iter()
+          .filter_map(|&stitch| pattern.remove_stitch(*from_layer_index, stitch))
+          .collect();
+        let replaced: Vec<Vec<Stitch>> = moved
+          .iter()
+          .map(|&stitch| pattern.add_stitch(*to_layer_index, stitch))
+          .collect();
+
+        moved_stitches.get_or_insert_with(|| moved.clone());
+        conflicts.get_or_insert_with(|| replaced.clone());
+
+        if moved.is_empty() {
+          return Ok(vec![]);
+        }
+
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *from_layer_index,
+            stitches: moved.clone(),
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *to_layer_index,
+            stitches: moved,
+          },
+          EditorEvent::StitchesRemove {
+            layer_index: *to_layer_index,
+            stitches: replaced.into_iter().flatten().collect(),
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::RemoveByKind {
+        layer_index,
+        kinds,
+        removed_stitches,
+      } => {
+        let layer = &mut embproj.pattern.layers[*layer_index];
+        let removed: Vec<Stitch> = layer
+          .get_stitches()
+          .into_iter()
+          .filter(|stitch| kinds.contains(stitch))
+          .collect();
+        layer.remove_stitches(removed.clone());
+        removed_stitches.get_or_insert_with(|| removed.clone());
+
+        if removed.is_empty() {
+          return Ok(vec![]);
+        }
+
         Ok(vec![
           EditorEvent::StitchesRemove {
             layer_index: *layer_index,
@@ -133,6 +1016,11 @@ impl StitchAction {
         layer_index,
         removed_stitches,
         ..
+      }
+      | Self::RemoveByKind {
+        layer_index,
+        removed_stitches,
+        ..
       } => {
         let saved = removed_stitches.take().ok_or(Error::ActionNotPerformed)?;
         if saved.is_empty() {
@@ -149,6 +1037,228 @@ impl StitchAction {
           EditorEvent::PatternChanged(embproj.id),
         ])
       }
+      Self::Fill {
+        layer_index,
+        filled_stitches,
+        replaced_stitches,
+        ..
+      } => {
+        let filled = filled_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        let replaced = replaced_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        if filled.is_empty() {
+          return Ok(vec![]);
+        }
+
+        embproj.pattern.remove_stitches(*layer_index, filled.clone());
+        embproj.pattern.add_stitches(*layer_index, replaced.clone());
+
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: filled,
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: replaced,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::AddShape {
+        layer_index,
+        added_stitches,
+        conflicts,
+        ..
+      } => {
+        let added = added_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        let saved = conflicts.take().ok_or(Error::ActionNotPerformed)?;
+        if added.is_empty() {
+          return Ok(vec![]);
+        }
+
+        embproj.pattern.remove_stitches(*layer_index, added.clone());
+        embproj.pattern.add_stitches(*layer_index, saved.clone());
+
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: added,
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: saved,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::AddMany {
+        layer_index,
+        stitches,
+        conflicts,
+      } => {
+        let saved = conflicts.take().ok_or(Error::ActionNotPerformed)?;
+        // Revert in reverse order, so that the stitches that conflicted with each other within the batch are restored correctly.
+        for (&stitch, conflicts) in stitches.iter().zip(saved.iter()).rev() {
+          embproj.pattern.remove_stitch(*layer_index, stitch);
+          embproj.pattern.add_stitches(*layer_index, conflicts.clone());
+        }
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: stitches.clone(),
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: saved.into_iter().flatten().collect(),
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::RemoveMany {
+        layer_index,
+        actual_stitches,
+        ..
+      } => {
+        let saved = actual_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        if saved.is_empty() {
+          return Ok(vec![]);
+        }
+
+        embproj.pattern.add_stitches(*layer_index, saved.clone());
+
+        Ok(vec![
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: saved,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::Paste {
+        layer_index,
+        pasted_stitches,
+        conflicts,
+        added_items,
+        ..
+      } => {
+        let stitches = pasted_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        let saved = conflicts.take().ok_or(Error::ActionNotPerformed)?;
+        let (palitems_added, models_added) = added_items.take().ok_or(Error::ActionNotPerformed)?;
+        let pattern = &mut embproj.pattern;
+
+        for (&stitch, conflicts) in stitches.iter().zip(saved.iter()).rev() {
+          pattern.remove_stitch(*layer_index, stitch);
+          pattern.add_stitches(*layer_index, conflicts.clone());
+        }
+
+        let palette_len = pattern.palette.len() as u32;
+        let removed_palindexes: Vec<u32> = (palette_len - palitems_added..palette_len).collect();
+        for _ in 0..palitems_added {
+          pattern.palette.pop();
+        }
+        let models_len = pattern.special_stitch_models.len() - models_added as usize;
+        pattern.special_stitch_models.truncate(models_len);
+
+        if stitches.is_empty() && removed_palindexes.is_empty() {
+          return Ok(vec![]);
+        }
+
+        let mut events = vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches,
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: saved.into_iter().flatten().collect(),
+          },
+        ];
+        if !removed_palindexes.is_empty() {
+          events.push(EditorEvent::PaletteRemoveItems(removed_palindexes));
+        }
+        events.push(EditorEvent::PatternChanged(embproj.id));
+        Ok(events)
+      }
+      Self::Transform {
+        layer_index,
+        removed_stitches,
+        transformed_stitches,
+        conflicts,
+        ..
+      }
+      | Self::Scale {
+        layer_index,
+        removed_stitches,
+        scaled_stitches: transformed_stitches,
+        conflicts,
+        ..
+      } => {
+        let removed = removed_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        let transformed = transformed_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        let saved = conflicts.take().ok_or(Error::ActionNotPerformed)?;
+        if removed.is_empty() {
+          return Ok(vec![]);
+        }
+
+        let pattern = &mut embproj.pattern;
+        for (&stitch, conflicts) in transformed.iter().zip(saved.iter()).rev() {
+          pattern.remove_stitch(*layer_index, stitch);
+          pattern.add_stitches(*layer_index, conflicts.clone());
+        }
+        pattern.add_stitches(*layer_index, removed.clone());
+
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *layer_index,
+            stitches: transformed,
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: saved.into_iter().flatten().collect(),
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *layer_index,
+            stitches: removed,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
+      Self::MoveToLayer {
+        from_layer_index,
+        to_layer_index,
+        moved_stitches,
+        conflicts,
+        ..
+      } => {
+        let moved = moved_stitches.take().ok_or(Error::ActionNotPerformed)?;
+        let saved = conflicts.take().ok_or(Error::ActionNotPerformed)?;
+        if moved.is_empty() {
+          return Ok(vec![]);
+        }
+
+        let pattern = &mut embproj.pattern;
+        for (&stitch, conflicts) in moved.iter().zip(saved.iter()).rev() {
+          pattern.remove_stitch(*to_layer_index, stitch);
+          pattern.add_stitches(*to_layer_index, conflicts.clone());
+        }
+        pattern.add_stitches(*from_layer_index, moved.clone());
+
+        Ok(vec![
+          EditorEvent::StitchesRemove {
+            layer_index: *to_layer_index,
+            stitches: moved.clone(),
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *to_layer_index,
+            stitches: saved.into_iter().flatten().collect(),
+          },
+          EditorEvent::StitchesAdd {
+            layer_index: *from_layer_index,
+            stitches: moved,
+          },
+          EditorEvent::PatternChanged(embproj.id),
+        ])
+      }
     }
   }
 }
@@ -1,7 +1,9 @@
 use embroiderly_pattern::*;
 
 use crate::actions::StitchAction;
-use crate::{EditorAction, EditorEvent};
+use crate::actions::stitches::{StitchKinds, StitchShape, StitchTransform, scale_stitches};
+use crate::error::Error;
+use crate::{Clipboard, EditorAction, EditorEvent};
 
 fn create_pattern_project() -> EmbroiderlyProject {
   let mut embproj = EmbroiderlyProject::default();
@@ -281,3 +283,960 @@ fn test_remove_stitch() {
     assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
   }
 }
+
+#[test]
+fn test_fill_replaces_region_and_restores_it() {
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 4,
+    height: 4,
+    ..Fabric::default()
+  }));
+  // A 2x2 block of palindex 1 in the top-left corner.
+  for (x, y) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+    embproj.pattern.add_stitch(
+      0,
+      Stitch::Full(FullStitch {
+        x: Coord::new(x).unwrap(),
+        y: Coord::new(y).unwrap(),
+        palindex: 1,
+        kind: FullStitchKind::Full,
+      }),
+    );
+  }
+  let mut action = EditorAction::Stitch(StitchAction::Fill {
+    layer_index: 0,
+    x: 0.5,
+    y: 0.5,
+    palindex: 2,
+    filled_stitches: None,
+    replaced_stitches: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    let EditorEvent::StitchesAdd { layer_index, stitches } = &events[0] else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(layer_index, &0);
+    assert_eq!(stitches.len(), 4);
+
+    let EditorEvent::StitchesRemove { layer_index, stitches } = &events[1] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(layer_index, &0);
+    assert_eq!(stitches.len(), 4);
+
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 4);
+    assert!(embproj.pattern.layers[0].fullstitches.iter().all(|s| s.palindex == 2));
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the command.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    let EditorEvent::StitchesRemove { stitches, .. } = &events[0] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(stitches.len(), 4);
+
+    let EditorEvent::StitchesAdd { stitches, .. } = &events[1] else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(stitches.len(), 4);
+
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 4);
+    assert!(embproj.pattern.layers[0].fullstitches.iter().all(|s| s.palindex == 1));
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+}
+
+#[test]
+fn test_fill_with_same_color_does_nothing() {
+  let mut embproj = EmbroiderlyProject::default();
+  embproj.pattern.add_stitch(
+    0,
+    Stitch::Full(FullStitch {
+      x: Coord::new(0.0).unwrap(),
+      y: Coord::new(0.0).unwrap(),
+      palindex: 1,
+      kind: FullStitchKind::Full,
+    }),
+  );
+  let mut action = EditorAction::Stitch(StitchAction::Fill {
+    layer_index: 0,
+    x: 0.0,
+    y: 0.0,
+    palindex: 1,
+    filled_stitches: None,
+    replaced_stitches: None,
+  });
+
+  assert!(action.perform(&mut embproj).unwrap().is_empty());
+  assert!(action.revoke(&mut embproj).unwrap().is_empty());
+  assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 1);
+}
+
+#[test]
+fn test_rectangle_shape_cells() {
+  let filled = StitchShape::Rectangle.cells((3, 2), (0, 0), true);
+  assert_eq!(filled.len(), 12);
+
+  let outline = StitchShape::Rectangle.cells((0, 0), (3, 2), false);
+  assert_eq!(outline.len(), 10);
+  assert!(!outline.contains(&(1, 1)));
+  assert!(!outline.contains(&(2, 1)));
+}
+
+#[test]
+fn test_ellipse_shape_cells() {
+  let filled = StitchShape::Ellipse.cells((0, 0), (4, 4), true);
+  // The corners of the bounding box lie outside the inscribed circle.
+  assert!(!filled.contains(&(0, 0)));
+  assert!(!filled.contains(&(4, 4)));
+  assert!(filled.contains(&(2, 0)));
+  assert!(filled.contains(&(2, 2)));
+
+  let outline = StitchShape::Ellipse.cells((0, 0), (4, 4), false);
+  assert!(outline.len() < filled.len());
+  assert!(outline.contains(&(2, 0)));
+  assert!(!outline.contains(&(2, 2)));
+  assert!(outline.iter().all(|cell| filled.contains(cell)));
+}
+
+#[test]
+fn test_add_shape_and_revoke() {
+  let mut embproj = create_pattern_project();
+  let mut action = EditorAction::Stitch(StitchAction::AddShape {
+    layer_index: 0,
+    shape: StitchShape::Rectangle,
+    start: (0, 0),
+    end: (2, 2),
+    filled: false,
+    palindex: 1,
+    added_stitches: None,
+    conflicts: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    let EditorEvent::StitchesAdd { layer_index, stitches } = &events[0] else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(layer_index, &0);
+    assert_eq!(stitches.len(), 8);
+
+    // The petites and quarters at (0, 0) conflict with the new full stitch.
+    let EditorEvent::StitchesRemove { layer_index, stitches } = &events[1] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(layer_index, &0);
+    assert_eq!(stitches.len(), 4);
+
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 8);
+    assert_eq!(embproj.pattern.layers[0].partstitches.len(), 0);
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the command.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    let EditorEvent::StitchesRemove { stitches, .. } = &events[0] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(stitches.len(), 8);
+
+    let EditorEvent::StitchesAdd { stitches, .. } = &events[1] else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(stitches.len(), 4);
+
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 2);
+    assert_eq!(embproj.pattern.layers[0].partstitches.len(), 2);
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+}
+
+#[test]
+fn test_add_shape_clips_to_fabric() {
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 2,
+    height: 2,
+    ..Fabric::default()
+  }));
+  let mut action = EditorAction::Stitch(StitchAction::AddShape {
+    layer_index: 0,
+    shape: StitchShape::Rectangle,
+    start: (0, 0),
+    end: (5, 5),
+    filled: true,
+    palindex: 0,
+    added_stitches: None,
+    conflicts: None,
+  });
+
+  action.perform(&mut embproj).unwrap();
+
+  assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 4);
+}
+
+#[test]
+fn test_add_many_stitches_and_revoke() {
+  let mut embproj = create_pattern_project();
+  let full = |x: f32, palindex: u32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(0.0).unwrap(),
+      palindex,
+      kind: FullStitchKind::Full,
+    })
+  };
+  // The last stitch replaces the first one within the same batch.
+  let stitches = vec![full(0.0, 1), full(1.0, 1), full(0.0, 2)];
+  let mut action = EditorAction::Stitch(StitchAction::AddMany {
+    layer_index: 0,
+    stitches: stitches.clone(),
+    conflicts: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    let EditorEvent::StitchesAdd {
+      layer_index,
+      stitches: added,
+    } = &events[0]
+    else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(layer_index, &0);
+    assert_eq!(added, &stitches);
+
+    // All the partial stitches in the cell (0, 0), plus the replaced full stitch.
+    let EditorEvent::StitchesRemove { stitches: removed, .. } = &events[1] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(removed.len(), 5);
+
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 2);
+    assert!(embproj.pattern.layers[0].partstitches.is_empty());
+    assert!(embproj.pattern.contains_stitch(0, &full(0.0, 2)));
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the command.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    let EditorEvent::StitchesRemove { stitches: removed, .. } = &events[0] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(removed, &stitches);
+
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 2);
+    assert_eq!(embproj.pattern.layers[0].partstitches.len(), 2);
+    assert!(!embproj.pattern.contains_stitch(0, &full(0.0, 1)));
+    assert!(!embproj.pattern.contains_stitch(0, &full(1.0, 1)));
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+}
+
+#[test]
+fn test_remove_many_stitches_and_revoke() {
+  let mut embproj = create_pattern_project();
+  let petite = Stitch::Full(FullStitch {
+    x: Coord::new(0.0).unwrap(),
+    y: Coord::new(0.0).unwrap(),
+    palindex: 0,
+    kind: FullStitchKind::Petite,
+  });
+  let quarter = Stitch::Part(PartStitch {
+    x: Coord::new(0.5).unwrap(),
+    y: Coord::new(0.5).unwrap(),
+    palindex: 0,
+    kind: PartStitchKind::Quarter,
+    direction: PartStitchDirection::Backward,
+  });
+  let missing = Stitch::Full(FullStitch {
+    x: Coord::new(5.0).unwrap(),
+    y: Coord::new(5.0).unwrap(),
+    palindex: 0,
+    kind: FullStitchKind::Full,
+  });
+  let mut action = EditorAction::Stitch(StitchAction::RemoveMany {
+    layer_index: 0,
+    target_stitches: vec![petite, quarter, missing],
+    actual_stitches: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    let EditorEvent::StitchesRemove { layer_index, stitches } = &events[0] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(layer_index, &0);
+    assert_eq!(stitches, &vec![petite, quarter]);
+
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 1);
+    assert_eq!(embproj.pattern.layers[0].partstitches.len(), 1);
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  // Test revoking the command.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    let EditorEvent::StitchesAdd { stitches, .. } = &events[0] else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(stitches, &vec![petite, quarter]);
+
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 2);
+    assert_eq!(embproj.pattern.layers[0].partstitches.len(), 2);
+
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+}
+
+// Actions are persisted in the journal, so the variants existing before must keep their borsh discriminants.
+#[test]
+fn test_stitch_action_discriminants_are_stable() {
+  let remove_at = StitchAction::RemoveAt {
+    layer_index: 0,
+    x: 0.0,
+    y: 0.0,
+    removed_stitches: None,
+  };
+  let fill = StitchAction::Fill {
+    layer_index: 0,
+    x: 0.0,
+    y: 0.0,
+    palindex: 0,
+    filled_stitches: None,
+    replaced_stitches: None,
+  };
+  assert_eq!(borsh::to_vec(&remove_at).unwrap()[0], 2);
+  assert_eq!(borsh::to_vec(&fill).unwrap()[0], 3);
+}
+
+#[test]
+fn test_paste_clips_to_fabric_and_revoke() {
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 3,
+    height: 3,
+    ..Fabric::default()
+  }));
+  let full = |x: f32, y: f32, palindex: u32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex,
+      kind: FullStitchKind::Full,
+    })
+  };
+  let palitem = PaletteItem {
+    brand: String::from("DMC"),
+    number: String::from("310"),
+    name: String::from("Black"),
+    color: String::from("2C3225"),
+    blends: None,
+    symbol: None,
+  };
+  embproj.pattern.add_stitch(0, full(2.0, 2.0, 0));
+
+  let clipboard = Clipboard {
+    width: 2,
+    height: 2,
+    stitches: vec![full(0.0, 0.0, 0), full(1.0, 1.0, 0)],
+    palette: vec![palitem.clone()],
+    special_stitch_models: vec![],
+  };
+  let mut action = EditorAction::Stitch(StitchAction::Paste {
+    layer_index: 0,
+    x: 2,
+    y: 2,
+    clipboard,
+    pasted_stitches: None,
+    conflicts: None,
+    added_items: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    assert!(matches!(&events[0], EditorEvent::PaletteAddItem { palindex: 0, .. }));
+
+    // The second stitch falls outside the fabric.
+    let EditorEvent::StitchesAdd { stitches, .. } = &events[1] else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(stitches, &vec![full(2.0, 2.0, 0)]);
+
+    assert_eq!(embproj.pattern.palette.len(), 1);
+    assert_eq!(embproj.pattern.palette[0], palitem);
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 1);
+  }
+
+  // Test revoking the command.
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    assert!(matches!(&events[2], EditorEvent::PaletteRemoveItems(palindexes) if palindexes == &vec![0]));
+
+    assert!(embproj.pattern.palette.is_empty());
+    assert!(embproj.pattern.contains_stitch(0, &full(2.0, 2.0, 0)));
+  }
+}
+
+#[test]
+fn test_mirror_stitches_horizontally() {
+  let bounds = Bounds::new(0, 0, 3, 2);
+  let transform = StitchTransform::MirrorHorizontal;
+  let coord = |value: f32| Coord::new(value).unwrap();
+
+  let petite = Stitch::Full(FullStitch {
+    x: coord(0.5),
+    y: coord(0.0),
+    palindex: 0,
+    kind: FullStitchKind::Petite,
+  });
+  assert_eq!(
+    transform.transform_stitch(bounds, petite, &[]),
+    Stitch::Full(FullStitch {
+      x: coord(2.0),
+      y: coord(0.0),
+      palindex: 0,
+      kind: FullStitchKind::Petite,
+    })
+  );
+
+  let half = Stitch::Part(PartStitch {
+    x: coord(0.0),
+    y: coord(1.0),
+    palindex: 0,
+    direction: PartStitchDirection::Forward,
+    kind: PartStitchKind::Half,
+  });
+  assert_eq!(
+    transform.transform_stitch(bounds, half, &[]),
+    Stitch::Part(PartStitch {
+      x: coord(2.0),
+      y: coord(1.0),
+      palindex: 0,
+      direction: PartStitchDirection::Backward,
+      kind: PartStitchKind::Half,
+    })
+  );
+
+  let quarter = Stitch::Part(PartStitch {
+    x: coord(0.0),
+    y: coord(0.0),
+    palindex: 0,
+    direction: PartStitchDirection::Backward,
+    kind: PartStitchKind::Quarter,
+  });
+  assert_eq!(
+    transform.transform_stitch(bounds, quarter, &[]),
+    Stitch::Part(PartStitch {
+      x: coord(2.5),
+      y: coord(0.0),
+      palindex: 0,
+      direction: PartStitchDirection::Forward,
+      kind: PartStitchKind::Quarter,
+    })
+  );
+
+  let line = Stitch::Line(LineStitch {
+    x: (coord(0.0), coord(1.0)),
+    y: (coord(0.0), coord(2.0)),
+    palindex: 0,
+    kind: LineStitchKind::Back,
+  });
+  assert_eq!(
+    transform.transform_stitch(bounds, line, &[]),
+    Stitch::Line(LineStitch {
+      x: (coord(3.0), coord(2.0)),
+      y: (coord(0.0), coord(2.0)),
+      palindex: 0,
+      kind: LineStitchKind::Back,
+    })
+  );
+
+  let special = Stitch::Special(SpecialStitch {
+    x: coord(1.0),
+    y: coord(0.0),
+    rotation: 30,
+    flip: (false, false),
+    palindex: 0,
+    modindex: 0,
+  });
+  assert_eq!(
+    transform.transform_stitch(bounds, special, &[]),
+    Stitch::Special(SpecialStitch {
+      x: coord(2.0),
+      y: coord(0.0),
+      rotation: 330,
+      flip: (true, false),
+      palindex: 0,
+      modindex: 0,
+    })
+  );
+}
+
+#[test]
+fn test_rotate_stitches_clockwise() {
+  // A horizontal row of three cells turns into a vertical column around the same center.
+  let bounds = Bounds::new(2, 2, 3, 1);
+  let transform = StitchTransform::Rotate(1);
+  let coord = |value: f32| Coord::new(value).unwrap();
+
+  assert_eq!(transform.transform_bounds(bounds), Bounds::new(3, 1, 1, 3));
+
+  let full = |x: f32, y: f32| {
+    Stitch::Full(FullStitch {
+      x: coord(x),
+      y: coord(y),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    })
+  };
+  assert_eq!(transform.transform_stitch(bounds, full(2.0, 2.0), &[]), full(3.0, 1.0));
+  assert_eq!(transform.transform_stitch(bounds, full(4.0, 2.0), &[]), full(3.0, 3.0));
+
+  let half = |x: f32, y: f32, direction: PartStitchDirection| {
+    Stitch::Part(PartStitch {
+      x: coord(x),
+      y: coord(y),
+      palindex: 0,
+      direction,
+      kind: PartStitchKind::Half,
+    })
+  };
+  assert_eq!(
+    transform.transform_stitch(bounds, half(3.0, 2.0, PartStitchDirection::Forward), &[]),
+    half(3.0, 2.0, PartStitchDirection::Backward)
+  );
+
+  let bead = |x: f32, y: f32, rotated: bool| {
+    Stitch::Node(NodeStitch {
+      x: coord(x),
+      y: coord(y),
+      rotated,
+      palindex: 0,
+      kind: NodeStitchKind::Bead,
+    })
+  };
+  assert_eq!(
+    transform.transform_stitch(bounds, bead(2.0, 2.0, false), &[]),
+    bead(4.0, 1.0, true)
+  );
+
+  // Rotating twice equals rotating by half a turn.
+  let special = Stitch::Special(SpecialStitch {
+    x: coord(2.0),
+    y: coord(2.0),
+    rotation: 270,
+    flip: (false, true),
+    palindex: 0,
+    modindex: 0,
+  });
+  let Stitch::Special(rotated) = StitchTransform::Rotate(2).transform_stitch(bounds, special, &[]) else {
+    panic!("expected special stitch");
+  };
+  assert_eq!((*rotated.x, *rotated.y), (5.0, 3.0));
+  assert_eq!(rotated.rotation, 90);
+  assert_eq!(rotated.flip, (false, true));
+}
+
+#[test]
+fn test_transform_special_stitch_by_model_bounds() {
+  // A 3x1 model anchored at (1, 1) occupies the box from (1, 1) to (4, 2).
+  let bounds = Bounds::new(0, 0, 5, 3);
+  let coord = |value: f32| Coord::new(value).unwrap();
+  let models = vec![SpecialStitchModel {
+    unique_name: String::from("bar"),
+    name: String::from("Bar"),
+    width: 3.0,
+    height: 1.0,
+    nodestitches: Vec::new(),
+    linestitches: Vec::new(),
+    curvedstitches: Vec::new(),
+  }];
+  let special = Stitch::Special(SpecialStitch {
+    x: coord(1.0),
+    y: coord(1.0),
+    rotation: 0,
+    flip: (false, false),
+    palindex: 0,
+    modindex: 0,
+  });
+
+  let Stitch::Special(mirrored) = StitchTransform::MirrorHorizontal.transform_stitch(bounds, special, &models) else {
+    panic!("expected special stitch");
+  };
+  assert_eq!((*mirrored.x, *mirrored.y), (4.0, 1.0));
+  assert_eq!(mirrored.flip, (true, false));
+  assert_eq!(mirrored.aabb(&models[0]), (1.0, 1.0, 4.0, 2.0));
+
+  let Stitch::Special(mirrored) = StitchTransform::MirrorVertical.transform_stitch(bounds, special, &models) else {
+    panic!("expected special stitch");
+  };
+  assert_eq!((*mirrored.x, *mirrored.y), (1.0, 2.0));
+  assert_eq!(mirrored.flip, (false, true));
+  assert_eq!(mirrored.aabb(&models[0]), (1.0, 1.0, 4.0, 2.0));
+
+  // Rotating a quarter turn around the center (2.5, 1.5) turns the row into a column.
+  let Stitch::Special(rotated) = StitchTransform::Rotate(1).transform_stitch(bounds, special, &models) else {
+    panic!("expected special stitch");
+  };
+  assert_eq!((*rotated.x, *rotated.y), (3.0, 0.0));
+  assert_eq!(rotated.rotation, 90);
+  assert_eq!(rotated.aabb(&models[0]), (2.0, 0.0, 3.0, 3.0));
+}
+
+#[test]
+fn test_transform_stitches_and_revoke() {
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 4,
+    height: 1,
+    ..Fabric::default()
+  }));
+  let full = |x: f32, palindex: u32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(0.0).unwrap(),
+      palindex,
+      kind: FullStitchKind::Full,
+    })
+  };
+  embproj
+    .pattern
+    .add_stitches(0, vec![full(1.0, 1), full(2.0, 1), full(3.0, 2)]);
+
+  // The stitch at (2, 0) replaces the one at (3, 0), and the one at (3, 0) moves out of the fabric.
+  let mut action = EditorAction::Stitch(StitchAction::Transform {
+    layer_index: 0,
+    stitches: vec![full(1.0, 1), full(2.0, 1), full(0.0, 1)],
+    bounds: Bounds::new(1, 0, 2, 1),
+    transform: StitchTransform::Move { dx: 1, dy: 0 },
+    removed_stitches: None,
+    transformed_stitches: None,
+    conflicts: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    let EditorEvent::StitchesRemove { stitches, .. } = &events[0] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(stitches, &vec![full(1.0, 1), full(2.0, 1)]);
+    let EditorEvent::StitchesAdd { stitches, .. } = &events[1] else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(stitches, &vec![full(2.0, 1), full(3.0, 1)]);
+    let EditorEvent::StitchesRemove { stitches, .. } = &events[2] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(stitches, &vec![full(3.0, 2)]);
+
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 2);
+    assert!(!embproj.pattern.contains_stitch(0, &full(1.0, 1)));
+  }
+
+  // Test revoking the command.
+  {
+    action.revoke(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 3);
+    assert!(embproj.pattern.contains_stitch(0, &full(1.0, 1)));
+    assert!(embproj.pattern.contains_stitch(0, &full(2.0, 1)));
+    assert!(embproj.pattern.contains_stitch(0, &full(3.0, 2)));
+  }
+}
+
+#[test]
+fn test_scale_stitches_up() {
+  let full = |x: f32, y: f32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    })
+  };
+  let line = |x: (f32, f32), y: (f32, f32)| {
+    Stitch::Line(LineStitch {
+      x: (Coord::new(x.0).unwrap(), Coord::new(x.1).unwrap()),
+      y: (Coord::new(y.0).unwrap(), Coord::new(y.1).unwrap()),
+      palindex: 0,
+      kind: LineStitchKind::Back,
+    })
+  };
+
+  let scaled = scale_stitches(
+    Bounds::new(1, 1, 2, 1),
+    2.0,
+    &[full(2.0, 1.0), line((1.0, 3.0), (1.0, 1.5))],
+  )
+  .unwrap();
+  assert_eq!(
+    scaled,
+    vec![
+      full(3.0, 1.0),
+      full(4.0, 1.0),
+      full(3.0, 2.0),
+      full(4.0, 2.0),
+      line((1.0, 5.0), (1.0, 2.0)),
+    ]
+  );
+}
+
+#[test]
+fn test_scale_stitches_down() {
+  let full = |x: f32, y: f32, palindex: u32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex,
+      kind: FullStitchKind::Full,
+    })
+  };
+  let petite = Stitch::Full(FullStitch {
+    x: Coord::new(1.5).unwrap(),
+    y: Coord::new(0.0).unwrap(),
+    palindex: 0,
+    kind: FullStitchKind::Petite,
+  });
+
+  // Every 2x2 block collapses into its top-left cell, and the petite stitch in the odd half-cell column is dropped.
+  let stitches = [
+    full(0.0, 0.0, 1),
+    full(1.0, 0.0, 2),
+    full(0.0, 1.0, 3),
+    full(2.0, 0.0, 4),
+    petite,
+  ];
+  let scaled = scale_stitches(Bounds::new(0, 0, 4, 2), 0.5, &stitches).unwrap();
+  assert_eq!(scaled, vec![full(0.0, 0.0, 1), full(1.0, 0.0, 4)]);
+}
+
+#[test]
+fn test_scale_stitches_and_revoke() {
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 3,
+    height: 3,
+    ..Fabric::default()
+  }));
+  let full = |x: f32, y: f32, palindex: u32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex,
+      kind: FullStitchKind::Full,
+    })
+  };
+  embproj
+    .pattern
+    .add_stitches(0, vec![full(1.0, 1.0, 1), full(2.0, 2.0, 2)]);
+
+  // The stitch at (1, 1) grows over the one at (2, 2), and the rest is clipped by the fabric.
+  let mut action = EditorAction::Stitch(StitchAction::Scale {
+    layer_index: 0,
+    stitches: vec![full(1.0, 1.0, 1)],
+    bounds: Bounds::new(1, 1, 1, 1),
+    factor: 3.0,
+    removed_stitches: None,
+    scaled_stitches: None,
+    conflicts: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    let EditorEvent::StitchesAdd { stitches, .. } = &events[1] else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(
+      stitches,
+      &vec![
+        full(1.0, 1.0, 1),
+        full(2.0, 1.0, 1),
+        full(1.0, 2.0, 1),
+        full(2.0, 2.0, 1)
+      ]
+    );
+    let EditorEvent::StitchesRemove { stitches, .. } = &events[2] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(stitches, &vec![full(2.0, 2.0, 2)]);
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 4);
+  }
+
+  // Test revoking the command.
+  {
+    action.revoke(&mut embproj).unwrap();
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full(1.0, 1.0, 1), full(2.0, 2.0, 2)]
+    );
+  }
+}
+
+#[test]
+fn test_scale_stitches_rejects_invalid_factors() {
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 10,
+    height: 10,
+    ..Fabric::default()
+  }));
+  let stitches = vec![
+    Stitch::Full(FullStitch {
+      x: Coord::new(1.0).unwrap(),
+      y: Coord::new(1.0).unwrap(),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    }),
+    Stitch::Line(LineStitch {
+      x: (Coord::new(1.0).unwrap(), Coord::new(2.0).unwrap()),
+      y: (Coord::new(1.0).unwrap(), Coord::new(2.0).unwrap()),
+      palindex: 0,
+      kind: LineStitchKind::Back,
+    }),
+  ];
+  embproj.pattern.add_stitches(0, stitches.clone());
+
+  assert!(matches!(
+    scale_stitches(Bounds::new(1, 1, 1, 1), f32::NAN, &stitches),
+    Err(Error::InvalidScaleFactor(_))
+  ));
+
+  for factor in [f32::NAN, f32::INFINITY, 0.0, -1.0, 10_000.0] {
+    let mut action = EditorAction::Stitch(StitchAction::Scale {
+      layer_index: 0,
+      stitches: stitches.clone(),
+      bounds: Bounds::new(1, 1, 1, 1),
+      factor,
+      removed_stitches: None,
+      scaled_stitches: None,
+      conflicts: None,
+    });
+    assert!(matches!(
+      action.perform(&mut embproj),
+      Err(Error::InvalidScaleFactor(_) | Error::ScaleFactorTooLarge(_))
+    ));
+  }
+
+  // The stitches are left untouched.
+  assert_eq!(embproj.pattern.layers[0].get_stitches(), stitches);
+}
+
+#[test]
+fn test_move_stitches_to_layer_and_revoke() {
+  let mut embproj = EmbroiderlyProject::new(Pattern::default());
+  embproj.pattern.layers.push(Layer::new("Layer 2"));
+  let full = |x: f32, palindex: u32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(0.0).unwrap(),
+      palindex,
+      kind: FullStitchKind::Full,
+    })
+  };
+  embproj.pattern.add_stitches(0, vec![full(0.0, 0), full(1.0, 0)]);
+  embproj.pattern.add_stitches(1, vec![full(1.0, 1), full(2.0, 1)]);
+
+  let mut action = EditorAction::Stitch(StitchAction::MoveToLayer {
+    from_layer_index: 0,
+    to_layer_index: 1,
+    stitches: vec![full(0.0, 0), full(1.0, 0)],
+    moved_stitches: None,
+    conflicts: None,
+  });
+
+  // Test executing the command.
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    let EditorEvent::StitchesRemove { layer_index, stitches } = &events[2] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(layer_index, &1);
+    assert_eq!(stitches, &vec![full(1.0, 1)]);
+
+    assert!(embproj.pattern.layers[0].get_stitches().is_empty());
+    assert_eq!(
+      embproj.pattern.layers[1].get_stitches(),
+      vec![full(0.0, 0), full(1.0, 0), full(2.0, 1)]
+    );
+  }
+
+  // Test revoking the command.
+  {
+    action.revoke(&mut embproj).unwrap();
+    assert_eq!(
+      embproj.pattern.layers[0].get_stitches(),
+      vec![full(0.0, 0), full(1.0, 0)]
+    );
+    assert_eq!(
+      embproj.pattern.layers[1].get_stitches(),
+      vec![full(1.0, 1), full(2.0, 1)]
+    );
+  }
+}
+
+#[test]
+fn test_remove_stitches_by_kind_and_revoke() {
+  // create_pattern_project has two petites and two quarters.
+  let mut embproj = create_pattern_project();
+  let mut action = EditorAction::Stitch(StitchAction::RemoveByKind {
+    layer_index: 0,
+    kinds: StitchKinds {
+      quarterstitches: true,
+      ..Default::default()
+    },
+    removed_stitches: None,
+  });
+
+  {
+    let events = action.perform(&mut embproj).unwrap();
+    let EditorEvent::StitchesRemove { layer_index, stitches } = &events[0] else {
+      panic!("expected StitchesRemove");
+    };
+    assert_eq!(layer_index, &0);
+    assert_eq!(stitches.len(), 2);
+    assert!(stitches.iter().all(|stitch| matches!(stitch, Stitch::Part(_))));
+    assert!(embproj.pattern.layers[0].partstitches.is_empty());
+    assert_eq!(embproj.pattern.layers[0].fullstitches.len(), 2);
+    assert!(matches!(events.last(), Some(EditorEvent::PatternChanged(id)) if *id == embproj.id));
+  }
+
+  {
+    let events = action.revoke(&mut embproj).unwrap();
+    let EditorEvent::StitchesAdd { stitches, .. } = &events[0] else {
+      panic!("expected StitchesAdd");
+    };
+    assert_eq!(stitches.len(), 2);
+    assert_eq!(embproj.pattern.layers[0].partstitches.len(), 2);
+  }
+}
+
+#[test]
+fn test_remove_stitches_by_kind_nothing_present() {
+  let mut embproj = create_pattern_project();
+  let mut action = EditorAction::Stitch(StitchAction::RemoveByKind {
+    layer_index: 0,
+    kinds: StitchKinds {
+      backstitches: true,
+      ..Default::default()
+    },
+    removed_stitches: None,
+  });
+
+  assert!(action.perform(&mut embproj).unwrap().is_empty());
+  assert!(action.revoke(&mut embproj).unwrap().is_empty());
+}
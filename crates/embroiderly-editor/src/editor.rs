@@ -1,11 +1,15 @@
 use std::collections::HashMap;
 
-use embroiderly_pattern::{EmbroiderlyProject, EmbroiderlyProjectId};
+use embroiderly_pattern::{Bounds, EmbroiderlyProject, EmbroiderlyProjectId, Stitch};
 
 use crate::EditorEvent;
-use crate::actions::EditorAction;
+use crate::actions::pattern::ResizeAnchor;
+use crate::actions::stitches::{StitchKinds, StitchTransform, checked_scale_bounds};
+use crate::actions::{EditorAction, PatternAction, StitchAction};
 use crate::error::{Error, Result};
-use crate::history::History;
+use crate::history::{Clock, History, HistoryBranch, HistoryItem, HistoryLimits};
+use crate::selection::{Clipboard, Selection};
+use crate::symmetry::Symmetry;
 
 #[cfg(test)]
 #[path = "editor.test.rs"]
@@ -16,6 +20,14 @@ mod tests;
 pub struct Editor {
   projects: HashMap<EmbroiderlyProjectId, EmbroiderlyProject>,
   histories: HashMap<EmbroiderlyProjectId, History>,
+  selections: HashMap<EmbroiderlyProjectId, Selection>,
+  symmetries: HashMap<EmbroiderlyProjectId, Symmetry>,
+  /// The clipboard shared between all open pattern projects.
+  clipboard: Option<Clipboard>,
+  /// The clock used to timestamp history entries.
+  clock: Option<Clock>,
+  /// The limits applied to the history of every pattern project.
+  history_limits: HistoryLimits,
 }
 
 impl Editor {
@@ -34,15 +46,38 @@ impl Editor {
   pub fn add_pattern(&mut self, embproj: EmbroiderlyProject) -> EmbroiderlyProjectId {
     let id = embproj.id;
 
-    self.histories.entry(id).or_default();
+    let (clock, limits) = (self.clock, self.history_limits);
+    self.histories.entry(id).or_insert_with(|| {
+      let mut history = History::with_clock(clock);
+      history.set_limits(limits);
+      history
+    });
     self.projects.insert(id, embproj);
 
     id
   }
 
+  /// Sets the clock used to timestamp history entries of all open and future pattern projects.
+  pub fn set_clock(&mut self, clock: Clock) {
+    self.clock = Some(clock);
+    for history in self.histories.values_mut() {
+      history.set_clock(self.clock);
+    }
+  }
+
+  /// Sets the limits applied to the history of all open and future pattern projects.
+  pub fn set_history_limits(&mut self, limits: HistoryLimits) {
+    self.history_limits = limits;
+    for history in self.histories.values_mut() {
+      history.set_limits(limits);
+    }
+  }
+
   /// Removes a pattern project from the editor. Returns the project if it existed.
   pub fn remove_pattern(&mut self, id: &EmbroiderlyProjectId) -> Option<EmbroiderlyProject> {
     self.histories.remove(id);
+    self.selections.remove(id);
+    self.symmetries.remove(id);
     self.projects.remove(id)
   }
 
@@ -60,8 +95,11 @@ impl Editor {
   /// Performs an action on the pattern project.
   /// All changes are stored in the actions history.
   /// Returns the resulted events to which the caller should react on their side.
+  ///
+  /// Actions changing the stitches of a locked layer are rejected with [`Error::LayerLocked`].
   pub fn dispatch(&mut self, id: &EmbroiderlyProjectId, mut action: EditorAction) -> Result<Vec<EditorEvent>> {
     let embproj = self.projects.get_mut(id).ok_or(Error::PatternNotFound(*id))?;
+    ensure_unlocked(embproj, std::slice::from_ref(&action))?;
     let events = action.perform(embproj)?;
 
     let history = self.histories.get_mut(id).ok_or(Error::PatternNotFound(*id))?;
@@ -98,6 +136,32 @@ impl Editor {
     Ok(history.redo_transaction(embproj)?.unwrap_or_default())
   }
 
+  /// Returns the summaries of the history entries of the pattern project, from the oldest to the newest.
+  pub fn history(&self, id: &EmbroiderlyProjectId) -> Result<Vec<HistoryItem>> {
+    let history = self.histories.get(id).ok_or(Error::PatternNotFound(*id))?;
+    Ok(history.items())
+  }
+
+  /// Undoes or redoes the history of the pattern project until exactly `position` entries are applied.
+  pub fn jump_to(&mut self, id: &EmbroiderlyProjectId, position: usize) -> Result<Vec<EditorEvent>> {
+    let embproj = self.projects.get_mut(id).ok_or(Error::PatternNotFound(*id))?;
+    let history = self.histories.get_mut(id).ok_or(Error::PatternNotFound(*id))?;
+    history.jump_to(embproj, position)
+  }
+
+  /// Returns the summaries of the history branches forking from the current one.
+  pub fn history_branches(&self, id: &EmbroiderlyProjectId) -> Result<Vec<HistoryBranch>> {
+    let history = self.histories.get(id).ok_or(Error::PatternNotFound(*id))?;
+    Ok(history.branches())
+  }
+
+  /// Switches the history of the pattern project to another branch and redoes all of its changes.
+  pub fn switch_history_branch(&mut self, id: &EmbroiderlyProjectId, index: usize) -> Result<Vec<EditorEvent>> {
+    let embproj = self.projects.get_mut(id).ok_or(Error::PatternNotFound(*id))?;
+    let history = self.histories.get_mut(id).ok_or(Error::PatternNotFound(*id))?;
+    history.switch_branch(embproj, index)
+  }
+
   /// Starts a new transaction on the pattern project.
   pub fn start_transaction(&mut self, id: &EmbroiderlyProjectId) -> Result<()> {
     let history = self.histories.get_mut(id).ok_or(Error::PatternNotFound(*id))?;
@@ -112,6 +176,12 @@ impl Editor {
     Ok(())
   }
 
+  /// Checks if a transaction has been started on the pattern project and not ended yet.
+  pub fn has_active_transaction(&self, id: &EmbroiderlyProjectId) -> Result<bool> {
+    let history = self.histories.get(id).ok_or(Error::PatternNotFound(*id))?;
+    Ok(history.has_active_transaction())
+  }
+
   /// Records a checkpoint (save point) for the pattern. Used for tracking unsaved changes.
   pub fn checkpoint(&mut self, id: &EmbroiderlyProjectId) -> Result<()> {
     let history = self.histories.get_mut(id).ok_or(Error::PatternNotFound(*id))?;
@@ -124,4 +194,276 @@ impl Editor {
     let history = self.histories.get(id).ok_or(Error::PatternNotFound(*id))?;
     Ok(history.has_unsaved_changes())
   }
+
+  /// Returns the current selection on the pattern project.
+  #[must_use]
+  pub fn selection(&self, id: &EmbroiderlyProjectId) -> Option<&Selection> {
+    self.selections.get(id)
+  }
+
+  /// Replaces the selection on the pattern project. Passing `None` clears the selection.
+  pub fn set_selection(&mut self, id: &EmbroiderlyProjectId, selection: Option<Selection>) -> Result<()> {
+    if !self.projects.contains_key(id) {
+      return Err(Error::PatternNotFound(*id));
+    }
+    match selection {
+      Some(selection) => self.selections.insert(*id, selection),
+      None => self.selections.remove(id),
+    };
+    Ok(())
+  }
+
+  /// Returns the mirror drawing mode of the pattern project.
+  #[must_use]
+  pub fn symmetry(&self, id: &EmbroiderlyProjectId) -> Option<&Symmetry> {
+    self.symmetries.get(id)
+  }
+
+  /// Enables the mirror drawing mode on the pattern project. Passing `None` disables it.
+  pub fn set_symmetry(&mut self, id: &EmbroiderlyProjectId, symmetry: Option<Symmetry>) -> Result<()> {
+    if !self.projects.contains_key(id) {
+      return Err(Error::PatternNotFound(*id));
+    }
+    match symmetry {
+      Some(symmetry) => self.symmetries.insert(*id, symmetry),
+      None => self.symmetries.remove(id),
+    };
+    Ok(())
+  }
+
+  /// Returns the clipboard contents, if anything has been copied.
+  #[must_use]
+  pub const fn clipboard(&self) -> Option<&Clipboard> {
+    self.clipboard.as_ref()
+  }
+
+  /// Copies the selected stitches of the pattern project to the clipboard.
+  pub fn copy(&mut self, id: &EmbroiderlyProjectId) -> Result<()> {
+    let embproj = self.projects.get(id).ok_or(Error::PatternNotFound(*id))?;
+    let selection = self.selections.get(id).ok_or(Error::NothingSelected)?;
+    self.clipboard = Some(selection.copy(&embproj.pattern));
+    Ok(())
+  }
+
+  /// Pastes the clipboard contents into the layer of the pattern project with the top-left corner at the given cell.
+  pub fn paste(&mut self, id: &EmbroiderlyProjectId, layer_index: u32, x: u16, y: u16) -> Result<Vec<EditorEvent>> {
+    let clipboard = self.clipboard.clone().ok_or(Error::ClipboardEmpty)?;
+    let action = EditorAction::Stitch(StitchAction::Paste {
+      layer_index,
+      x,
+      y,
+      clipboard,
+      pasted_stitches: None,
+      conflicts: None,
+      added_items: None,
+    });
+    self.dispatch(id, action)
+  }
+
+  /// Performs the actions on the pattern project as a single transaction.
+  /// Returns the resulted events of all actions.
+  ///
+  /// If any of the actions targets a locked layer, none of them is performed.
+  pub fn dispatch_transaction(
+    &mut self,
+    id: &EmbroiderlyProjectId,
+    actions: Vec<EditorAction>,
+  ) -> Result<Vec<EditorEvent>> {
+    let embproj = self.projects.get(id).ok_or(Error::PatternNotFound(*id))?;
+    ensure_unlocked(embproj, &actions)?;
+
+    self.start_transaction(id)?;
+    let result = actions.into_iter().try_fold(Vec::new(), |mut events, action| {
+      events.extend(self.dispatch(id, action)?);
+      Ok(events)
+    });
+    self.end_transaction(id)?;
+    result
+  }
+
+  /// Returns the actions removing the selected stitches from every affected layer of the pattern project.
+  /// Together with [`Editor::copy`], they make up the cut of the selection.
+  ///
+  /// Like all `*_actions` methods, fails with [`Error::LayerLocked`] if any of the affected layers is locked,
+  /// so a change spanning several layers is rejected before any of its steps is performed.
+  pub fn remove_selection_actions(&self, id: &EmbroiderlyProjectId) -> Result<Vec<EditorAction>> {
+    let (embproj, selection) = self.selection_with_pattern(id)?;
+    let actions: Vec<EditorAction> = selection
+      .stitches(&embproj.pattern)
+      .into_iter()
+      .map(|(layer_index, target_stitches)| {
+        EditorAction::Stitch(StitchAction::RemoveMany {
+          layer_index,
+          target_stitches,
+          actual_stitches: None,
+        })
+      })
+      .collect();
+    ensure_unlocked(embproj, &actions)?;
+    Ok(actions)
+  }
+
+  /// Returns the actions moving the selected stitches of the pattern project from every affected layer to the given one.
+  pub fn move_selection_to_layer_actions(
+    &self,
+    id: &EmbroiderlyProjectId,
+    layer_index: u32,
+  ) -> Result<Vec<EditorAction>> {
+    let (embproj, selection) = self.selection_with_pattern(id)?;
+    let actions: Vec<EditorAction> = selection
+      .stitches(&embproj.pattern)
+      .into_iter()
+      .filter(|(from_layer_index, _)| *from_layer_index != layer_index)
+      .map(|(from_layer_index, stitches)| {
+        EditorAction::Stitch(StitchAction::MoveToLayer {
+          from_layer_index,
+          to_layer_index: layer_index,
+          stitches,
+          moved_stitches: None,
+          conflicts: None,
+        })
+      })
+      .collect();
+    ensure_unlocked(embproj, &actions)?;
+    Ok(actions)
+  }
+
+  /// Returns the actions transforming the selected stitches of the pattern project on every affected layer.
+  pub fn transform_selection_actions(
+    &self,
+    id: &EmbroiderlyProjectId,
+    transform: StitchTransform,
+  ) -> Result<Vec<EditorAction>> {
+    let (embproj, selection) = self.selection_with_pattern(id)?;
+    let bounds = selection.area.bounds();
+    let actions: Vec<EditorAction> = selection
+      .stitches(&embproj.pattern)
+      .into_iter()
+      .map(|(layer_index, stitches)| {
+        EditorAction::Stitch(StitchAction::Transform {
+          layer_index,
+          stitches,
+          bounds,
+          transform,
+          removed_stitches: None,
+          transformed_stitches: None,
+          conflicts: None,
+        })
+      })
+      .collect();
+    ensure_unlocked(embproj, &actions)?;
+    Ok(actions)
+  }
+
+  /// Returns the actions scaling the selected stitches of the pattern project on every affected layer by the given factor.
+  pub fn scale_selection_actions(&self, id: &EmbroiderlyProjectId, factor: f32) -> Result<Vec<EditorAction>> {
+    let (embproj, selection) = self.selection_with_pattern(id)?;
+    let bounds = selection.area.bounds();
+    checked_scale_bounds(bounds, factor)?;
+    let actions: Vec<EditorAction> = selection
+      .stitches(&embproj.pattern)
+      .into_iter()
+      .map(|(layer_index, stitches)| scale_action(layer_index, stitches, bounds, factor))
+      .collect();
+    ensure_unlocked(embproj, &actions)?;
+    Ok(actions)
+  }
+
+  /// Returns the actions scaling the whole pattern project by the given factor
+  /// and resizing the fabric to fit the scaled stitches.
+  pub fn scale_pattern_actions(&self, id: &EmbroiderlyProjectId, factor: f32) -> Result<Vec<EditorAction>> {
+    let embproj = self.projects.get(id).ok_or(Error::PatternNotFound(*id))?;
+    let fabric = &embproj.pattern.fabric;
+    let bounds = Bounds::new(0, 0, fabric.width, fabric.height);
+    let scaled_bounds = checked_scale_bounds(bounds, factor)?;
+    let mut actions: Vec<EditorAction> = embproj
+      .pattern
+      .layers
+      .iter()
+      .enumerate()
+      .map(|(layer_index, layer)| scale_action(layer_index as u32, layer.get_stitches(), bounds, factor))
+      .collect();
+    ensure_unlocked(embproj, &actions)?;
+
+    // Grow the fabric before scaling the stitches and shrink it after, so no stitches are clipped.
+    let resize = EditorAction::Pattern(PatternAction::Resize {
+      width: scaled_bounds.width,
+      height: scaled_bounds.height,
+      anchor: ResizeAnchor::TopLeft,
+      old_fabric: None,
+      removed_stitches: None,
+    });
+    if factor > 1.0 {
+      actions.insert(0, resize);
+    } else {
+      actions.push(resize);
+    }
+
+    Ok(actions)
+  }
+
+  /// Returns the actions removing every stitch of the given kinds from the layer of the pattern project,
+  /// or from all its unlocked layers if no layer is specified.
+  pub fn remove_stitches_by_kind_actions(
+    &self,
+    id: &EmbroiderlyProjectId,
+    layer_index: Option<u32>,
+    kinds: StitchKinds,
+  ) -> Result<Vec<EditorAction>> {
+    let embproj = self.projects.get(id).ok_or(Error::PatternNotFound(*id))?;
+    let layer_indexes: Vec<u32> = match layer_index {
+      Some(layer_index) => vec![layer_index],
+      None => (0..embproj.pattern.layers.len() as u32)
+        .filter(|&index| !embproj.pattern.layers[index].locked)
+        .collect(),
+    };
+    let actions: Vec<EditorAction> = layer_indexes
+      .into_iter()
+      .map(|layer_index| {
+        EditorAction::Stitch(StitchAction::RemoveByKind {
+          layer_index,
+          kinds,
+          removed_stitches: None,
+        })
+      })
+      .collect();
+    ensure_unlocked(embproj, &actions)?;
+    Ok(actions)
+  }
+
+  fn selection_with_pattern(&self, id: &EmbroiderlyProjectId) -> Result<(&EmbroiderlyProject, &Selection)> {
+    let embproj = self.projects.get(id).ok_or(Error::PatternNotFound(*id))?;
+    let selection = self.selections.get(id).ok_or(Error::NothingSelected)?;
+    Ok((embproj, selection))
+  }
+}
+
+/// Fails with [`Error::LayerLocked`] if any of the actions changes the stitches of a locked layer.
+fn ensure_unlocked(embproj: &EmbroiderlyProject, actions: &[EditorAction]) -> Result<()> {
+  let locked_layer_index = actions
+    .iter()
+    .flat_map(|action| match action {
+      EditorAction::Stitch(stitch_action) => stitch_action.layer_indexes(),
+      EditorAction::Layer(layer_action) => layer_action.layer_indexes(&embproj.pattern),
+      EditorAction::Palette(palette_action) => palette_action.layer_indexes(&embproj.pattern),
+      EditorAction::Pattern(pattern_action) => pattern_action.layer_indexes(&embproj.pattern),
+      _ => Vec::new(),
+    })
+    .find(|&index| embproj.pattern.layers.get(index).is_some_and(|layer| layer.locked));
+  match locked_layer_index {
+    Some(layer_index) => Err(Error::LayerLocked(layer_index)),
+    None => Ok(()),
+  }
+}
+
+const fn scale_action(layer_index: u32, stitches: Vec<Stitch>, bounds: Bounds, factor: f32) -> EditorAction {
+  EditorAction::Stitch(StitchAction::Scale {
+    layer_index,
+    stitches,
+    bounds,
+    factor,
+    removed_stitches: None,
+    scaled_stitches: None,
+    conflicts: None,
+  })
 }
@@ -1,8 +1,14 @@
-use embroiderly_pattern::{EmbroiderlyProject, EmbroiderlyProjectId};
+use embroiderly_pattern::{
+  Bounds, Coord, EmbroiderlyProject, EmbroiderlyProjectId, Fabric, FullStitch, FullStitchKind, Palette, PaletteItem,
+  Pattern, Stitch,
+};
 
 use super::*;
-use crate::actions::EditorAction;
+use crate::actions::palette::ReplaceColorScope;
+use crate::actions::stitches::{StitchKinds, StitchTransform};
+use crate::actions::{EditorAction, LayerAction, PaletteAction};
 use crate::error::Error;
+use crate::selection::{Selection, SelectionArea};
 
 #[test]
 fn test_editor_new() {
@@ -107,3 +113,542 @@ fn test_checkpoint() {
   editor.dispatch(&id, EditorAction::Mock).unwrap();
   assert!(editor.has_unsaved_changes(&id).unwrap());
 }
+
+#[test]
+fn test_copy_without_selection() {
+  let mut editor = Editor::new();
+  let id = editor.add_pattern(EmbroiderlyProject::default());
+
+  assert!(matches!(editor.copy(&id), Err(Error::NothingSelected)));
+  assert!(matches!(editor.paste(&id, 0, 0, 0), Err(Error::ClipboardEmpty)));
+}
+
+#[test]
+fn test_cut_and_paste_into_another_pattern() {
+  let palitem = |number: &str| PaletteItem {
+    brand: String::from("DMC"),
+    number: String::from(number),
+    name: String::new(),
+    color: String::from("000000"),
+    blends: None,
+    symbol: None,
+  };
+  let full_stitch = |x: f32, y: f32, palindex: u32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex,
+      kind: FullStitchKind::Full,
+    })
+  };
+
+  let mut editor = Editor::new();
+
+  let mut source = EmbroiderlyProject::new(Pattern::default());
+  source.pattern.palette = Palette::from(vec![palitem("310"), palitem("321")]);
+  source
+    .pattern
+    .add_stitches(0, vec![full_stitch(0.0, 0.0, 0), full_stitch(1.0, 0.0, 1)]);
+  let source_id = editor.add_pattern(source);
+
+  let mut target = EmbroiderlyProject::new(Pattern::default());
+  target.pattern.palette = Palette::from(vec![palitem("321")]);
+  let target_id = editor.add_pattern(target);
+
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(0, 0, 2, 1)), None);
+  editor.set_selection(&source_id, Some(selection)).unwrap();
+  editor.copy(&source_id).unwrap();
+  let actions = editor.remove_selection_actions(&source_id).unwrap();
+  editor.dispatch_transaction(&source_id, actions).unwrap();
+  assert!(
+    editor.get_pattern(&source_id).unwrap().pattern.layers[0]
+      .fullstitches
+      .is_empty()
+  );
+
+  editor.paste(&target_id, 0, 3, 4).unwrap();
+  let target = &editor.get_pattern(&target_id).unwrap().pattern;
+  // The existing palette item is reused, the missing one is appended.
+  assert_eq!(target.palette.len(), 2);
+  assert_eq!(target.palette[1], palitem("310"));
+  assert!(target.contains_stitch(0, &full_stitch(3.0, 4.0, 1)));
+  assert!(target.contains_stitch(0, &full_stitch(4.0, 4.0, 0)));
+
+  editor.undo(&target_id).unwrap();
+  let target = &editor.get_pattern(&target_id).unwrap().pattern;
+  assert_eq!(target.palette.len(), 1);
+  assert!(target.layers[0].fullstitches.is_empty());
+
+  editor.undo_transaction(&source_id).unwrap();
+  assert_eq!(
+    editor.get_pattern(&source_id).unwrap().pattern.layers[0]
+      .fullstitches
+      .len(),
+    2
+  );
+}
+
+#[test]
+fn test_transform_selection() {
+  let full_stitch = |x: f32, y: f32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    })
+  };
+
+  let mut editor = Editor::new();
+  let mut embproj = EmbroiderlyProject::new(Pattern::default());
+  embproj
+    .pattern
+    .add_stitches(0, vec![full_stitch(0.0, 1.0), full_stitch(1.0, 1.0)]);
+  let id = editor.add_pattern(embproj);
+
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(0, 1, 2, 1)), None);
+  editor.set_selection(&id, Some(selection)).unwrap();
+  let actions = editor
+    .transform_selection_actions(&id, StitchTransform::Rotate(1))
+    .unwrap();
+  editor.dispatch_transaction(&id, actions).unwrap();
+
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert!(pattern.contains_stitch(0, &full_stitch(0.0, 0.0)));
+  assert!(pattern.contains_stitch(0, &full_stitch(0.0, 1.0)));
+
+  editor.undo_transaction(&id).unwrap();
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert!(pattern.contains_stitch(0, &full_stitch(1.0, 1.0)));
+  assert!(!pattern.contains_stitch(0, &full_stitch(0.0, 0.0)));
+}
+
+#[test]
+fn test_scale_pattern_resizes_fabric() {
+  let full_stitch = |x: f32, y: f32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    })
+  };
+
+  let mut editor = Editor::new();
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 10,
+    height: 5,
+    ..Fabric::default()
+  }));
+  embproj.pattern.add_stitches(0, vec![full_stitch(9.0, 4.0)]);
+  let id = editor.add_pattern(embproj);
+
+  let actions = editor.scale_pattern_actions(&id, 2.0).unwrap();
+  editor.dispatch_transaction(&id, actions).unwrap();
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!((pattern.fabric.width, pattern.fabric.height), (20, 10));
+  assert_eq!(pattern.layers[0].fullstitches.len(), 4);
+  assert!(pattern.contains_stitch(0, &full_stitch(19.0, 9.0)));
+
+  editor.undo_transaction(&id).unwrap();
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!((pattern.fabric.width, pattern.fabric.height), (10, 5));
+  assert_eq!(pattern.layers[0].get_stitches(), vec![full_stitch(9.0, 4.0)]);
+}
+
+#[test]
+fn test_scale_selection() {
+  let full_stitch = |x: f32, y: f32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    })
+  };
+
+  let mut editor = Editor::new();
+  let mut embproj = EmbroiderlyProject::new(Pattern::default());
+  embproj.pattern.add_stitches(0, vec![full_stitch(2.0, 2.0)]);
+  let id = editor.add_pattern(embproj);
+
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(2, 2, 1, 1)), None);
+  editor.set_selection(&id, Some(selection)).unwrap();
+  let actions = editor.scale_selection_actions(&id, 2.0).unwrap();
+  editor.dispatch_transaction(&id, actions).unwrap();
+
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!(pattern.layers[0].fullstitches.len(), 4);
+  assert!(pattern.contains_stitch(0, &full_stitch(3.0, 3.0)));
+}
+
+#[test]
+fn test_scale_rejects_invalid_factors() {
+  let full_stitch = |x: f32, y: f32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    })
+  };
+
+  let mut editor = Editor::new();
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 10,
+    height: 5,
+    ..Fabric::default()
+  }));
+  embproj
+    .pattern
+    .add_stitches(0, vec![full_stitch(2.0, 2.0), full_stitch(3.0, 2.0)]);
+  let id = editor.add_pattern(embproj);
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(2, 2, 2, 1)), None);
+  editor.set_selection(&id, Some(selection)).unwrap();
+
+  for factor in [f32::NAN, f32::INFINITY, 0.0, -1.0] {
+    assert!(matches!(
+      editor.scale_selection_actions(&id, factor),
+      Err(Error::InvalidScaleFactor(_))
+    ));
+    assert!(matches!(
+      editor.scale_pattern_actions(&id, factor),
+      Err(Error::InvalidScaleFactor(_))
+    ));
+  }
+
+  // The scaled stitches and fabric may not exceed the maximum size.
+  assert!(matches!(
+    editor.scale_selection_actions(&id, 10_000.0),
+    Err(Error::ScaleFactorTooLarge(_))
+  ));
+  assert!(matches!(
+    editor.scale_pattern_actions(&id, 10_000.0),
+    Err(Error::ScaleFactorTooLarge(_))
+  ));
+
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!((pattern.fabric.width, pattern.fabric.height), (10, 5));
+  assert_eq!(pattern.layers[0].fullstitches.len(), 2);
+}
+
+#[test]
+fn test_move_selection_to_layer() {
+  let full_stitch = |x: f32, y: f32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    })
+  };
+
+  let mut editor = Editor::new();
+  let mut embproj = EmbroiderlyProject::new(Pattern::default());
+  embproj.pattern.layers.push(embroiderly_pattern::Layer::new("Layer 2"));
+  embproj
+    .pattern
+    .add_stitches(0, vec![full_stitch(0.0, 0.0), full_stitch(5.0, 5.0)]);
+  let id = editor.add_pattern(embproj);
+
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(0, 0, 2, 2)), Some(0));
+  editor.set_selection(&id, Some(selection)).unwrap();
+  let actions = editor.move_selection_to_layer_actions(&id, 1).unwrap();
+  editor.dispatch_transaction(&id, actions).unwrap();
+
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!(pattern.layers[0].get_stitches(), vec![full_stitch(5.0, 5.0)]);
+  assert_eq!(pattern.layers[1].get_stitches(), vec![full_stitch(0.0, 0.0)]);
+
+  editor.undo_transaction(&id).unwrap();
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!(pattern.layers[0].fullstitches.len(), 2);
+  assert!(pattern.layers[1].get_stitches().is_empty());
+}
+
+#[test]
+fn test_dispatch_rejects_stitch_actions_on_locked_layer() {
+  let stitch = Stitch::Full(FullStitch {
+    x: Coord::new(0.0).unwrap(),
+    y: Coord::new(0.0).unwrap(),
+    palindex: 0,
+    kind: FullStitchKind::Full,
+  });
+
+  let mut editor = Editor::new();
+  let mut embproj = EmbroiderlyProject::new(Pattern::default());
+  embproj.pattern.layers[0].locked = true;
+  embproj.pattern.layers.push(embroiderly_pattern::Layer::new("Layer 2"));
+  let id = editor.add_pattern(embproj);
+
+  let add = |layer_index| {
+    EditorAction::Stitch(StitchAction::Add {
+      layer_index,
+      stitch,
+      conflicts: None,
+    })
+  };
+  assert!(matches!(editor.dispatch(&id, add(0)), Err(Error::LayerLocked(0))));
+  assert!(!editor.has_unsaved_changes(&id).unwrap());
+  assert!(
+    editor.get_pattern(&id).unwrap().pattern.layers[0]
+      .get_stitches()
+      .is_empty()
+  );
+
+  editor.dispatch(&id, add(1)).unwrap();
+  let move_to_locked = EditorAction::Stitch(StitchAction::MoveToLayer {
+    from_layer_index: 1,
+    to_layer_index: 0,
+    stitches: vec![stitch],
+    moved_stitches: None,
+    conflicts: None,
+  });
+  assert!(matches!(
+    editor.dispatch(&id, move_to_locked),
+    Err(Error::LayerLocked(0))
+  ));
+}
+
+#[test]
+fn test_multi_layer_changes_are_rejected_as_a_whole_on_locked_layer() {
+  let full_stitch = |x: f32, y: f32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(y).unwrap(),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    })
+  };
+
+  let mut editor = Editor::new();
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 10,
+    height: 5,
+    ..Fabric::default()
+  }));
+  let locked_index = embproj.pattern.layers.push(embroiderly_pattern::Layer::new("Locked"));
+  for layer_index in [0, locked_index] {
+    embproj.pattern.add_stitch(layer_index, full_stitch(1.0, 1.0));
+  }
+  embproj.pattern.layers[locked_index].locked = true;
+  let id = editor.add_pattern(embproj);
+
+  // The fabric would be resized and the first layer scaled before reaching the locked one.
+  assert!(matches!(
+    editor.scale_pattern_actions(&id, 2.0),
+    Err(Error::LayerLocked(index)) if index == locked_index
+  ));
+
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(0, 0, 2, 2)), None);
+  editor.set_selection(&id, Some(selection)).unwrap();
+  assert!(matches!(
+    editor.remove_selection_actions(&id),
+    Err(Error::LayerLocked(index)) if index == locked_index
+  ));
+  assert!(matches!(
+    editor.move_selection_to_layer_actions(&id, locked_index),
+    Err(Error::LayerLocked(index)) if index == locked_index
+  ));
+
+  let actions = vec![
+    EditorAction::Stitch(StitchAction::Add {
+      layer_index: 0,
+      stitch: full_stitch(2.0, 2.0),
+      conflicts: None,
+    }),
+    EditorAction::Stitch(StitchAction::Add {
+      layer_index: locked_index,
+      stitch: full_stitch(2.0, 2.0),
+      conflicts: None,
+    }),
+  ];
+  assert!(matches!(
+    editor.dispatch_transaction(&id, actions),
+    Err(Error::LayerLocked(index)) if index == locked_index
+  ));
+
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!((pattern.fabric.width, pattern.fabric.height), (10, 5));
+  assert_eq!(pattern.layers[0].get_stitches(), vec![full_stitch(1.0, 1.0)]);
+  assert!(!editor.has_unsaved_changes(&id).unwrap());
+  assert!(!editor.has_active_transaction(&id).unwrap());
+}
+
+#[test]
+fn test_dispatch_rejects_layer_palette_and_pattern_actions_changing_locked_layer() {
+  let palitem = |number: &str| PaletteItem {
+    brand: String::from("DMC"),
+    number: String::from(number),
+    name: String::new(),
+    color: String::from("000000"),
+    blends: None,
+    symbol: None,
+  };
+  let full_stitch = |x: f32, palindex: u32| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(0.0).unwrap(),
+      palindex,
+      kind: FullStitchKind::Full,
+    })
+  };
+
+  let mut editor = Editor::new();
+  let mut embproj = EmbroiderlyProject::new(Pattern::new(Fabric {
+    width: 10,
+    height: 10,
+    ..Fabric::default()
+  }));
+  embproj.pattern.palette = Palette::from(vec![palitem("310"), palitem("321")]);
+  let unlocked_index = embproj.pattern.layers.push(embroiderly_pattern::Layer::new("Layer 2"));
+  embproj.pattern.add_stitch(0, full_stitch(0.0, 0));
+  embproj.pattern.add_stitch(unlocked_index, full_stitch(5.0, 1));
+  embproj.pattern.layers[0].locked = true;
+  let id = editor.add_pattern(embproj);
+
+  let actions = [
+    EditorAction::Layer(LayerAction::MergeDown {
+      layer_index: unlocked_index,
+      target_index: None,
+      merged_layer: None,
+      added_stitches: None,
+      conflicts: None,
+      old_positions: None,
+    }),
+    EditorAction::Layer(LayerAction::Flatten { old_layers: None }),
+    EditorAction::Palette(PaletteAction::ReplaceColor {
+      from: 0,
+      to: 1,
+      scope: ReplaceColorScope::Pattern,
+      replaced_stitches: None,
+    }),
+    EditorAction::Palette(PaletteAction::MergeItems {
+      palindexes: vec![0],
+      target: 1,
+      saved_palitems: None,
+      old_positions: None,
+      merged_stitches: None,
+    }),
+    EditorAction::Palette(PaletteAction::RemoveItems {
+      palindexes: vec![0],
+      saved_palitems: None,
+      saved_conflicts: None,
+    }),
+    // Every stitch is shifted when the pattern grows around its center.
+    EditorAction::Pattern(PatternAction::Resize {
+      width: 20,
+      height: 20,
+      anchor: ResizeAnchor::Center,
+      old_fabric: None,
+      removed_stitches: None,
+    }),
+  ];
+  for action in actions {
+    assert!(matches!(editor.dispatch(&id, action), Err(Error::LayerLocked(0))));
+  }
+  assert!(!editor.has_unsaved_changes(&id).unwrap());
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!(pattern.layers.len(), 2);
+  assert_eq!(pattern.palette.len(), 2);
+  assert_eq!(pattern.layers[0].get_stitches(), vec![full_stitch(0.0, 0)]);
+
+  // Changes that keep the stitches of the locked layer intact are allowed.
+  editor
+    .dispatch(
+      &id,
+      EditorAction::Pattern(PatternAction::Resize {
+        width: 5,
+        height: 5,
+        anchor: ResizeAnchor::TopLeft,
+        old_fabric: None,
+        removed_stitches: None,
+      }),
+    )
+    .unwrap();
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!(pattern.layers[0].get_stitches(), vec![full_stitch(0.0, 0)]);
+  assert!(pattern.layers[unlocked_index].get_stitches().is_empty());
+}
+
+#[test]
+fn test_remove_stitches_by_kind_skips_locked_layers() {
+  let stitch = |kind| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(0.0).unwrap(),
+      y: Coord::new(0.0).unwrap(),
+      palindex: 0,
+      kind,
+    })
+  };
+
+  let mut editor = Editor::new();
+  let mut embproj = EmbroiderlyProject::new(Pattern::default());
+  let locked_index = embproj.pattern.layers.push(embroiderly_pattern::Layer::new("Locked"));
+  for layer_index in [0, locked_index] {
+    embproj.pattern.add_stitch(layer_index, stitch(FullStitchKind::Petite));
+  }
+  embproj.pattern.layers[locked_index].locked = true;
+  let id = editor.add_pattern(embproj);
+
+  let kinds = StitchKinds {
+    petitestitches: true,
+    ..Default::default()
+  };
+  let actions = editor.remove_stitches_by_kind_actions(&id, None, kinds).unwrap();
+  editor.dispatch_transaction(&id, actions).unwrap();
+
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert!(pattern.layers[0].get_stitches().is_empty());
+  assert_eq!(pattern.layers[locked_index].get_stitches().len(), 1);
+
+  editor.undo_transaction(&id).unwrap();
+  let pattern = &editor.get_pattern(&id).unwrap().pattern;
+  assert_eq!(pattern.layers[0].get_stitches(), vec![stitch(FullStitchKind::Petite)]);
+}
+
+// Restoring a project replays its journal into a fresh editor, which must rebuild the same undo history.
+#[test]
+fn test_replayed_actions_are_undoable() {
+  let stitch = |x| {
+    Stitch::Full(FullStitch {
+      x: Coord::new(x).unwrap(),
+      y: Coord::new(0.0).unwrap(),
+      palindex: 0,
+      kind: FullStitchKind::Full,
+    })
+  };
+  let add = |x| {
+    EditorAction::Stitch(StitchAction::Add {
+      layer_index: 0,
+      stitch: stitch(x),
+      conflicts: None,
+    })
+  };
+
+  // Restore from the snapshot as if the session crashed in the middle of the transaction.
+  let mut restored = Editor::new();
+  let id = restored.add_pattern(EmbroiderlyProject::new(Pattern::default()));
+  restored.checkpoint(&id).unwrap();
+  restored.dispatch(&id, add(0.0)).unwrap();
+  restored.start_transaction(&id).unwrap();
+  restored.dispatch(&id, add(1.0)).unwrap();
+  restored.dispatch(&id, add(2.0)).unwrap();
+  assert!(restored.has_active_transaction(&id).unwrap());
+  restored.end_transaction(&id).unwrap();
+  assert!(!restored.has_active_transaction(&id).unwrap());
+
+  assert_eq!(restored.history(&id).unwrap().len(), 2);
+  restored.undo_transaction(&id).unwrap();
+  assert_eq!(
+    restored.get_pattern(&id).unwrap().pattern.layers[0].get_stitches(),
+    vec![stitch(0.0)]
+  );
+  restored.undo_transaction(&id).unwrap();
+  assert!(!restored.has_unsaved_changes(&id).unwrap());
+  restored.redo_transaction(&id).unwrap();
+  assert_eq!(
+    restored.get_pattern(&id).unwrap().pattern.layers[0].get_stitches(),
+    vec![stitch(0.0)]
+  );
+}
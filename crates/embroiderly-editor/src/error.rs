@@ -8,6 +8,34 @@ pub enum Error {
   StitchNotFound,
   #[error("Cannot remove last layer")]
   CannotRemoveLastLayer,
+  #[error("Cannot merge down the bottom layer")]
+  CannotMergeDownBottomLayer,
+  #[error("Palette item not found: {0}")]
+  PaletteItemNotFound(u32),
+  #[error("Palette item {0} is listed more than once")]
+  DuplicatePaletteItem(u32),
+  #[error("Cannot merge palette item {0} into itself")]
+  CannotMergePaletteItemIntoItself(u32),
+  #[error("Invalid pattern size: {0}x{1}")]
+  InvalidPatternSize(u16, u16),
+  #[error("Invalid scale factor: {0}")]
+  InvalidScaleFactor(f32),
+  #[error("Scale factor {0} makes the stitches too large")]
+  ScaleFactorTooLarge(f32),
+  #[error("Layer group not found: {0}")]
+  LayerGroupNotFound(u32),
+  #[error("Layer group {0} cannot be nested into itself")]
+  CyclicLayerGroup(u32),
+  #[error("Invalid layer opacity: {0}")]
+  InvalidLayerOpacity(f32),
+  #[error("Layer {0} is locked")]
+  LayerLocked(u32),
+  #[error("History branch not found: {0}")]
+  HistoryBranchNotFound(usize),
+  #[error("Nothing is selected")]
+  NothingSelected,
+  #[error("Clipboard is empty")]
+  ClipboardEmpty,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
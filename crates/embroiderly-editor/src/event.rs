@@ -1,6 +1,6 @@
 use embroiderly_pattern::{
-  DisplaySettings, EmbroiderlyProjectId, Fabric, Grid, Layer, PaletteItem, PaletteSettings, PatternInfo,
-  PdfExportOptions, ReferenceImage, ReferenceImageSettings, Stitch, Symbol,
+  DisplaySettings, EmbroiderlyProjectId, Fabric, Grid, Layer, LayerBlendMode, LayerGroup, PaletteItem, PaletteSettings,
+  PatternInfo, PdfExportOptions, ReferenceImage, ReferenceImageSettings, Stitch, Symbol,
 };
 
 use crate::actions::layers::LayerVisibility;
@@ -53,6 +53,31 @@ pub enum EditorEvent {
     visibility: LayerVisibility,
   },
   LayerMove(Vec<u32>),
+  LayerUpdateLocked {
+    layer_index: u32,
+    locked: bool,
+  },
+  LayerUpdateOpacity {
+    layer_index: u32,
+    opacity: f32,
+  },
+  LayerUpdateBlendMode {
+    layer_index: u32,
+    blend_mode: LayerBlendMode,
+  },
+  LayerUpdateGroup {
+    layer_index: u32,
+    group: Option<u32>,
+  },
+  LayerGroupAdd {
+    index: u32,
+    group: LayerGroup,
+  },
+  LayerGroupRemove(u32),
+  LayerGroupUpdate {
+    group_index: u32,
+    group: LayerGroup,
+  },
 
   PublishUpdatePdf(PdfExportOptions),
 
@@ -2,30 +2,151 @@ use embroiderly_pattern::EmbroiderlyProject;
 
 use crate::EditorEvent;
 use crate::actions::EditorAction;
-use crate::error::Result;
+use crate::error::{Error, Result};
 
 #[cfg(test)]
 #[path = "history.test.rs"]
 mod tests;
 
+/// A function that returns the current time in milliseconds since the Unix epoch.
+///
+/// The editor doesn't read the system time itself, so the embedding application provides it.
+pub type Clock = fn() -> u64;
+
+/// The maximum time between two compatible actions, in milliseconds, for them to be coalesced into a single entry.
+pub const COALESCING_WINDOW: u64 = 1_000;
+
 /// An actions history of a specific pattern project.
+///
+/// The history is an undo tree.
+/// The undo and redo stacks hold the current branch, while the branches abandoned by making a change after an undo
+/// are kept aside and can be switched to later.
 #[derive(Default)]
 pub struct History {
   undo_stack: Vec<HistoryEntry>,
   redo_stack: Vec<HistoryEntry>,
+  /// The branches forking from the current one.
+  branches: Vec<Branch>,
   active_transaction: Option<Vec<EditorAction>>,
   last_transaction_id: usize,
+  /// The clock used to timestamp entries. Without it, entries are timestamped with `0`.
+  clock: Option<Clock>,
+  limits: HistoryLimits,
+  /// The estimated number of bytes the entries of the undo stack occupy.
+  undo_bytes: usize,
+}
+
+/// A summary of a history branch, used to present the alternatives to the user.
+#[derive(Debug, Clone, PartialEq, Eq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct HistoryBranch {
+  /// The number of entries of the current branch applied at the point the branch forks from.
+  pub position: usize,
+  /// A human-readable description of the first change of the branch.
+  pub label: String,
+  /// The time the first change of the branch was made at, in milliseconds since the Unix epoch.
+  pub timestamp: u64,
+}
+
+/// Limits on the size of the undo stack.
+/// When a limit is exceeded, the oldest entries are dropped and can no longer be undone.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryLimits {
+  /// The maximum number of entries (single actions or transactions) to keep.
+  pub max_entries: Option<usize>,
+  /// The maximum number of bytes the entries may occupy, estimated by their serialized size.
+  pub max_bytes: Option<usize>,
 }
 
+#[allow(clippy::large_enum_variant)]
 enum HistoryEntry {
-  Single(EditorAction),
+  Single {
+    action: EditorAction,
+    timestamp: u64,
+    /// The estimated number of bytes the action occupies.
+    size: usize,
+  },
   Transaction(Transaction),
   Checkpoint,
 }
 
+/// A branch of the undo tree which is not current.
+struct Branch {
+  /// The length of the undo stack at the point the branch forks from.
+  depth: usize,
+  /// The entries of the branch, in the order of the redo stack.
+  entries: Vec<HistoryEntry>,
+  /// The branches forking from this one.
+  branches: Vec<Self>,
+}
+
+impl Branch {
+  /// Shifts the fork points after dropping the given number of entries from the bottom of the undo stack.
+  /// The branches forking from the dropped entries can no longer be reached, so they are dropped too.
+  fn rebase(branches: &mut Vec<Self>, dropped: usize) {
+    branches.retain(|branch| branch.depth >= dropped);
+    for branch in branches {
+      branch.depth -= dropped;
+      Self::rebase(&mut branch.branches, dropped);
+    }
+  }
+}
+
 struct Transaction {
   id: usize,
   actions: Vec<EditorAction>,
+  timestamp: u64,
+  /// The estimated number of bytes the actions occupy.
+  size: usize,
+}
+
+/// Returns the estimated number of bytes the action occupies, which is its serialized size.
+fn action_size(action: &EditorAction) -> usize {
+  borsh::object_length(action).unwrap_or_default()
+}
+
+impl HistoryEntry {
+  /// Returns the estimated number of bytes the entry occupies, as computed when the entry was built.
+  const fn size(&self) -> usize {
+    match self {
+      Self::Single { size, .. } => *size,
+      Self::Transaction(transaction) => transaction.size,
+      Self::Checkpoint => 0,
+    }
+  }
+
+  /// Returns the summary of the entry, or `None` for checkpoints.
+  fn item(&self) -> Option<HistoryItem> {
+    match self {
+      Self::Single { action, timestamp, .. } => Some(HistoryItem {
+        label: action.label().to_string(),
+        timestamp: *timestamp,
+        undone: false,
+      }),
+      Self::Transaction(transaction) => Some(HistoryItem {
+        // Transactions are labeled by the action that started them.
+        label: transaction
+          .actions
+          .first()
+          .map(EditorAction::label)
+          .unwrap_or_default()
+          .to_string(),
+        timestamp: transaction.timestamp,
+        undone: false,
+      }),
+      Self::Checkpoint => None,
+    }
+  }
+}
+
+/// A summary of a history entry, used to present the history to the user.
+#[derive(Debug, Clone, PartialEq, Eq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct HistoryItem {
+  /// A human-readable description of the change.
+  pub label: String,
+  /// The time the change was made at, in milliseconds since the Unix epoch.
+  pub timestamp: u64,
+  /// Whether the change has been undone and can be redone.
+  pub undone: bool,
 }
 
 impl History {
@@ -34,6 +155,30 @@ impl History {
     Self::default()
   }
 
+  /// Creates a new history which timestamps its entries using the given clock.
+  #[must_use]
+  pub fn with_clock(clock: Option<Clock>) -> Self {
+    Self {
+      clock,
+      ..Self::default()
+    }
+  }
+
+  /// Sets the clock used to timestamp new entries.
+  pub const fn set_clock(&mut self, clock: Option<Clock>) {
+    self.clock = clock;
+  }
+
+  /// Sets the limits on the size of the undo stack and drops the entries that exceed them.
+  pub fn set_limits(&mut self, limits: HistoryLimits) {
+    self.limits = limits;
+    self.enforce_limits();
+  }
+
+  fn now(&self) -> u64 {
+    self.clock.map_or(0, |clock| clock())
+  }
+
   /// Returns the number of items in the undo stack.
   #[must_use]
   pub const fn undo_stack_len(&self) -> usize {
@@ -58,12 +203,18 @@ impl History {
     self.redo_stack.is_empty()
   }
 
+  /// Returns whether a transaction has been started and not ended yet.
+  #[must_use]
+  pub const fn has_active_transaction(&self) -> bool {
+    self.active_transaction.is_some()
+  }
+
   /// Creates a new transaction.
   /// After calling this method, all actions pushed to the history will be part of this transaction until `end_transaction` is called.
   pub fn start_transaction(&mut self) {
     if self.active_transaction.is_none() {
       self.active_transaction = Some(Vec::new());
-      self.redo_stack.clear();
+      self.fork();
     }
   }
 
@@ -72,26 +223,120 @@ impl History {
     if let Some(actions) = self.active_transaction.take()
       && !actions.is_empty()
     {
-      self.undo_stack.push(HistoryEntry::Transaction(Transaction {
+      let size = actions.iter().map(action_size).sum();
+      self.push_undo(HistoryEntry::Transaction(Transaction {
         id: self.last_transaction_id,
         actions,
+        timestamp: self.now(),
+        size,
       }));
-      self.redo_stack.clear();
+      self.fork();
       self.last_transaction_id += 1;
+      self.enforce_limits();
     }
   }
 
   /// Adds an action to the history.
   /// If there is an active transaction, the action will be added to that transaction.
-  /// Otherwise, it will be added as a single action to the undo stack.
+  /// Otherwise, it will be coalesced with the last single action if they are compatible and follow each other
+  /// within the [`COALESCING_WINDOW`], or added as a single action to the undo stack.
+  ///
+  /// The undone entries are kept as a new branch.
   pub fn push(&mut self, action: EditorAction) {
     if let Some(active_transaction) = &mut self.active_transaction {
       active_transaction.push(action);
+      self.fork();
     } else {
-      self.undo_stack.push(HistoryEntry::Single(action));
+      // An action following an undo starts a new branch, so it must not change the entry the old branch is built on.
+      let forked = self.fork();
+      let now = self.now();
+      if !forked
+        && let Some(HistoryEntry::Single {
+          action: last,
+          timestamp,
+          size,
+        }) = self.undo_stack.last_mut()
+        && now.saturating_sub(*timestamp) <= COALESCING_WINDOW
+        && last.coalesce(&action)
+      {
+        *timestamp = now;
+        let new_size = action_size(last);
+        self.undo_bytes = self.undo_bytes - *size + new_size;
+        *size = new_size;
+      } else {
+        let size = action_size(&action);
+        self.push_undo(HistoryEntry::Single {
+          action,
+          timestamp: now,
+          size,
+        });
+      }
+      self.enforce_limits();
+    }
+  }
+
+  /// Moves the redo stack to a new branch forking from the current point.
+  /// Returns `true` if there was anything to move.
+  fn fork(&mut self) -> bool {
+    if self.redo_stack.is_empty() {
+      return false;
     }
 
-    self.redo_stack.clear();
+    let depth = self.undo_stack.len();
+    let (nested, siblings) = std::mem::take(&mut self.branches)
+      .into_iter()
+      .partition(|branch| branch.depth > depth);
+    self.branches = siblings;
+    self.branches.push(Branch {
+      depth,
+      entries: std::mem::take(&mut self.redo_stack),
+      branches: nested,
+    });
+    true
+  }
+
+  /// Pushes the entry to the undo stack, keeping track of the occupied bytes.
+  fn push_undo(&mut self, entry: HistoryEntry) {
+    self.undo_bytes += entry.size();
+    self.undo_stack.push(entry);
+  }
+
+  /// Pops the last entry from the undo stack, keeping track of the occupied bytes.
+  fn pop_undo(&mut self) -> Option<HistoryEntry> {
+    let entry = self.undo_stack.pop()?;
+    self.undo_bytes -= entry.size();
+    Some(entry)
+  }
+
+  /// Drops the oldest entries of the undo stack until it fits into the limits.
+  /// The newest entry is always kept, even if it alone exceeds the byte limit.
+  fn enforce_limits(&mut self) {
+    let is_entry = |entry: &HistoryEntry| !matches!(entry, HistoryEntry::Checkpoint);
+    let max_entries = self
+      .limits
+      .max_entries
+      .map_or(usize::MAX, |max_entries| max_entries.max(1));
+    let max_bytes = self.limits.max_bytes.unwrap_or(usize::MAX);
+
+    let mut entries = self.undo_stack.iter().filter(|entry| is_entry(entry)).count();
+    let mut bytes = self.undo_bytes;
+    let mut dropped = 0;
+    for entry in &self.undo_stack {
+      if entries <= 1 || (entries <= max_entries && bytes <= max_bytes) {
+        break;
+      }
+      entries -= usize::from(is_entry(entry));
+      bytes -= entry.size();
+      dropped += 1;
+    }
+
+    // A checkpoint left at the bottom still marks the saved state correctly, since the entries above it are intact.
+    if dropped > 0 {
+      self.undo_stack.drain(..dropped);
+      self.undo_bytes = bytes;
+
+      Branch::rebase(&mut self.branches, dropped);
+    }
   }
 
   /// Pushes a checkpoint to the undo stack.
@@ -104,8 +349,9 @@ impl History {
     {
       return;
     }
-    self.undo_stack.push(HistoryEntry::Checkpoint);
-    self.redo_stack.clear();
+    // The branch forks after the checkpoint, so the saved state stays marked on both branches.
+    self.push_undo(HistoryEntry::Checkpoint);
+    self.fork();
   }
 
   /// Undoes the last action and return the events produced by the revoke.
@@ -123,34 +369,50 @@ impl History {
       match entry {
         HistoryEntry::Checkpoint => {
           // Remove the checkpoint and push it to redo, then undo the next real action.
-          let checkpoint = self.undo_stack.pop().unwrap();
+          let checkpoint = self.pop_undo().unwrap();
           self.redo_stack.push(checkpoint);
           return self.undo(embproj);
         }
-        HistoryEntry::Single(_) => {
-          let HistoryEntry::Single(mut action) = self.undo_stack.pop().unwrap() else {
+        HistoryEntry::Single { .. } => {
+          let HistoryEntry::Single {
+            mut action,
+            timestamp,
+            size,
+          } = self.pop_undo().unwrap()
+          else {
             unreachable!()
           };
           let events = action.revoke(embproj)?;
-          self.redo_stack.push(HistoryEntry::Single(action));
+          self.redo_stack.push(HistoryEntry::Single {
+            action,
+            timestamp,
+            size,
+          });
           return Ok(Some(events));
         }
         HistoryEntry::Transaction(transaction) => {
           if let Some(mut action) = transaction.actions.pop() {
+            // Sizes are estimated for performed actions, so measure the action before revoking it.
+            let size = action_size(&action);
             let events = action.revoke(embproj)?;
+            transaction.size -= size;
+            self.undo_bytes -= size;
             match self.redo_stack.last_mut() {
               Some(HistoryEntry::Transaction(last_t)) if last_t.id == transaction.id => {
                 last_t.actions.push(action);
+                last_t.size += size;
               }
               _ => {
                 self.redo_stack.push(HistoryEntry::Transaction(Transaction {
                   id: transaction.id,
                   actions: vec![action],
+                  timestamp: transaction.timestamp,
+                  size,
                 }));
               }
             }
             if transaction.actions.is_empty() {
-              self.undo_stack.pop();
+              self.pop_undo();
             }
             return Ok(Some(events));
           }
@@ -170,19 +432,30 @@ impl History {
         HistoryEntry::Checkpoint => {
           // The checkpoint sits at the top, so we've already redone everything above it.
           // Absorb it back into the undo stack and try the next entry.
-          self.undo_stack.push(self.redo_stack.pop().unwrap());
+          let checkpoint = self.redo_stack.pop().unwrap();
+          self.push_undo(checkpoint);
           return self.redo(embproj);
         }
-        HistoryEntry::Single(_) => {
-          let HistoryEntry::Single(mut action) = self.redo_stack.pop().unwrap() else {
+        HistoryEntry::Single { .. } => {
+          let HistoryEntry::Single {
+            mut action,
+            timestamp,
+            size,
+          } = self.redo_stack.pop().unwrap()
+          else {
             unreachable!()
           };
           let events = action.perform(embproj)?;
-          self.undo_stack.push(HistoryEntry::Single(action));
+          self.push_undo(HistoryEntry::Single {
+            action,
+            timestamp,
+            size,
+          });
 
           // If the next item in the redo stack is a checkpoint, absorb it into the undo stack.
           if matches!(self.redo_stack.last(), Some(HistoryEntry::Checkpoint)) {
-            self.undo_stack.push(self.redo_stack.pop().unwrap());
+            let checkpoint = self.redo_stack.pop().unwrap();
+            self.push_undo(checkpoint);
           }
 
           return Ok(Some(events));
@@ -190,14 +463,22 @@ impl History {
         HistoryEntry::Transaction(transaction) => {
           if let Some(mut action) = transaction.actions.pop() {
             let events = action.perform(embproj)?;
+            let size = action_size(&action);
+            // A re-performed action may differ in size slightly, so the undone part is only an estimate.
+            transaction.size = transaction.size.saturating_sub(size);
             match self.undo_stack.last_mut() {
               Some(HistoryEntry::Transaction(last_t)) if last_t.id == transaction.id => {
                 last_t.actions.push(action);
+                last_t.size += size;
+                self.undo_bytes += size;
               }
               _ => {
+                self.undo_bytes += size;
                 self.undo_stack.push(HistoryEntry::Transaction(Transaction {
                   id: transaction.id,
                   actions: vec![action],
+                  timestamp: transaction.timestamp,
+                  size,
                 }));
               }
             }
@@ -206,7 +487,8 @@ impl History {
 
               // If the next item in the redo stack is a checkpoint, absorb it into the undo stack.
               if matches!(self.redo_stack.last(), Some(HistoryEntry::Checkpoint)) {
-                self.undo_stack.push(self.redo_stack.pop().unwrap());
+                let checkpoint = self.redo_stack.pop().unwrap();
+                self.push_undo(checkpoint);
               }
             }
             return Ok(Some(events));
@@ -227,18 +509,18 @@ impl History {
 
     // Skip a checkpoint at the top before proceeding.
     if matches!(self.undo_stack.last(), Some(HistoryEntry::Checkpoint)) {
-      let checkpoint = self.undo_stack.pop().unwrap();
+      let checkpoint = self.pop_undo().unwrap();
       self.redo_stack.push(checkpoint);
     }
 
     if let Some(entry) = self.undo_stack.last() {
       match entry {
         HistoryEntry::Checkpoint => unreachable!(),
-        HistoryEntry::Single(_) => {
+        HistoryEntry::Single { .. } => {
           return self.undo(embproj);
         }
         HistoryEntry::Transaction(_) => {
-          let HistoryEntry::Transaction(mut transaction) = self.undo_stack.pop().unwrap() else {
+          let HistoryEntry::Transaction(mut transaction) = self.pop_undo().unwrap() else {
             unreachable!()
           };
 
@@ -256,11 +538,14 @@ impl History {
           match self.redo_stack.last_mut() {
             Some(HistoryEntry::Transaction(last_t)) if last_t.id == transaction.id => {
               last_t.actions.extend(revoked);
+              last_t.size += transaction.size;
             }
             _ => {
               self.redo_stack.push(HistoryEntry::Transaction(Transaction {
                 id: transaction.id,
                 actions: revoked,
+                timestamp: transaction.timestamp,
+                size: transaction.size,
               }));
             }
           }
@@ -280,10 +565,11 @@ impl History {
         HistoryEntry::Checkpoint => {
           // The checkpoint sits at the top, so we've already redone everything above it.
           // Absorb it back into the undo stack and try the next entry.
-          self.undo_stack.push(self.redo_stack.pop().unwrap());
+          let checkpoint = self.redo_stack.pop().unwrap();
+          self.push_undo(checkpoint);
           return self.redo_transaction(embproj);
         }
-        HistoryEntry::Single(_) => {
+        HistoryEntry::Single { .. } => {
           return self.redo(embproj);
         }
         HistoryEntry::Transaction(_) => {
@@ -301,21 +587,27 @@ impl History {
           }
 
           // Push to undo in original order.
+          let size = performed.iter().map(action_size).sum();
           match self.undo_stack.last_mut() {
             Some(HistoryEntry::Transaction(last_t)) if last_t.id == transaction.id => {
               last_t.actions.extend(performed);
+              last_t.size += size;
+              self.undo_bytes += size;
             }
             _ => {
-              self.undo_stack.push(HistoryEntry::Transaction(Transaction {
+              self.push_undo(HistoryEntry::Transaction(Transaction {
                 id: transaction.id,
                 actions: performed,
+                timestamp: transaction.timestamp,
+                size,
               }));
             }
           }
 
           // If the next item in the redo stack is a checkpoint, absorb it into the undo stack.
           if matches!(self.redo_stack.last(), Some(HistoryEntry::Checkpoint)) {
-            self.undo_stack.push(self.redo_stack.pop().unwrap());
+            let checkpoint = self.redo_stack.pop().unwrap();
+            self.push_undo(checkpoint);
           }
 
           return Ok(Some(all_events));
@@ -325,6 +617,136 @@ impl History {
     Ok(None)
   }
 
+  /// Returns the summaries of all entries, from the oldest to the newest, including the undone ones.
+  /// A transaction that has been partially undone is listed once, as not undone.
+  #[must_use]
+  pub fn items(&self) -> Vec<HistoryItem> {
+    let mut items: Vec<HistoryItem> = self.undo_stack.iter().filter_map(HistoryEntry::item).collect();
+    let mut redo_stack = self.redo_stack.iter().rev().peekable();
+    if let Some(HistoryEntry::Transaction(undone)) = redo_stack.peek()
+      && matches!(self.undo_stack.last(), Some(HistoryEntry::Transaction(done)) if done.id == undone.id)
+    {
+      redo_stack.next();
+    }
+    items.extend(
+      redo_stack
+        .filter_map(HistoryEntry::item)
+        .map(|item| HistoryItem { undone: true, ..item }),
+    );
+    items
+  }
+
+  /// Undoes or redoes whole entries until exactly `position` of them are applied,
+  /// where `0` is the state before the first entry.
+  /// Returns all events produced along the way.
+  pub fn jump_to(&mut self, embproj: &mut EmbroiderlyProject, position: usize) -> Result<Vec<EditorEvent>> {
+    let applied = |history: &Self| {
+      history
+        .undo_stack
+        .iter()
+        .filter(|entry| !matches!(entry, HistoryEntry::Checkpoint))
+        .count()
+    };
+
+    let mut all_events = Vec::new();
+    while applied(self) > position {
+      match self.undo_transaction(embproj)? {
+        Some(events) => all_events.extend(events),
+        None => break,
+      }
+    }
+    while applied(self) < position {
+      match self.redo_transaction(embproj)? {
+        Some(events) => all_events.extend(events),
+        None => break,
+      }
+    }
+    Ok(all_events)
+  }
+
+  /// Returns the summaries of the branches forking from the current one.
+  #[must_use]
+  pub fn branches(&self) -> Vec<HistoryBranch> {
+    self
+      .branches
+      .iter()
+      .map(|branch| {
+        let item = branch.entries.iter().rev().find_map(HistoryEntry::item);
+        HistoryBranch {
+          position: self.position_at(branch.depth),
+          label: item.as_ref().map(|item| item.label.clone()).unwrap_or_default(),
+          timestamp: item.map(|item| item.timestamp).unwrap_or_default(),
+        }
+      })
+      .collect()
+  }
+
+  /// Switches to the branch with the given index and redoes all of its entries.
+  /// The current branch is kept as a new branch, so it can be switched back to.
+  /// Returns all events produced along the way.
+  pub fn switch_branch(&mut self, embproj: &mut EmbroiderlyProject, index: usize) -> Result<Vec<EditorEvent>> {
+    let depth = self
+      .branches
+      .get(index)
+      .ok_or(Error::HistoryBranchNotFound(index))?
+      .depth;
+    let mut all_events = Vec::new();
+
+    // Move to the point the branch forks from.
+    while self.undo_stack.len() < depth {
+      match self.redo_transaction(embproj)? {
+        Some(events) => all_events.extend(events),
+        None => break,
+      }
+    }
+    while self.undo_stack.len() > depth {
+      if matches!(self.undo_stack.last(), Some(HistoryEntry::Checkpoint)) {
+        // Checkpoints are plain markers, so they are moved without undoing anything underneath.
+        let checkpoint = self.pop_undo().unwrap();
+        self.redo_stack.push(checkpoint);
+      } else {
+        match self.undo(embproj)? {
+          Some(events) => all_events.extend(events),
+          None => break,
+        }
+      }
+    }
+
+    self.fork();
+    let branch = self.branches.remove(index);
+    self.redo_stack = branch.entries;
+    self.branches.extend(branch.branches);
+
+    while let Some(events) = self.redo_transaction(embproj)? {
+      all_events.extend(events);
+    }
+    Ok(all_events)
+  }
+
+  /// Returns the number of applied entries at the given length of the undo stack, following the current branch.
+  fn position_at(&self, depth: usize) -> usize {
+    let is_entry = |entry: &&HistoryEntry| !matches!(entry, HistoryEntry::Checkpoint);
+    let applied = self.undo_stack.iter().take(depth).filter(is_entry).count();
+    if depth <= self.undo_stack.len() {
+      return applied;
+    }
+
+    // The rest of a partially undone transaction is not a separate entry.
+    let split = usize::from(matches!(
+      (self.undo_stack.last(), self.redo_stack.last()),
+      (Some(HistoryEntry::Transaction(done)), Some(HistoryEntry::Transaction(undone))) if done.id == undone.id
+    ));
+    applied
+      + self
+        .redo_stack
+        .iter()
+        .rev()
+        .skip(split)
+        .take(depth - self.undo_stack.len())
+        .filter(is_entry)
+        .count()
+  }
+
   /// Checks if there are any unsaved changes (i.e., the top of the undo stack is not a checkpoint).
   #[must_use]
   pub fn has_unsaved_changes(&self) -> bool {
@@ -467,7 +467,7 @@ mod transactions {
     assert_eq!(history.undo_stack_len(), 0);
     assert_eq!(history.redo_stack_len(), 1);
 
-    assert!(matches!(history.redo_stack.first(), Some(HistoryEntry::Single(_))));
+    assert!(matches!(history.redo_stack.first(), Some(HistoryEntry::Single { .. })));
   }
 
   #[test]
@@ -502,7 +502,7 @@ mod transactions {
     assert_eq!(history.undo_stack_len(), 2);
     assert_eq!(history.redo_stack_len(), 0);
 
-    assert!(matches!(history.undo_stack.last(), Some(HistoryEntry::Single(_))));
+    assert!(matches!(history.undo_stack.last(), Some(HistoryEntry::Single { .. })));
   }
 
   #[test]
@@ -669,3 +669,504 @@ mod transactions {
     assert!(!history.has_unsaved_changes());
   }
 }
+
+mod items {
+  use super::*;
+  use crate::actions::LayerAction;
+
+  fn fake_clock() -> u64 {
+    1_700_000_000_000
+  }
+
+  fn labels(history: &History) -> Vec<(String, bool)> {
+    history
+      .items()
+      .into_iter()
+      .map(|item| (item.label, item.undone))
+      .collect()
+  }
+
+  #[test]
+  fn test_items_are_labeled_and_timestamped() {
+    let mut history = History::with_clock(Some(fake_clock));
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    history.push_checkpoint();
+
+    history.start_transaction();
+    history.push(EditorAction::Layer(LayerAction::Add { added_index: None }));
+    history.push(EditorAction::Mock);
+    history.end_transaction();
+
+    let items = history.items();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].label, "Change pattern info");
+    assert_eq!(items[1].label, "Add layer");
+    assert!(items.iter().all(|item| item.timestamp == fake_clock() && !item.undone));
+  }
+
+  #[test]
+  fn test_items_without_clock_have_zero_timestamp() {
+    let mut history = History::default();
+    history.push(EditorAction::Mock);
+    assert_eq!(history.items()[0].timestamp, 0);
+  }
+
+  #[test]
+  fn test_items_include_undone_entries() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    history.push(EditorAction::Mock);
+    history.undo(&mut embproj).unwrap();
+    history.undo(&mut embproj).unwrap();
+
+    assert_eq!(
+      labels(&history),
+      vec![
+        (String::from("Change pattern info"), false),
+        (String::from("Change pattern info"), true),
+        (String::from("Mock"), true),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_items_list_partially_undone_transaction_once() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    history.start_transaction();
+    history.push(EditorAction::Mock);
+    history.push(EditorAction::Mock);
+    history.end_transaction();
+    history.undo(&mut embproj).unwrap();
+
+    assert_eq!(labels(&history), vec![(String::from("Mock"), false)]);
+  }
+
+  #[test]
+  fn test_jump_to() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    history.push_checkpoint();
+    history.start_transaction();
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    perform_and_push(&mut history, info_action("C"), &mut embproj);
+    history.end_transaction();
+    perform_and_push(&mut history, info_action("D"), &mut embproj);
+
+    history.jump_to(&mut embproj, 1).unwrap();
+    assert_eq!(embproj.pattern.info.title, "A");
+    assert_eq!(history.items().iter().filter(|item| item.undone).count(), 2);
+
+    history.jump_to(&mut embproj, 0).unwrap();
+    assert_eq!(embproj.pattern.info.title, "Untitled");
+
+    history.jump_to(&mut embproj, 2).unwrap();
+    assert_eq!(embproj.pattern.info.title, "C");
+
+    // Jumping past the end stops at the newest entry.
+    history.jump_to(&mut embproj, 10).unwrap();
+    assert_eq!(embproj.pattern.info.title, "D");
+    assert!(history.items().iter().all(|item| !item.undone));
+  }
+}
+
+mod limits {
+  use super::*;
+  use crate::history::HistoryLimits;
+
+  #[test]
+  fn test_max_entries_drops_oldest() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+    history.set_limits(HistoryLimits {
+      max_entries: Some(2),
+      max_bytes: None,
+    });
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    perform_and_push(&mut history, info_action("C"), &mut embproj);
+    assert_eq!(history.undo_stack_len(), 2);
+
+    history.undo(&mut embproj).unwrap();
+    history.undo(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.info.title, "A");
+    assert!(history.undo(&mut embproj).unwrap().is_none());
+  }
+
+  #[test]
+  fn test_max_entries_counts_transactions_as_one() {
+    let mut history = History::default();
+    history.set_limits(HistoryLimits {
+      max_entries: Some(1),
+      max_bytes: None,
+    });
+
+    history.push(EditorAction::Mock);
+    history.start_transaction();
+    history.push(EditorAction::Mock);
+    history.push(EditorAction::Mock);
+    history.end_transaction();
+
+    assert_eq!(history.undo_stack_len(), 1);
+    assert_eq!(history.items().len(), 1);
+  }
+
+  #[test]
+  fn test_dropping_keeps_checkpoints() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    history.push_checkpoint();
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    history.set_limits(HistoryLimits {
+      max_entries: Some(1),
+      max_bytes: None,
+    });
+
+    assert_eq!(history.undo_stack_len(), 2);
+    assert!(history.has_unsaved_changes());
+
+    history.undo(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.info.title, "A");
+    assert!(!history.has_unsaved_changes());
+  }
+
+  /// Checks that the tracked sizes match the actual sizes of the undo stack entries.
+  fn assert_tracked_sizes(history: &History) {
+    let mut total = 0;
+    for entry in &history.undo_stack {
+      let actual = match entry {
+        HistoryEntry::Single { action, .. } => borsh::object_length(action).unwrap(),
+        HistoryEntry::Transaction(transaction) => transaction
+          .actions
+          .iter()
+          .map(|action| borsh::object_length(action).unwrap())
+          .sum(),
+        HistoryEntry::Checkpoint => 0,
+      };
+      assert_eq!(entry.size(), actual);
+      total += actual;
+    }
+    assert_eq!(history.undo_bytes, total);
+  }
+
+  #[test]
+  fn test_sizes_are_tracked_across_undo_and_redo() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    history.push_checkpoint();
+    history.start_transaction();
+    perform_and_push(&mut history, info_action("BB"), &mut embproj);
+    perform_and_push(&mut history, info_action("CCC"), &mut embproj);
+    history.end_transaction();
+    assert_tracked_sizes(&history);
+
+    history.undo(&mut embproj).unwrap();
+    assert_tracked_sizes(&history);
+    history.undo_transaction(&mut embproj).unwrap();
+    assert_tracked_sizes(&history);
+    history.redo(&mut embproj).unwrap();
+    assert_tracked_sizes(&history);
+    history.redo_transaction(&mut embproj).unwrap();
+    assert_tracked_sizes(&history);
+    history.jump_to(&mut embproj, 0).unwrap();
+    assert_eq!(history.undo_bytes, 0);
+    history.jump_to(&mut embproj, 2).unwrap();
+    assert_tracked_sizes(&history);
+
+    history.set_limits(HistoryLimits {
+      max_entries: Some(1),
+      max_bytes: None,
+    });
+    assert_tracked_sizes(&history);
+  }
+
+  #[test]
+  fn test_max_bytes_keeps_newest_entry() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+    history.set_limits(HistoryLimits {
+      max_entries: None,
+      max_bytes: Some(1),
+    });
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    assert_eq!(history.undo_stack_len(), 1);
+
+    history.undo(&mut embproj).unwrap();
+    assert_eq!(embproj.pattern.info.title, "A");
+  }
+}
+
+mod coalescing {
+  use embroiderly_pattern::DisplaySettings;
+
+  use super::*;
+  use crate::actions::DisplayAction;
+
+  fn display_action(major_lines_interval: u16) -> EditorAction {
+    let mut display_settings = DisplaySettings::default();
+    display_settings.grid.major_lines_interval = major_lines_interval;
+    EditorAction::Display(DisplayAction::Update {
+      display_settings,
+      old_display_settings: None,
+    })
+  }
+
+  #[test]
+  fn test_consecutive_updates_are_undone_at_once() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+    let initial = embproj.display_settings.grid.major_lines_interval;
+
+    perform_and_push(&mut history, display_action(1), &mut embproj);
+    perform_and_push(&mut history, display_action(2), &mut embproj);
+    perform_and_push(&mut history, display_action(3), &mut embproj);
+    assert_eq!(history.undo_stack_len(), 1);
+
+    history.undo(&mut embproj).unwrap();
+    assert_eq!(embproj.display_settings.grid.major_lines_interval, initial);
+
+    history.redo(&mut embproj).unwrap();
+    assert_eq!(embproj.display_settings.grid.major_lines_interval, 3);
+  }
+
+  thread_local! {
+    static NOW: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+  }
+
+  fn thread_clock() -> u64 {
+    NOW.get()
+  }
+
+  #[test]
+  fn test_updates_apart_in_time_are_not_coalesced() {
+    let mut history = History::with_clock(Some(thread_clock));
+    let mut embproj = EmbroiderlyProject::default();
+
+    NOW.set(60_000);
+    perform_and_push(&mut history, display_action(1), &mut embproj);
+    NOW.set(60_500);
+    perform_and_push(&mut history, display_action(2), &mut embproj);
+    assert_eq!(history.undo_stack_len(), 1);
+
+    // A few minutes later, the same kind of change is a separate step.
+    NOW.set(240_000);
+    perform_and_push(&mut history, display_action(3), &mut embproj);
+    assert_eq!(history.undo_stack_len(), 2);
+
+    history.undo(&mut embproj).unwrap();
+    assert_eq!(embproj.display_settings.grid.major_lines_interval, 2);
+  }
+
+  #[test]
+  fn test_checkpoint_and_other_actions_break_coalescing() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, display_action(1), &mut embproj);
+    history.push_checkpoint();
+    perform_and_push(&mut history, display_action(2), &mut embproj);
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    perform_and_push(&mut history, display_action(3), &mut embproj);
+    assert_eq!(history.items().len(), 4);
+
+    history.undo(&mut embproj).unwrap();
+    assert_eq!(embproj.display_settings.grid.major_lines_interval, 2);
+  }
+}
+
+mod branches {
+  use super::*;
+  use crate::error::Error;
+  use crate::history::HistoryLimits;
+
+  fn title(embproj: &EmbroiderlyProject) -> &str {
+    &embproj.pattern.info.title
+  }
+
+  #[test]
+  fn test_push_after_undo_keeps_branch() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    history.undo(&mut embproj).unwrap();
+    perform_and_push(&mut history, info_action("C"), &mut embproj);
+
+    assert_eq!(history.redo_stack_len(), 0);
+    let branches = history.branches();
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].position, 1);
+    assert_eq!(branches[0].label, "Change pattern info");
+
+    history.switch_branch(&mut embproj, 0).unwrap();
+    assert_eq!(title(&embproj), "B");
+    assert_eq!(history.items().len(), 2);
+
+    // The abandoned branch is kept as well, so it's possible to switch back.
+    assert_eq!(history.branches().len(), 1);
+    history.switch_branch(&mut embproj, 0).unwrap();
+    assert_eq!(title(&embproj), "C");
+
+    history.undo(&mut embproj).unwrap();
+    history.undo(&mut embproj).unwrap();
+    assert_eq!(title(&embproj), "Untitled");
+  }
+
+  #[test]
+  fn test_switch_keeps_nested_branches() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    // A -> B -> C, then A -> B -> D, then A -> E.
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    perform_and_push(&mut history, info_action("C"), &mut embproj);
+    history.undo(&mut embproj).unwrap();
+    perform_and_push(&mut history, info_action("D"), &mut embproj);
+    history.undo(&mut embproj).unwrap();
+    history.undo(&mut embproj).unwrap();
+    perform_and_push(&mut history, info_action("E"), &mut embproj);
+
+    let branches = history.branches();
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].position, 1);
+
+    history.switch_branch(&mut embproj, 0).unwrap();
+    assert_eq!(title(&embproj), "D");
+
+    // Both the branch with C and the one with E are reachable now.
+    let positions: Vec<usize> = history.branches().iter().map(|branch| branch.position).collect();
+    assert_eq!(positions.len(), 2);
+    assert!(positions.contains(&1) && positions.contains(&2));
+
+    let c_branch = history
+      .branches()
+      .iter()
+      .position(|branch| branch.position == 2)
+      .unwrap();
+    history.switch_branch(&mut embproj, c_branch).unwrap();
+    assert_eq!(title(&embproj), "C");
+  }
+
+  #[test]
+  fn test_switch_across_checkpoint() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    history.undo(&mut embproj).unwrap();
+    history.push_checkpoint();
+    assert_eq!(history.branches().len(), 1);
+    assert!(!history.has_unsaved_changes());
+
+    history.switch_branch(&mut embproj, 0).unwrap();
+    assert_eq!(title(&embproj), "B");
+    assert!(history.has_unsaved_changes());
+
+    history.undo(&mut embproj).unwrap();
+    assert_eq!(title(&embproj), "A");
+    assert!(!history.has_unsaved_changes());
+  }
+
+  #[test]
+  fn test_switch_with_transactions() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    history.start_transaction();
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    history.end_transaction();
+    history.undo_transaction(&mut embproj).unwrap();
+
+    history.start_transaction();
+    perform_and_push(&mut history, info_action("C"), &mut embproj);
+    history.end_transaction();
+    assert_eq!(history.branches().len(), 1);
+
+    history.switch_branch(&mut embproj, 0).unwrap();
+    assert_eq!(title(&embproj), "B");
+
+    history.undo_transaction(&mut embproj).unwrap();
+    assert_eq!(title(&embproj), "Untitled");
+  }
+
+  #[test]
+  fn test_undo_does_not_coalesce_into_forked_entry() {
+    use embroiderly_pattern::DisplaySettings;
+
+    use crate::actions::DisplayAction;
+
+    let display_action = |major_lines_interval| {
+      let mut display_settings = DisplaySettings::default();
+      display_settings.grid.major_lines_interval = major_lines_interval;
+      EditorAction::Display(DisplayAction::Update {
+        display_settings,
+        old_display_settings: None,
+      })
+    };
+
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, display_action(1), &mut embproj);
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    history.undo(&mut embproj).unwrap();
+    perform_and_push(&mut history, display_action(2), &mut embproj);
+
+    assert_eq!(history.items().len(), 2);
+    history.undo(&mut embproj).unwrap();
+    assert_eq!(embproj.display_settings.grid.major_lines_interval, 1);
+  }
+
+  #[test]
+  fn test_limits_drop_unreachable_branches() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+
+    perform_and_push(&mut history, info_action("A"), &mut embproj);
+    perform_and_push(&mut history, info_action("B"), &mut embproj);
+    history.undo(&mut embproj).unwrap();
+    perform_and_push(&mut history, info_action("C"), &mut embproj);
+    perform_and_push(&mut history, info_action("D"), &mut embproj);
+
+    history.set_limits(HistoryLimits {
+      max_entries: Some(2),
+      max_bytes: None,
+    });
+    assert_eq!(history.branches()[0].position, 0);
+
+    history.set_limits(HistoryLimits {
+      max_entries: Some(1),
+      max_bytes: None,
+    });
+    assert!(history.branches().is_empty());
+  }
+
+  #[test]
+  fn test_switch_to_unknown_branch() {
+    let mut history = History::default();
+    let mut embproj = EmbroiderlyProject::default();
+    assert!(matches!(
+      history.switch_branch(&mut embproj, 0),
+      Err(Error::HistoryBranchNotFound(0))
+    ));
+  }
+}
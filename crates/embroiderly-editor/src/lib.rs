@@ -8,7 +8,13 @@ mod event;
 pub use event::*;
 
 mod history;
-pub use history::History;
+pub use history::{COALESCING_WINDOW, Clock, History, HistoryBranch, HistoryItem, HistoryLimits};
+
+mod selection;
+pub use selection::{Clipboard, Selection, SelectionArea};
+
+mod symmetry;
+pub use symmetry::{Symmetry, SymmetryMode};
 
 mod error;
 pub use error::*;
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+
+use embroiderly_pattern::{
+  Bounds, Coord, FullStitchKind, Layer, PaletteItem, PartStitchKind, Pattern, SpecialStitchModel, Stitch,
+};
+
+use crate::actions::stitches::{StitchTransform, scale_bounds};
+
+#[cfg(test)]
+#[path = "selection.test.rs"]
+mod tests;
+
+/// A region of a pattern selected by the user.
+#[derive(Debug, Clone, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub enum SelectionArea {
+  /// A rectangular region of whole cells.
+  Rectangle(Bounds),
+  /// A free-form polygon in pattern coordinates.
+  Lasso(Vec<(f32, f32)>),
+}
+
+impl SelectionArea {
+  /// Returns the smallest cell-aligned bounds that enclose the area.
+  #[must_use]
+  pub fn bounds(&self) -> Bounds {
+    match self {
+      Self::Rectangle(bounds) => *bounds,
+      Self::Lasso(points) => {
+        if points.is_empty() {
+          return Bounds::new(0, 0, 0, 0);
+        }
+
+        let (min_x, min_y, max_x, max_y) = points.iter().fold(
+          (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+          |(min_x, min_y, max_x, max_y), &(x, y)| (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+        );
+        let (min_x, min_y) = (min_x.max(0.0).floor(), min_y.max(0.0).floor());
+        let (max_x, max_y) = (max_x.max(min_x).ceil(), max_y.max(min_y).ceil());
+
+        Bounds::new(
+          min_x as u16,
+          min_y as u16,
+          (max_x - min_x) as u16,
+          (max_y - min_y) as u16,
+        )
+      }
+    }
+  }
+
+  /// Returns the area transformed the same way as the stitches within it.
+  #[must_use]
+  pub fn transformed(&self, transform: StitchTransform) -> Self {
+    let bounds = self.bounds();
+    match self {
+      Self::Rectangle(_) => Self::Rectangle(transform.transform_bounds(bounds)),
+      Self::Lasso(points) => Self::Lasso(
+        points
+          .iter()
+          .map(|&(x, y)| transform.transform_point(bounds, x, y))
+          .collect(),
+      ),
+    }
+  }
+
+  /// Returns the area scaled the same way as the stitches within it.
+  #[must_use]
+  pub fn scaled(&self, factor: f32) -> Self {
+    let bounds = self.bounds();
+    match self {
+      Self::Rectangle(_) => Self::Rectangle(scale_bounds(bounds, factor)),
+      Self::Lasso(points) => {
+        let (ox, oy) = (f32::from(bounds.x), f32::from(bounds.y));
+        Self::Lasso(
+          points
+            .iter()
+            .map(|&(x, y)| ((x - ox).mul_add(factor, ox), (y - oy).mul_add(factor, oy)))
+            .collect(),
+        )
+      }
+    }
+  }
+
+  /// Returns the stitches of the layer that fall into the area.
+  #[must_use]
+  pub fn stitches_in_layer(&self, layer: &Layer) -> Vec<Stitch> {
+    let bounds = self.bounds();
+
+    let candidates = layer
+      .fullstitches
+      .get_stitches_in_bounds(bounds)
+      .copied()
+      .map(Stitch::Full)
+      .chain(
+        layer
+          .partstitches
+          .get_stitches_in_bounds(bounds)
+          .copied()
+          .map(Stitch::Part),
+      )
+      .chain(
+        layer
+          .linestitches
+          .get_stitches_in_bounds(bounds)
+          .copied()
+          .map(Stitch::Line),
+      )
+      .chain(
+        layer
+          .nodestitches
+          .get_stitches_in_bounds(bounds)
+          .copied()
+          .map(Stitch::Node),
+      )
+      .chain(
+        layer
+          .specialstitches
+          .get_stitches_in_bounds(bounds)
+          .copied()
+          .map(Stitch::Special),
+      );
+
+    match self {
+      // The candidates are already filtered by the rectangle bounds.
+      Self::Rectangle(_) => candidates.collect(),
+      Self::Lasso(points) => candidates
+        .filter(|stitch| {
+          let (x, y) = stitch_center(stitch);
+          polygon_contains_point(points, x, y)
+        })
+        .collect(),
+    }
+  }
+}
+
+/// A selection of stitches on a pattern.
+#[derive(Debug, Clone, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct Selection {
+  pub area: SelectionArea,
+  /// The layer to select stitches from, or `None` to select across all layers.
+  pub layer_index: Option<u32>,
+}
+
+impl Selection {
+  #[must_use]
+  pub const fn new(area: SelectionArea, layer_index: Option<u32>) -> Self {
+    Self { area, layer_index }
+  }
+
+  /// Returns the selected stitches grouped by their layer indexes.
+  /// Layers go from the bottom to the top, and layers without selected stitches are skipped.
+  #[must_use]
+  pub fn stitches(&self, pattern: &Pattern) -> Vec<(u32, Vec<Stitch>)> {
+    let layer_indexes = match self.layer_index {
+      Some(layer_index) => vec![layer_index],
+      None => pattern.layers.positions().iter().rev().copied().collect(),
+    };
+
+    layer_indexes
+      .into_iter()
+      .filter_map(|layer_index| {
+        let layer = pattern.layers.get(layer_index)?;
+        let stitches = self.area.stitches_in_layer(layer);
+        (!stitches.is_empty()).then_some((layer_index, stitches))
+      })
+      .collect()
+  }
+
+  /// Copies the selected stitches to a clipboard.
+  #[must_use]
+  pub fn copy(&self, pattern: &Pattern) -> Clipboard {
+    let bounds = self.area.bounds();
+    let (dx, dy) = (-f32::from(bounds.x), -f32::from(bounds.y));
+
+    let mut palette = Vec::new();
+    let mut palindexes = HashMap::new();
+    let mut special_stitch_models = Vec::new();
+    let mut modindexes = HashMap::new();
+
+    let stitches = self
+      .stitches(pattern)
+      .into_iter()
+      .flat_map(|(_, stitches)| stitches)
+      .map(|stitch| {
+        let palindex = *palindexes.entry(stitch_palindex(&stitch)).or_insert_with(|| {
+          palette.push(pattern.palette[stitch_palindex(&stitch)].clone());
+          (palette.len() - 1) as u32
+        });
+
+        let mut stitch = translate_stitch(stitch, dx, dy);
+        set_stitch_palindex(&mut stitch, palindex);
+
+        if let Stitch::Special(special) = &mut stitch {
+          special.modindex = *modindexes.entry(special.modindex).or_insert_with(|| {
+            special_stitch_models.push(pattern.special_stitch_models[special.modindex as usize].clone());
+            (special_stitch_models.len() - 1) as u32
+          });
+        }
+
+        stitch
+      })
+      .collect();
+
+    Clipboard {
+      width: bounds.width,
+      height: bounds.height,
+      stitches,
+      palette,
+      special_stitch_models,
+    }
+  }
+}
+
+/// Stitches copied from a selection along with everything required to paste them into any pattern.
+#[derive(Debug, Clone, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct Clipboard {
+  pub width: u16,
+  pub height: u16,
+  /// The copied stitches relative to the top-left corner of the selection.
+  /// Their palette and model indexes refer to the clipboard's own palette and special stitch models.
+  pub stitches: Vec<Stitch>,
+  pub palette: Vec<PaletteItem>,
+  pub special_stitch_models: Vec<SpecialStitchModel>,
+}
+
+impl Clipboard {
+  /// Returns `true` if the clipboard contains no stitches.
+  #[must_use]
+  pub const fn is_empty(&self) -> bool {
+    self.stitches.is_empty()
+  }
+}
+
+/// Returns the stitch moved by the given offset.
+pub fn translate_stitch(stitch: Stitch, dx: f32, dy: f32) -> Stitch {
+  let shift = |coord: Coord, delta: f32| Coord::new(*coord + delta).unwrap();
+  match stitch {
+    Stitch::Full(mut fullstitch) => {
+      fullstitch.x = shift(fullstitch.x, dx);
+      fullstitch.y = shift(fullstitch.y, dy);
+      Stitch::Full(fullstitch)
+    }
+    Stitch::Part(mut partstitch) => {
+      partstitch.x = shift(partstitch.x, dx);
+      partstitch.y = shift(partstitch.y, dy);
+      Stitch::Part(partstitch)
+    }
+    Stitch::Line(mut line) => {
+      line.x = (shift(line.x.0, dx), shift(line.x.1, dx));
+      line.y = (shift(line.y.0, dy), shift(line.y.1, dy));
+      Stitch::Line(line)
+    }
+    Stitch::Node(mut node) => {
+      node.x = shift(node.x, dx);
+      node.y = shift(node.y, dy);
+      Stitch::Node(node)
+    }
+    Stitch::Special(mut special) => {
+      special.x = shift(special.x, dx);
+      special.y = shift(special.y, dy);
+      Stitch::Special(special)
+    }
+  }
+}
+
+/// Returns `true` if the stitch lies completely within the bounds.
+pub fn is_stitch_within_bounds(stitch: &Stitch, bounds: Bounds, models: &[SpecialStitchModel]) -> bool {
+  let (min_x, min_y) = (f32::from(bounds.x), f32::from(bounds.y));
+  let (max_x, max_y) = (min_x + f32::from(bounds.width), min_y + f32::from(bounds.height));
+  match stitch {
+    Stitch::Full(fullstitch) => bounds.contains_point(fullstitch.x, fullstitch.y),
+    Stitch::Part(partstitch) => bounds.contains_point(partstitch.x, partstitch.y),
+    Stitch::Node(node) => bounds.contains_point(node.x, node.y),
+    Stitch::Line(line) => {
+      [line.x.0, line.x.1].iter().all(|&x| *x >= min_x && *x <= max_x)
+        && [line.y.0, line.y.1].iter().all(|&y| *y >= min_y && *y <= max_y)
+    }
+    Stitch::Special(special) => models
+      .get(special.modindex as usize)
+      .is_some_and(|model| !special.is_outside_bounds(bounds, model)),
+  }
+}
+
+pub const fn stitch_palindex(stitch: &Stitch) -> u32 {
+  match stitch {
+    Stitch::Full(fullstitch) => fullstitch.palindex,
+    Stitch::Part(partstitch) => partstitch.palindex,
+    Stitch::Line(line) => line.palindex,
+    Stitch::Node(node) => node.palindex,
+    Stitch::Special(special) => special.palindex,
+  }
+}
+
+pub const fn set_stitch_palindex(stitch: &mut Stitch, palindex: u32) {
+  match stitch {
+    Stitch::Full(fullstitch) => fullstitch.palindex = palindex,
+    Stitch::Part(partstitch) => partstitch.palindex = palindex,
+    Stitch::Line(line) => line.palindex = palindex,
+    Stitch::Node(node) => node.palindex = palindex,
+    Stitch::Special(special) => special.palindex = palindex,
+  }
+}
+
+/// Returns the point used to decide whether a stitch falls into a free-form area.
+fn stitch_center(stitch: &Stitch) -> (f32, f32) {
+  match stitch {
+    Stitch::Full(fullstitch) => {
+      let offset = match fullstitch.kind {
+        FullStitchKind::Full => 0.5,
+        FullStitchKind::Petite => 0.25,
+      };
+      (*fullstitch.x + offset, *fullstitch.y + offset)
+    }
+    Stitch::Part(partstitch) => {
+      let offset = match partstitch.kind {
+        PartStitchKind::Half => 0.5,
+        PartStitchKind::Quarter => 0.25,
+      };
+      (*partstitch.x + offset, *partstitch.y + offset)
+    }
+    Stitch::Line(line) => (f32::midpoint(*line.x.0, *line.x.1), f32::midpoint(*line.y.0, *line.y.1)),
+    Stitch::Node(node) => (*node.x, *node.y),
+    Stitch::Special(special) => (*special.x, *special.y),
+  }
+}
+
+/// Checks if the point lies inside the polygon using the even-odd rule.
+fn polygon_contains_point(polygon: &[(f32, f32)], x: f32, y: f32) -> bool {
+  let mut inside = false;
+  let mut j = polygon.len().wrapping_sub(1);
+  for (i, &(xi, yi)) in polygon.iter().enumerate() {
+    let (xj, yj) = polygon[j];
+    if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+      inside = !inside;
+    }
+    j = i;
+  }
+  inside
+}
@@ -0,0 +1,108 @@
+use embroiderly_pattern::*;
+
+use super::*;
+
+fn palitem(number: &str) -> PaletteItem {
+  PaletteItem {
+    brand: String::from("DMC"),
+    number: String::from(number),
+    name: String::new(),
+    color: String::from("000000"),
+    blends: None,
+    symbol: None,
+  }
+}
+
+fn full_stitch(x: f32, y: f32, palindex: u32) -> Stitch {
+  Stitch::Full(FullStitch {
+    x: Coord::new(x).unwrap(),
+    y: Coord::new(y).unwrap(),
+    palindex,
+    kind: FullStitchKind::Full,
+  })
+}
+
+fn create_pattern() -> Pattern {
+  let mut pattern = Pattern {
+    palette: Palette::from(vec![palitem("310"), palitem("321"), palitem("699")]),
+    ..Pattern::default()
+  };
+  pattern.add_stitches(
+    0,
+    vec![
+      full_stitch(1.0, 1.0, 2),
+      full_stitch(2.0, 1.0, 0),
+      full_stitch(5.0, 5.0, 1),
+    ],
+  );
+  pattern
+}
+
+#[test]
+fn test_lasso_bounds() {
+  let area = SelectionArea::Lasso(vec![(1.5, 2.2), (4.1, 0.7), (3.0, 5.0)]);
+  assert_eq!(area.bounds(), Bounds::new(1, 0, 4, 5));
+
+  assert_eq!(SelectionArea::Lasso(vec![]).bounds(), Bounds::new(0, 0, 0, 0));
+}
+
+#[test]
+fn test_transformed_and_scaled_areas() {
+  let area = SelectionArea::Rectangle(Bounds::new(0, 1, 2, 1));
+  assert_eq!(
+    area.transformed(StitchTransform::Rotate(1)),
+    SelectionArea::Rectangle(Bounds::new(0, 0, 1, 2))
+  );
+
+  let area = SelectionArea::Rectangle(Bounds::new(2, 2, 4, 2));
+  assert_eq!(area.scaled(0.5), SelectionArea::Rectangle(Bounds::new(2, 2, 2, 1)));
+}
+
+#[test]
+fn test_rectangle_selects_stitches_in_bounds() {
+  let pattern = create_pattern();
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(0, 0, 3, 3)), Some(0));
+
+  let selected = selection.stitches(&pattern);
+  assert_eq!(
+    selected,
+    vec![(0, vec![full_stitch(1.0, 1.0, 2), full_stitch(2.0, 1.0, 0)])]
+  );
+}
+
+#[test]
+fn test_lasso_selects_stitches_inside_polygon() {
+  let pattern = create_pattern();
+  // A triangle that covers the center of the cell (1, 1), but not the one of the cell (2, 1).
+  let selection = Selection::new(SelectionArea::Lasso(vec![(0.0, 0.0), (3.2, 0.0), (0.0, 3.2)]), None);
+
+  let selected = selection.stitches(&pattern);
+  assert_eq!(selected, vec![(0, vec![full_stitch(1.0, 1.0, 2)])]);
+}
+
+#[test]
+fn test_selection_skips_missing_and_empty_layers() {
+  let pattern = create_pattern();
+
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(8, 8, 2, 2)), None);
+  assert!(selection.stitches(&pattern).is_empty());
+
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(0, 0, 3, 3)), Some(5));
+  assert!(selection.stitches(&pattern).is_empty());
+}
+
+#[test]
+fn test_copy_uses_own_palette_and_relative_coordinates() {
+  let pattern = create_pattern();
+  let selection = Selection::new(SelectionArea::Rectangle(Bounds::new(1, 1, 2, 1)), Some(0));
+
+  let clipboard = selection.copy(&pattern);
+  assert_eq!(clipboard.width, 2);
+  assert_eq!(clipboard.height, 1);
+  assert_eq!(clipboard.palette, vec![palitem("699"), palitem("310")]);
+  assert_eq!(
+    clipboard.stitches,
+    vec![full_stitch(0.0, 0.0, 0), full_stitch(1.0, 0.0, 1)]
+  );
+  assert!(clipboard.special_stitch_models.is_empty());
+}
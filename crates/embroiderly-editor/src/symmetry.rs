@@ -0,0 +1,68 @@
+use embroiderly_pattern::{Bounds, Pattern, Stitch};
+
+use crate::actions::stitches::StitchTransform;
+use crate::selection::is_stitch_within_bounds;
+
+#[cfg(test)]
+#[path = "symmetry.test.rs"]
+mod tests;
+
+/// Specifies the axes the stitches are mirrored about while drawing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub enum SymmetryMode {
+  /// Mirrors stitches about the vertical axis (left to right).
+  Vertical,
+  /// Mirrors stitches about the horizontal axis (top to bottom).
+  Horizontal,
+  /// Mirrors stitches about both axes.
+  FourWay,
+}
+
+/// A mirror drawing mode of a pattern project.
+#[derive(Debug, Clone, Copy, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct Symmetry {
+  pub mode: SymmetryMode,
+  /// The point the axes go through, in pattern coordinates.
+  /// It is snapped to the nearest cell edge or cell center.
+  pub center: (f32, f32),
+}
+
+impl Symmetry {
+  #[must_use]
+  pub const fn new(mode: SymmetryMode, center: (f32, f32)) -> Self {
+    Self { mode, center }
+  }
+
+  /// Returns the stitch followed by its mirrored counterparts.
+  /// Counterparts that coincide with the stitch (e.g., when it lies on an axis) or fall outside the pattern's fabric are skipped.
+  #[must_use]
+  pub fn mirror(&self, stitch: Stitch, pattern: &Pattern) -> Vec<Stitch> {
+    let models = &pattern.special_stitch_models;
+    let fabric_bounds = Bounds::new(0, 0, pattern.fabric.width, pattern.fabric.height);
+
+    // Mirroring within bounds reflects about their center, so build bounds centered on the symmetry center.
+    let (cx, cy) = (self.center.0.max(0.0), self.center.1.max(0.0));
+    let bounds = Bounds::new(0, 0, (cx * 2.0).round() as u16, (cy * 2.0).round() as u16);
+
+    let horizontal = || StitchTransform::MirrorHorizontal.transform_stitch(bounds, stitch, models);
+    let vertical = || StitchTransform::MirrorVertical.transform_stitch(bounds, stitch, models);
+
+    let counterparts = match self.mode {
+      SymmetryMode::Vertical => vec![horizontal()],
+      SymmetryMode::Horizontal => vec![vertical()],
+      SymmetryMode::FourWay => vec![
+        horizontal(),
+        vertical(),
+        StitchTransform::MirrorVertical.transform_stitch(bounds, horizontal(), models),
+      ],
+    };
+
+    let mut stitches = vec![stitch];
+    for counterpart in counterparts {
+      if !stitches.contains(&counterpart) && is_stitch_within_bounds(&counterpart, fabric_bounds, models) {
+        stitches.push(counterpart);
+      }
+    }
+    stitches
+  }
+}
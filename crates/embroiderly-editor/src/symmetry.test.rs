@@ -0,0 +1,107 @@
+use embroiderly_pattern::*;
+
+use super::*;
+
+fn coord(value: f32) -> Coord {
+  Coord::new(value).unwrap()
+}
+
+fn pattern(width: u16, height: u16) -> Pattern {
+  Pattern::new(Fabric {
+    width,
+    height,
+    ..Fabric::default()
+  })
+}
+
+fn full_stitch(x: f32, y: f32) -> Stitch {
+  Stitch::Full(FullStitch {
+    x: coord(x),
+    y: coord(y),
+    palindex: 0,
+    kind: FullStitchKind::Full,
+  })
+}
+
+#[test]
+fn test_vertical_symmetry() {
+  let symmetry = Symmetry::new(SymmetryMode::Vertical, (5.0, 5.0));
+  assert_eq!(
+    symmetry.mirror(full_stitch(1.0, 2.0), &pattern(10, 10)),
+    vec![full_stitch(1.0, 2.0), full_stitch(8.0, 2.0)]
+  );
+}
+
+#[test]
+fn test_horizontal_symmetry_reflects_part_stitches() {
+  let symmetry = Symmetry::new(SymmetryMode::Horizontal, (5.0, 5.0));
+  let half = |y: f32, direction: PartStitchDirection| {
+    Stitch::Part(PartStitch {
+      x: coord(1.0),
+      y: coord(y),
+      palindex: 0,
+      direction,
+      kind: PartStitchKind::Half,
+    })
+  };
+  assert_eq!(
+    symmetry.mirror(half(1.0, PartStitchDirection::Forward), &pattern(10, 10)),
+    vec![
+      half(1.0, PartStitchDirection::Forward),
+      half(8.0, PartStitchDirection::Backward)
+    ]
+  );
+}
+
+#[test]
+fn test_four_way_symmetry_reflects_back_stitches() {
+  let symmetry = Symmetry::new(SymmetryMode::FourWay, (2.0, 2.0));
+  let back = |x: (f32, f32), y: (f32, f32)| {
+    Stitch::Line(LineStitch {
+      x: (coord(x.0), coord(x.1)),
+      y: (coord(y.0), coord(y.1)),
+      palindex: 0,
+      kind: LineStitchKind::Back,
+    })
+  };
+  assert_eq!(
+    symmetry.mirror(back((0.0, 1.0), (0.0, 1.0)), &pattern(4, 4)),
+    vec![
+      back((0.0, 1.0), (0.0, 1.0)),
+      back((4.0, 3.0), (0.0, 1.0)),
+      back((0.0, 1.0), (4.0, 3.0)),
+      back((4.0, 3.0), (4.0, 3.0)),
+    ]
+  );
+}
+
+#[test]
+fn test_symmetry_skips_coinciding_counterparts() {
+  // The axis goes through the center of the cell column 2.
+  let symmetry = Symmetry::new(SymmetryMode::FourWay, (2.5, 4.0));
+  assert_eq!(
+    symmetry.mirror(full_stitch(2.0, 0.0), &pattern(5, 8)),
+    vec![full_stitch(2.0, 0.0), full_stitch(2.0, 7.0)]
+  );
+}
+
+#[test]
+fn test_symmetry_clips_counterparts_to_fabric() {
+  // The axis is off the fabric's center, so some counterparts land beyond its edges.
+  let symmetry = Symmetry::new(SymmetryMode::Vertical, (2.0, 5.0));
+  let pattern = pattern(10, 10);
+  assert_eq!(
+    symmetry.mirror(full_stitch(1.0, 2.0), &pattern),
+    vec![full_stitch(1.0, 2.0), full_stitch(2.0, 2.0)]
+  );
+  assert_eq!(
+    symmetry.mirror(full_stitch(6.0, 2.0), &pattern),
+    vec![full_stitch(6.0, 2.0)]
+  );
+
+  let symmetry = Symmetry::new(SymmetryMode::FourWay, (7.0, 5.0));
+  assert_eq!(
+    symmetry.mirror(full_stitch(2.0, 2.0), &pattern),
+    vec![full_stitch(2.0, 2.0), full_stitch(2.0, 7.0)]
+  );
+}
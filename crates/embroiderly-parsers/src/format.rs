@@ -1,9 +1,23 @@
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
 use crate::error::Error;
 
+#[cfg(test)]
+#[path = "format.test.rs"]
+mod tests;
+
+/// The signature of local file headers in ZIP archives.
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+
+/// Returns the lowercased extension of the file.
+fn file_extension(file_name: &str) -> String {
+  file_name.split('.').next_back().unwrap_or_default().to_lowercase()
+}
+
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub enum PatternFormat {
   /// Probably, stands for `Cross-Stitch Design`.
-  /// Only **read-only** mode is currently available.
   Xsd,
 
   /// Stands for `Open Cross-Stitch`.
@@ -18,12 +32,59 @@ pub enum PatternFormat {
   EmbProj,
 }
 
+impl PatternFormat {
+  /// Detects the pattern format by the file content.
+  /// If the content is not recognized, the format is determined by the file extension.
+  pub fn detect(data: &[u8], file_name: &str) -> std::result::Result<Self, Error> {
+    if let Some(format) = Self::sniff(data) {
+      return Ok(format);
+    }
+
+    // The `.xml` extension is too generic, so such files are accepted only if their content looks like OXS.
+    let extension = file_extension(file_name);
+    if extension == "xml" {
+      return Err(Error::UnsupportedPatternType(extension));
+    }
+
+    Self::try_from(file_name)
+  }
+
+  /// Recognizes the pattern format by the file content.
+  #[must_use]
+  pub fn sniff(data: &[u8]) -> Option<Self> {
+    if data.starts_with(ZIP_SIGNATURE) {
+      Some(Self::EmbProj)
+    } else if crate::pmaker::has_valid_signature(data) {
+      Some(Self::Xsd)
+    } else if has_chart_root_element(data) {
+      Some(Self::Oxs)
+    } else {
+      None
+    }
+  }
+}
+
+/// Checks whether the data is an XML document with the `chart` root element, as OXS requires.
+fn has_chart_root_element(data: &[u8]) -> bool {
+  let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data); // Skip the UTF-8 BOM.
+  let mut reader = Reader::from_reader(data);
+  let mut buf = Vec::new();
+  loop {
+    match reader.read_event_into(&mut buf) {
+      Ok(Event::Start(e) | Event::Empty(e)) => return e.name().as_ref() == b"chart",
+      Ok(Event::Text(text)) if !text.iter().all(u8::is_ascii_whitespace) => return false,
+      Ok(Event::Eof) | Err(_) => return false,
+      _ => {}
+    }
+    buf.clear();
+  }
+}
+
 impl TryFrom<&str> for PatternFormat {
   type Error = Error;
 
   fn try_from(file_name: &str) -> std::result::Result<Self, Self::Error> {
-    let extension = file_name.split('.').next_back().unwrap_or_default();
-    match extension.to_lowercase().as_str() {
+    match file_extension(file_name).as_str() {
       "xsd" => Ok(Self::Xsd),
       "oxs" | "xml" => Ok(Self::Oxs),
       "embproj" => Ok(Self::EmbProj),
@@ -57,12 +118,40 @@ pub enum PaletteFormat {
   Embroiderly,
 }
 
+impl PaletteFormat {
+  /// Detects the palette format by the file content.
+  /// If the content is not recognized, the format is determined by the file extension.
+  pub fn detect(data: &[u8], file_name: &str) -> std::result::Result<Self, Error> {
+    Self::sniff(data).map_or_else(|| Self::try_from(file_name), Ok)
+  }
+
+  /// Recognizes the palette format by the file content.
+  ///
+  /// Pattern Maker user palettes and XSPro palettes don't have any distinctive content,
+  /// so they are recognized only by the file extension.
+  #[must_use]
+  pub fn sniff(data: &[u8]) -> Option<Self> {
+    if crate::pmaker::is_master_palette(data) {
+      return Some(Self::Pmaker);
+    }
+
+    // Both remaining formats are text ones, while the binary formats contain null bytes.
+    let text = std::str::from_utf8(data).ok().filter(|text| !text.contains('\0'))?;
+    if serde_json::from_str::<serde_json::Value>(text).is_ok_and(|value| value.is_array()) {
+      Some(Self::Embroiderly)
+    } else if text.trim_start().starts_with('"') {
+      Some(Self::Ursa)
+    } else {
+      None
+    }
+  }
+}
+
 impl TryFrom<&str> for PaletteFormat {
   type Error = Error;
 
   fn try_from(file_name: &str) -> std::result::Result<Self, Self::Error> {
-    let extension = file_name.split('.').next_back().unwrap_or_default();
-    match extension.to_lowercase().as_str() {
+    match file_extension(file_name).as_str() {
       "master" | "user" => Ok(Self::Pmaker),
       "threads" => Ok(Self::Ursa),
       "rng" => Ok(Self::Xspro),
@@ -0,0 +1,74 @@
+use super::*;
+
+fn load_testdata(path: &str) -> Vec<u8> {
+  let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("../../testdata")
+    .join(path);
+  std::fs::read(path).unwrap()
+}
+
+#[test]
+fn sniffs_pattern_formats() {
+  assert!(PatternFormat::sniff(&load_testdata("patterns/pmaker/piggies.xsd")) == Some(PatternFormat::Xsd));
+  assert!(PatternFormat::sniff(&load_testdata("patterns/rainbow.oxs")) == Some(PatternFormat::Oxs));
+  assert!(PatternFormat::sniff(b"PK\x03\x04\x14\x00\x00\x00") == Some(PatternFormat::EmbProj));
+}
+
+#[test]
+fn sniffs_oxs_without_declaration() {
+  assert!(PatternFormat::sniff(b"\xEF\xBB\xBF<!-- OXS -->\n<chart>\n</chart>") == Some(PatternFormat::Oxs));
+  assert!(PatternFormat::sniff(b"<chart/>") == Some(PatternFormat::Oxs));
+}
+
+#[test]
+fn does_not_sniff_unknown_patterns() {
+  assert!(PatternFormat::sniff(b"").is_none());
+  assert!(PatternFormat::sniff(b"<?xml version=\"1.0\"?>\n<svg></svg>").is_none());
+  assert!(PatternFormat::sniff(b"chart <chart></chart>").is_none());
+}
+
+#[test]
+fn detects_pattern_format_despite_wrong_extension() {
+  let data = load_testdata("patterns/pmaker/piggies.xsd");
+  assert!(PatternFormat::detect(&data, "piggies.oxs").unwrap() == PatternFormat::Xsd);
+  assert!(PatternFormat::detect(&data, "piggies").unwrap() == PatternFormat::Xsd);
+}
+
+#[test]
+fn falls_back_to_pattern_extension() {
+  assert!(PatternFormat::detect(b"", "pattern.oxs").unwrap() == PatternFormat::Oxs);
+  assert!(matches!(
+    PatternFormat::detect(b"", "pattern.txt"),
+    Err(Error::UnsupportedPatternType(ext)) if ext == "txt"
+  ));
+}
+
+#[test]
+fn rejects_xml_that_is_not_oxs() {
+  assert!(PatternFormat::detect(b"<chart></chart>", "pattern.xml").unwrap() == PatternFormat::Oxs);
+  assert!(matches!(
+    PatternFormat::detect(b"<svg></svg>", "pattern.xml"),
+    Err(Error::UnsupportedPatternType(ext)) if ext == "xml"
+  ));
+}
+
+#[test]
+fn sniffs_palette_formats() {
+  assert!(PaletteFormat::sniff(&load_testdata("palettes/pmaker/DMC.master")) == Some(PaletteFormat::Pmaker));
+  assert!(PaletteFormat::sniff(&load_testdata("palettes/ursa/DMC.threads")) == Some(PaletteFormat::Ursa));
+  assert!(
+    PaletteFormat::sniff(br#"[{"brand":"DMC","number":"310","name":"Black","color":"000000"}]"#)
+      == Some(PaletteFormat::Embroiderly)
+  );
+  assert!(PaletteFormat::sniff(&load_testdata("palettes/xspro/DMC.rng")).is_none());
+}
+
+#[test]
+fn falls_back_to_palette_extension() {
+  let data = load_testdata("palettes/xspro/DMC.rng");
+  assert!(PaletteFormat::detect(&data, "DMC.rng").unwrap() == PaletteFormat::Xspro);
+  assert!(matches!(
+    PaletteFormat::detect(&data, "DMC.txt"),
+    Err(Error::UnsupportedPaletteType(ext)) if ext == "txt"
+  ));
+}
@@ -15,7 +15,7 @@ pub mod xspro;
 mod utils;
 
 pub fn parse_pattern(data: &[u8], file_name: &str) -> Result<EmbroiderlyProject> {
-  match PatternFormat::try_from(file_name)? {
+  match PatternFormat::detect(data, file_name)? {
     PatternFormat::Xsd => pmaker::parse_pattern(data),
     PatternFormat::Oxs => oxs::parse_pattern(data),
     PatternFormat::EmbProj => embproj::parse_pattern(data),
@@ -25,7 +25,7 @@ pub fn parse_pattern(data: &[u8], file_name: &str) -> Result<EmbroiderlyProject>
 
 pub fn save_pattern(embproj: &EmbroiderlyProject, format: PatternFormat) -> Result<Vec<u8>> {
   match format {
-    PatternFormat::Xsd => Err(Error::UnsupportedPatternType(PatternFormat::Xsd.to_string()).into()),
+    PatternFormat::Xsd => pmaker::save_pattern(embproj),
     PatternFormat::Oxs => oxs::save_pattern(embproj),
     PatternFormat::EmbProj => embproj::save_pattern(embproj),
   }
@@ -33,7 +33,7 @@ pub fn save_pattern(embproj: &EmbroiderlyProject, format: PatternFormat) -> Resu
 }
 
 pub fn parse_palette(data: &[u8], file_name: &str) -> Result<Vec<BrandPaletteItem>> {
-  match PaletteFormat::try_from(file_name)? {
+  match PaletteFormat::detect(data, file_name)? {
     PaletteFormat::Pmaker => pmaker::parse_palette(data).map_err(Error::FailedToParse),
     PaletteFormat::Ursa => ursa::parse_palette(data).map_err(Error::FailedToParse),
     PaletteFormat::Xspro => xspro::parse_palette(data).map_err(Error::FailedToParse),
@@ -1,5 +1,7 @@
 mod palette;
+pub(crate) use palette::is_master_palette;
 pub use palette::parse_palette;
 
 mod xsd;
-pub use xsd::parse_pattern;
+pub(crate) use xsd::has_valid_signature;
+pub use xsd::{parse_pattern, save_pattern};
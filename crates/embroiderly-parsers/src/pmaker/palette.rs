@@ -7,6 +7,12 @@ use super::xsd::read_palette_item;
 
 const MASTER_PALETTE_MAGIC: [u8; 4] = [0x31, 0x54, 0x76, 0x98];
 
+/// Checks whether the data starts with the magic number of Pattern Maker master palettes.
+/// User palettes don't have any magic number, so they can't be recognized.
+pub fn is_master_palette(data: &[u8]) -> bool {
+  data.starts_with(&MASTER_PALETTE_MAGIC)
+}
+
 /// Parses a Pattern Maker palette from raw bytes.
 /// `file_name` must include the extension (`.master` or `.user`) to determine the palette type.
 #[tracing::instrument(name = "parse_pmaker_palette", level = "debug", skip_all)]
@@ -1,15 +1,16 @@
-//! A parser for the proprietary XSD pattern format.
+//! A parser and writer for the proprietary XSD pattern format.
 //!
 //! The specification of this format was obtained by reverse engineering several applications, including Pattern Maker.
 //! Therefore, it is rather incomplete, but it contains all the knowledge to be able to extract enough data to display the pattern.
 
-use std::io::{self, Read, Seek, SeekFrom};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::sync::LazyLock;
 
 use anyhow::Result;
-use byteorder::{LittleEndian, ReadBytesExt as _};
+use byteorder::{LittleEndian, ReadBytesExt as _, WriteBytesExt as _};
 
 use crate::utils::read::ReadXspExt as _;
+use crate::utils::write::WriteXspExt as _;
 
 #[cfg(test)]
 #[path = "xsd.test.rs"]
@@ -54,6 +55,34 @@ const PAGE_HEADER_AND_FOOTER_LENGTH: usize = 119;
 
 const SPECIAL_STITCH_NAME_LENGTH: usize = 255;
 
+/// Special stitches reference their models by a single byte.
+const MAX_SPECIAL_STITCH_MODELS: usize = u8::MAX as usize + 1;
+
+/// This brand is absent in the list of Pattern Maker's thread brands, so it is read back as `Unknown`.
+const UNKNOWN_THREAD_BRAND_ID: u8 = 255;
+
+const DEFAULT_STITCH_FONT: &str = "CrossStitch3";
+
+/// The version of Pattern Maker that is written into the saved patterns.
+const PATTERN_MAKER_VERSION: PatternMakerVersion = PatternMakerVersion((8, 4, 12, 4));
+
+/// The random numbers used to encode the stitches data.
+/// They are stored in the file alongside the data, so any values are suitable.
+const XSD_RANDOM_NUMBERS: [i32; 4] = [0x2F4B_1C7A, 0x1A3C_5E79, 0x6B2D_4F13, 0x0C5E_7A91];
+
+/// Pattern Maker splits the stitches data into chunks that cover this number of cells.
+const STITCHES_DATA_CHUNK_LENGTH: usize = 4096;
+
+/// The value of the cell that doesn't contain any stitches.
+const EMPTY_STITCH_DATA: i32 = 0x0F00_0000;
+
+/// The flag of the stitches data element which means that the next element must be copied several times.
+const COPY_STITCH_DATA_FLAG: i32 = 0x4000_0000;
+
+/// The maximum number of cells with small stitches.
+/// Their positions must not overlap with [`COPY_STITCH_DATA_FLAG`].
+const MAX_SMALL_STITCH_BUFFERS: usize = 0x4000;
+
 #[allow(clippy::struct_field_names)]
 #[derive(Debug, PartialEq)]
 pub struct Pattern {
@@ -95,6 +124,18 @@ impl From<PatternInfo> for embroiderly_pattern::PatternInfo {
   }
 }
 
+impl From<embroiderly_pattern::PatternInfo> for PatternInfo {
+  fn from(info: embroiderly_pattern::PatternInfo) -> Self {
+    Self {
+      title: info.title,
+      author: info.author,
+      company: String::new(),
+      copyright: info.copyright,
+      description: info.description,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Fabric {
   pub width: u16,
@@ -118,6 +159,19 @@ impl From<Fabric> for embroiderly_pattern::Fabric {
   }
 }
 
+impl From<embroiderly_pattern::Fabric> for Fabric {
+  fn from(fabric: embroiderly_pattern::Fabric) -> Self {
+    Self {
+      width: fabric.width,
+      height: fabric.height,
+      stitches_per_inch: fabric.spi,
+      kind: fabric.kind,
+      name: fabric.name,
+      color: fabric.color,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct PaletteItem {
   pub brand: String,
@@ -157,13 +211,23 @@ impl From<Blend> for embroiderly_pattern::Blend {
   }
 }
 
+impl From<embroiderly_pattern::Blend> for Blend {
+  fn from(blend: embroiderly_pattern::Blend) -> Self {
+    Self {
+      brand: blend.brand,
+      number: blend.number,
+      strands: 1, // We don't track the blend strands, so use a single strand of each color.
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Bead {
   pub length: f32,
   pub diameter: f32,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq)]
 pub struct Formats {
   pub symbol: SymbolFormat,
   pub back_stitch: LineStitchFormat,
@@ -182,6 +246,16 @@ pub struct SymbolFormat {
   pub fg_color: String,
 }
 
+impl Default for SymbolFormat {
+  fn default() -> Self {
+    Self {
+      use_alt_bg_color: false,
+      bg_color: String::from("FFFFFF"),
+      fg_color: String::from("000000"),
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct LineStitchFormat {
   pub use_alt_color: bool,
@@ -190,6 +264,17 @@ pub struct LineStitchFormat {
   pub thickness: f32,
 }
 
+impl Default for LineStitchFormat {
+  fn default() -> Self {
+    Self {
+      use_alt_color: false,
+      color: String::from("000000"),
+      style: 5,
+      thickness: 1.0,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct NodeStitchFormat {
   pub use_dot_style: bool,
@@ -198,6 +283,17 @@ pub struct NodeStitchFormat {
   pub thickness: f32,
 }
 
+impl Default for NodeStitchFormat {
+  fn default() -> Self {
+    Self {
+      use_dot_style: true,
+      use_alt_color: false,
+      color: String::from("000000"),
+      thickness: 4.0,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct FontFormat {
   pub font_name: Option<String>,
@@ -207,6 +303,18 @@ pub struct FontFormat {
   pub small_stitch_size: u8,
 }
 
+impl Default for FontFormat {
+  fn default() -> Self {
+    Self {
+      font_name: None,
+      bold: false,
+      italic: false,
+      stitch_size: 100,
+      small_stitch_size: 60,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Symbols {
   pub full: Option<u16>,
@@ -238,6 +346,17 @@ impl TryFrom<FullStitch> for embroiderly_pattern::FullStitch {
   }
 }
 
+impl From<embroiderly_pattern::FullStitch> for FullStitch {
+  fn from(fs: embroiderly_pattern::FullStitch) -> Self {
+    Self {
+      x: fs.x.into_inner(),
+      y: fs.y.into_inner(),
+      palindex: fs.palindex as u8,
+      kind: fs.kind.into(),
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum FullStitchKind {
   Full,
@@ -253,6 +372,15 @@ impl From<FullStitchKind> for embroiderly_pattern::FullStitchKind {
   }
 }
 
+impl From<embroiderly_pattern::FullStitchKind> for FullStitchKind {
+  fn from(kind: embroiderly_pattern::FullStitchKind) -> Self {
+    match kind {
+      embroiderly_pattern::FullStitchKind::Full => Self::Full,
+      embroiderly_pattern::FullStitchKind::Petite => Self::Petite,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct PartStitch {
   pub x: f32,
@@ -276,6 +404,18 @@ impl TryFrom<PartStitch> for embroiderly_pattern::PartStitch {
   }
 }
 
+impl From<embroiderly_pattern::PartStitch> for PartStitch {
+  fn from(ps: embroiderly_pattern::PartStitch) -> Self {
+    Self {
+      x: ps.x.into_inner(),
+      y: ps.y.into_inner(),
+      palindex: ps.palindex as u8,
+      direction: ps.direction.into(),
+      kind: ps.kind.into(),
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum PartStitchDirection {
   Forward,
@@ -291,6 +431,15 @@ impl From<PartStitchDirection> for embroiderly_pattern::PartStitchDirection {
   }
 }
 
+impl From<embroiderly_pattern::PartStitchDirection> for PartStitchDirection {
+  fn from(dir: embroiderly_pattern::PartStitchDirection) -> Self {
+    match dir {
+      embroiderly_pattern::PartStitchDirection::Forward => Self::Forward,
+      embroiderly_pattern::PartStitchDirection::Backward => Self::Backward,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum PartStitchKind {
   Half,
@@ -306,6 +455,15 @@ impl From<PartStitchKind> for embroiderly_pattern::PartStitchKind {
   }
 }
 
+impl From<embroiderly_pattern::PartStitchKind> for PartStitchKind {
+  fn from(kind: embroiderly_pattern::PartStitchKind) -> Self {
+    match kind {
+      embroiderly_pattern::PartStitchKind::Half => Self::Half,
+      embroiderly_pattern::PartStitchKind::Quarter => Self::Quarter,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct LineStitch {
   pub x: (f32, f32),
@@ -333,6 +491,17 @@ impl TryFrom<LineStitch> for embroiderly_pattern::LineStitch {
   }
 }
 
+impl From<embroiderly_pattern::LineStitch> for LineStitch {
+  fn from(ls: embroiderly_pattern::LineStitch) -> Self {
+    Self {
+      x: (ls.x.0.into_inner(), ls.x.1.into_inner()),
+      y: (ls.y.0.into_inner(), ls.y.1.into_inner()),
+      palindex: ls.palindex as u8,
+      kind: ls.kind.into(),
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum LineStitchKind {
   Back,
@@ -348,6 +517,15 @@ impl From<LineStitchKind> for embroiderly_pattern::LineStitchKind {
   }
 }
 
+impl From<embroiderly_pattern::LineStitchKind> for LineStitchKind {
+  fn from(kind: embroiderly_pattern::LineStitchKind) -> Self {
+    match kind {
+      embroiderly_pattern::LineStitchKind::Back => Self::Back,
+      embroiderly_pattern::LineStitchKind::Straight => Self::Straight,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct NodeStitch {
   pub x: f32,
@@ -371,6 +549,18 @@ impl TryFrom<NodeStitch> for embroiderly_pattern::NodeStitch {
   }
 }
 
+impl From<embroiderly_pattern::NodeStitch> for NodeStitch {
+  fn from(ns: embroiderly_pattern::NodeStitch) -> Self {
+    Self {
+      x: ns.x.into_inner(),
+      y: ns.y.into_inner(),
+      rotated: ns.rotated,
+      palindex: ns.palindex as u8,
+      kind: ns.kind.into(),
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum NodeStitchKind {
   FrenchKnot,
@@ -386,6 +576,15 @@ impl From<NodeStitchKind> for embroiderly_pattern::NodeStitchKind {
   }
 }
 
+impl From<embroiderly_pattern::NodeStitchKind> for NodeStitchKind {
+  fn from(kind: embroiderly_pattern::NodeStitchKind) -> Self {
+    match kind {
+      embroiderly_pattern::NodeStitchKind::FrenchKnot => Self::FrenchKnot,
+      embroiderly_pattern::NodeStitchKind::Bead => Self::Bead,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct SpecialStitch {
   pub x: f32,
@@ -411,6 +610,19 @@ impl TryFrom<SpecialStitch> for embroiderly_pattern::SpecialStitch {
   }
 }
 
+impl From<embroiderly_pattern::SpecialStitch> for SpecialStitch {
+  fn from(special_stitch: embroiderly_pattern::SpecialStitch) -> Self {
+    Self {
+      x: special_stitch.x.into_inner(),
+      y: special_stitch.y.into_inner(),
+      rotation: special_stitch.rotation,
+      flip: special_stitch.flip,
+      palindex: special_stitch.palindex as u8,
+      modindex: special_stitch.modindex as u8,
+    }
+  }
+}
+
 #[derive(Debug, Default, PartialEq)]
 pub struct SpecialStitchModel {
   pub unique_name: String,
@@ -450,6 +662,20 @@ impl TryFrom<SpecialStitchModel> for embroiderly_pattern::SpecialStitchModel {
   }
 }
 
+impl From<embroiderly_pattern::SpecialStitchModel> for SpecialStitchModel {
+  fn from(spsmodel: embroiderly_pattern::SpecialStitchModel) -> Self {
+    Self {
+      unique_name: spsmodel.unique_name,
+      name: spsmodel.name,
+      width: spsmodel.width,
+      height: spsmodel.height,
+      linestitches: spsmodel.linestitches.into_iter().map(LineStitch::from).collect(),
+      nodestitches: spsmodel.nodestitches.into_iter().map(NodeStitch::from).collect(),
+      curvedstitches: spsmodel.curvedstitches.into_iter().map(CurvedStitch::from).collect(),
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct CurvedStitch {
   pub points: Vec<(f32, f32)>,
@@ -469,6 +695,18 @@ impl TryFrom<CurvedStitch> for embroiderly_pattern::CurvedStitch {
   }
 }
 
+impl From<embroiderly_pattern::CurvedStitch> for CurvedStitch {
+  fn from(cs: embroiderly_pattern::CurvedStitch) -> Self {
+    Self {
+      points: cs
+        .points
+        .into_iter()
+        .map(|(x, y)| (x.into_inner(), y.into_inner()))
+        .collect(),
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Grid {
   pub major_lines_interval: u16,
@@ -504,6 +742,15 @@ impl From<GridLineStyle> for embroiderly_pattern::GridLine {
   }
 }
 
+impl From<embroiderly_pattern::GridLine> for GridLineStyle {
+  fn from(line: embroiderly_pattern::GridLine) -> Self {
+    Self {
+      color: line.color,
+      thickness: line.thickness,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct PatternSettings {
   pub default_stitch_font: String,
@@ -524,6 +771,30 @@ pub struct StitchSettings {
   pub stitch_outline: StitchOutline,
 }
 
+impl Default for StitchSettings {
+  fn default() -> Self {
+    Self {
+      default_strands: StitchStrands {
+        full: 2,
+        petite: 2,
+        half: 2,
+        quarter: 2,
+        back: 1,
+        straight: 1,
+        french_knot: 2,
+        special: 2,
+      },
+      display_thickness: [1.0, 1.5, 2.5, 3.0, 3.5, 4.0, 4.5, 5.0, 5.5, 6.0, 6.5, 7.0, 4.0],
+      outlined_stitches: true,
+      stitch_outline: StitchOutline {
+        color: None,
+        color_percentage: 80,
+        thickness: 0.2,
+      },
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct StitchOutline {
   pub color: Option<String>,
@@ -545,6 +816,23 @@ pub struct SymbolSettings {
   pub use_triangles_behind_quarter_stitches: bool,
 }
 
+impl Default for SymbolSettings {
+  fn default() -> Self {
+    Self {
+      screen_spacing: (1, 1),
+      printer_spacing: (1, 1),
+      scale_using_maximum_font_width: true,
+      scale_using_font_height: true,
+      stitch_size: 100,
+      small_stitch_size: 60,
+      draw_symbols_over_backstitches: false,
+      show_stitch_color: false,
+      use_large_half_stitch_symbol: false,
+      use_triangles_behind_quarter_stitches: false,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct PrintSettings {
   pub font: Font,
@@ -556,6 +844,32 @@ pub struct PrintSettings {
   pub center_chart_on_pages: bool,
 }
 
+impl Default for PrintSettings {
+  fn default() -> Self {
+    Self {
+      font: Font {
+        name: String::from("Courier New"),
+        size: 10,
+        weight: 400,
+        italic: false,
+      },
+      header: String::from("&l&t &r&n"),
+      footer: String::new(),
+      margins: PageMargins {
+        left: 0.5,
+        right: 0.5,
+        top: 0.5,
+        bottom: 0.5,
+        header: 0.5,
+        footer: 0.5,
+      },
+      show_page_numbers: true,
+      show_adjacent_page_numbers: true,
+      center_chart_on_pages: false,
+    }
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Font {
   pub name: String,
@@ -684,6 +998,178 @@ pub fn parse_pattern(data: &[u8]) -> Result<embroiderly_pattern::EmbroiderlyProj
   )
 }
 
+#[tracing::instrument(name = "save_xsd", level = "debug", skip_all)]
+pub fn save_pattern(embproj: &embroiderly_pattern::EmbroiderlyProject) -> Result<Vec<u8>> {
+  let embroiderly_pattern::EmbroiderlyProject {
+    pattern,
+    display_settings,
+    ..
+  } = embproj;
+
+  let palette_size = pattern.palette.len();
+  if palette_size > FORMAT_LENGTH {
+    anyhow::bail!("Pattern Maker supports up to {FORMAT_LENGTH} palette items, found: {palette_size}");
+  }
+
+  let models_count = pattern.special_stitch_models.len();
+  if models_count > MAX_SPECIAL_STITCH_MODELS {
+    anyhow::bail!(
+      "Pattern Maker supports up to {MAX_SPECIAL_STITCH_MODELS} special stitch models, found: {models_count}"
+    );
+  }
+
+  // Pattern Maker stores the palette in the visual order, so the stitches must reference visual positions.
+  let mut index_to_position = vec![0u8; palette_size];
+  for (position, &index) in pattern.palette.positions().iter().enumerate() {
+    index_to_position[index as usize] = position as u8;
+  }
+
+  let mut palette = Vec::with_capacity(palette_size);
+  let mut formats = Vec::with_capacity(palette_size);
+  let mut symbols = Vec::with_capacity(palette_size);
+  for &index in pattern.palette.positions() {
+    let palitem = pattern.palette.get(index).unwrap();
+
+    let blends = palitem.blends.clone().filter(|blends| !blends.is_empty());
+    if let Some(blends) = &blends
+      && blends.len() > BLEND_COLORS_NUMBER
+    {
+      anyhow::bail!(
+        "Pattern Maker supports up to {BLEND_COLORS_NUMBER} blend colors, found: {} in {} {}",
+        blends.len(),
+        palitem.brand,
+        palitem.number,
+      );
+    }
+
+    // Symbols are stored as UTF-16 code units, so only the Basic Multilingual Plane is supported.
+    let symbol = match &palitem.symbol {
+      Some(symbol) => match u16::try_from(u32::from(symbol.char)) {
+        Ok(code) => Some(code),
+        Err(_) => anyhow::bail!(
+          "Pattern Maker supports symbols up to U+FFFF, found: U+{:04X} in {} {}",
+          u32::from(symbol.char),
+          palitem.brand,
+          palitem.number,
+        ),
+      },
+      None => None,
+    };
+
+    palette.push(PaletteItem {
+      brand: palitem.brand.clone(),
+      number: palitem.number.clone(),
+      name: palitem.name.clone(),
+      color: palitem.color.clone(),
+      blends: blends.map(|blends| blends.into_iter().map(Blend::from).collect()),
+      bead: None,
+      strands: None,
+    });
+    formats.push(Formats {
+      font: FontFormat {
+        font_name: palitem.symbol.as_ref().map(|symbol| symbol.font.clone()),
+        ..Default::default()
+      },
+      ..Default::default()
+    });
+    symbols.push(Symbols {
+      full: symbol,
+      petite: None,
+      half: None,
+      quarter: None,
+      french_knot: None,
+      bead: None,
+    });
+  }
+
+  let layer = pattern.flatten_visible_layers();
+  let xsd_pattern = Pattern {
+    info: pattern.info.clone().into(),
+    fabric: pattern.fabric.clone().into(),
+    palette,
+    formats,
+    symbols,
+    fullstitches: layer
+      .fullstitches
+      .iter()
+      .map(|&stitch| FullStitch {
+        palindex: index_to_position[stitch.palindex as usize],
+        ..stitch.into()
+      })
+      .collect(),
+    partstitches: layer
+      .partstitches
+      .iter()
+      .map(|&stitch| PartStitch {
+        palindex: index_to_position[stitch.palindex as usize],
+        ..stitch.into()
+      })
+      .collect(),
+    linestitches: layer
+      .linestitches
+      .iter()
+      .map(|&stitch| LineStitch {
+        palindex: index_to_position[stitch.palindex as usize],
+        ..stitch.into()
+      })
+      .collect(),
+    nodestitches: layer
+      .nodestitches
+      .iter()
+      .map(|&stitch| NodeStitch {
+        palindex: index_to_position[stitch.palindex as usize],
+        ..stitch.into()
+      })
+      .collect(),
+    specialstitches: layer
+      .specialstitches
+      .iter()
+      .map(|&stitch| SpecialStitch {
+        palindex: index_to_position[stitch.palindex as usize],
+        ..stitch.into()
+      })
+      .collect(),
+    special_stitch_models: pattern
+      .special_stitch_models
+      .iter()
+      .cloned()
+      .map(SpecialStitchModel::from)
+      .collect(),
+    grid: Grid {
+      major_lines_interval: display_settings.grid.major_lines_interval,
+      minor_screen_lines: display_settings.grid.minor_lines.clone().into(),
+      major_screen_lines: display_settings.grid.major_lines.clone().into(),
+      minor_printer_lines: GridLineStyle {
+        color: String::from("000000"),
+        thickness: 2.0,
+      },
+      major_printer_lines: GridLineStyle {
+        color: String::from("000000"),
+        thickness: 7.0,
+      },
+    },
+    pattern_settings: PatternSettings {
+      default_stitch_font: String::from(DEFAULT_STITCH_FONT),
+      view: match display_settings.display_mode {
+        embroiderly_pattern::DisplayMode::Stitches => 0,
+        embroiderly_pattern::DisplayMode::Mixed => 1,
+        embroiderly_pattern::DisplayMode::Solid => 2,
+      },
+      zoom: 0,
+      show_grid: display_settings.show_grid,
+      show_rulers: display_settings.show_rulers,
+      show_centering_marks: false,
+      show_fabric_colors_with_symbols: false,
+      gaps_between_stitches: false,
+    },
+    stitch_settings: StitchSettings::default(),
+    symbol_settings: SymbolSettings::default(),
+    print_settings: PrintSettings::default(),
+  };
+
+  save_pattern_inner(&xsd_pattern)
+}
+
 #[tracing::instrument(name = "parse_xsd_raw", level = "debug", skip_all)]
 fn parse_pattern_inner(data: &[u8]) -> Result<Pattern> {
   let mut cursor = std::io::Cursor::new(data);
@@ -774,11 +1260,91 @@ fn parse_pattern_inner(data: &[u8]) -> Result<Pattern> {
   })
 }
 
+#[tracing::instrument(name = "save_xsd_raw", level = "debug", skip_all)]
+fn save_pattern_inner(pattern: &Pattern) -> Result<Vec<u8>> {
+  let mut writer = Vec::new();
+
+  let total_stitches_count = (pattern.fabric.width as usize) * (pattern.fabric.height as usize);
+  let (stitches_data, small_stitch_buffers) = map_stitches_into_stitches_data(
+    &pattern.fullstitches,
+    &pattern.partstitches,
+    pattern.fabric.width as usize,
+    total_stitches_count,
+  )?;
+
+  let joints_count = pattern.linestitches.len() + pattern.nodestitches.len() + pattern.specialstitches.len();
+  let Ok(joints_count) = u16::try_from(joints_count) else {
+    anyhow::bail!(
+      "Pattern Maker supports up to {} back, straight, special stitches, french knots and beads, found: {joints_count}",
+      u16::MAX,
+    );
+  };
+
+  write_signature(&mut writer)?;
+  writer.write_padding(4)?;
+
+  write_pmaker_version(&mut writer, &PATTERN_MAKER_VERSION)?;
+
+  writer.write_padding(727)?; // Fill the unknown data.
+
+  writer.write_u16::<LittleEndian>(pattern.fabric.width)?;
+  writer.write_u16::<LittleEndian>(pattern.fabric.height)?;
+
+  writer.write_u32::<LittleEndian>(small_stitch_buffers.len() as u32)?;
+  writer.write_u16::<LittleEndian>(joints_count)?;
+
+  writer.write_u16::<LittleEndian>(pattern.fabric.stitches_per_inch.0.into())?;
+  writer.write_u16::<LittleEndian>(pattern.fabric.stitches_per_inch.1.into())?;
+  writer.write_padding(6)?;
+
+  write_palette(&mut writer, &pattern.palette)?;
+  write_formats(&mut writer, &pattern.formats)?;
+  write_symbols(&mut writer, &pattern.symbols)?;
+
+  write_pattern_and_print_settings(&mut writer, &pattern.pattern_settings, &pattern.print_settings)?;
+
+  write_grid(&mut writer, &pattern.grid)?;
+
+  writer.write_cstring(&pattern.fabric.name, FABRIC_COLOR_NAME_LENGTH)?;
+  writer.write_hex_color(&pattern.fabric.color)?;
+  writer.write_padding(65)?;
+  write_pattern_info(&mut writer, &pattern.info)?;
+  writer.write_padding(6)?;
+  writer.write_cstring(&pattern.fabric.kind, FABRIC_KIND_NAME_LENGTH)?;
+  writer.write_padding(206)?;
+
+  write_stitch_settings(&mut writer, &pattern.stitch_settings)?;
+  write_symbol_settings(&mut writer, &pattern.symbol_settings)?;
+
+  writer.write_padding(16412)?; // Fill library info.
+  writer.write_padding(512)?; // Fill machine export info.
+
+  write_stitches_data(&mut writer, &stitches_data)?;
+  write_small_stitch_buffers(&mut writer, &small_stitch_buffers)?;
+
+  write_special_stitch_models(&mut writer, &pattern.special_stitch_models)?;
+
+  write_joints(
+    &mut writer,
+    &pattern.linestitches,
+    &pattern.nodestitches,
+    &pattern.specialstitches,
+    &[],
+  )?;
+
+  Ok(writer)
+}
+
 fn read_signature<R: Read>(reader: &mut R) -> io::Result<u16> {
   let signature = reader.read_u16::<LittleEndian>()?;
   Ok(signature)
 }
 
+/// Checks whether the data starts with the signature of Pattern Maker v4 patterns.
+pub fn has_valid_signature(data: &[u8]) -> bool {
+  read_signature(&mut io::Cursor::new(data)).is_ok_and(|signature| signature == VALID_SIGNATURE)
+}
+
 fn read_pmaker_version<R: Read>(reader: &mut R) -> io::Result<PatternMakerVersion> {
   Ok(PatternMakerVersion((
     reader.read_u16::<LittleEndian>()?,
@@ -788,6 +1354,19 @@ fn read_pmaker_version<R: Read>(reader: &mut R) -> io::Result<PatternMakerVersio
   )))
 }
 
+fn write_signature<W: Write>(writer: &mut W) -> io::Result<()> {
+  writer.write_u16::<LittleEndian>(VALID_SIGNATURE)
+}
+
+fn write_pmaker_version<W: Write>(writer: &mut W, version: &PatternMakerVersion) -> io::Result<()> {
+  let PatternMakerVersion((a, b, c, d)) = version;
+  writer.write_u16::<LittleEndian>(*a)?;
+  writer.write_u16::<LittleEndian>(*b)?;
+  writer.write_u16::<LittleEndian>(*c)?;
+  writer.write_u16::<LittleEndian>(*d)?;
+  Ok(())
+}
+
 struct PatternMakerVersion((u16, u16, u16, u16));
 
 impl std::fmt::Display for PatternMakerVersion {
@@ -817,6 +1396,78 @@ fn read_palette<R: Read + Seek>(reader: &mut R) -> io::Result<Vec<PaletteItem>>
   Ok(palette)
 }
 
+/// Writes the color palette of the pattern.
+fn write_palette<W: Write>(writer: &mut W, palette: &[PaletteItem]) -> io::Result<()> {
+  tracing::debug!("Writing palette");
+
+  writer.write_u16::<LittleEndian>(palette.len() as u16)?;
+
+  for pi in palette {
+    write_palette_item(writer, pi)?;
+  }
+
+  for position in 0..palette.len() {
+    writer.write_u16::<LittleEndian>(position as u16)?;
+  }
+  write_palette_items_notes(writer, palette.len())?;
+
+  for pi in palette {
+    write_palette_item_strands(writer, pi.strands.as_ref().unwrap_or(&StitchStrands::default()))?;
+  }
+
+  Ok(())
+}
+
+/// Writes a single palette item.
+fn write_palette_item<W: Write>(writer: &mut W, palitem: &PaletteItem) -> io::Result<()> {
+  /// Writes the blend colors of the palette item.
+  fn write_blends<W: Write>(writer: &mut W, blends: &[Blend]) -> io::Result<()> {
+    writer.write_u16::<LittleEndian>(blends.len() as u16)?;
+
+    // Write blends.
+    for blend in blends {
+      writer.write_u8(thread_brand_id(&blend.brand))?;
+      writer.write_cstring(&blend.number, COLOR_NUMBER_LENGTH)?;
+    }
+    writer.write_padding((BLEND_COLORS_NUMBER - blends.len()) * 12)?; // Fill empty blends.
+
+    // Write blend's strands.
+    for blend in blends {
+      writer.write_u8(blend.strands)?;
+    }
+    writer.write_padding(BLEND_COLORS_NUMBER - blends.len())?; // Fill empty blend's strands.
+
+    Ok(())
+  }
+
+  writer.write_padding(2)?;
+  writer.write_u8(thread_brand_id(&palitem.brand))?;
+  writer.write_cstring(&palitem.number, COLOR_NUMBER_LENGTH)?;
+  writer.write_cstring(&palitem.name, COLOR_NAME_LENGTH)?;
+  writer.write_hex_color(&palitem.color)?;
+  writer.write_padding(1)?;
+  write_blends(writer, palitem.blends.as_deref().unwrap_or_default())?;
+  if let Some(bead) = &palitem.bead {
+    writer.write_u32::<LittleEndian>(1)?;
+    writer.write_u16::<LittleEndian>((bead.length * 10.0).round() as u16)?;
+    writer.write_u16::<LittleEndian>((bead.diameter * 10.0).round() as u16)?;
+  } else {
+    writer.write_u32::<LittleEndian>(0)?;
+    writer.write_padding(4)?;
+  }
+  writer.write_padding(2)?;
+
+  Ok(())
+}
+
+/// Returns the identifier of the thread brand in Pattern Maker.
+fn thread_brand_id(brand: &str) -> u8 {
+  PM_THREAD_BRANDS
+    .iter()
+    .find_map(|(&id, name)| (name == brand).then_some(id))
+    .unwrap_or(UNKNOWN_THREAD_BRAND_ID)
+}
+
 /// Reads a single palette item.
 pub fn read_palette_item<R: Read + Seek>(reader: &mut R) -> io::Result<PaletteItem> {
   /// Reads the blend colors of the palette item.
@@ -911,6 +1562,33 @@ fn read_palette_item_strands<R: Read>(reader: &mut R) -> io::Result<StitchStrand
   })
 }
 
+/// Writes empty notes of the palette items.
+fn write_palette_items_notes<W: Write>(writer: &mut W, palette_size: usize) -> io::Result<()> {
+  for _ in 0..palette_size {
+    for _ in 0..STITCH_TYPES_NUMBER {
+      writer.write_u16::<LittleEndian>(0)?; // The note length.
+    }
+  }
+  Ok(())
+}
+
+fn write_palette_item_strands<W: Write>(writer: &mut W, strands: &StitchStrands<Option<u8>>) -> io::Result<()> {
+  // Order is important!
+  for value in [
+    strands.full,
+    strands.half,
+    strands.quarter,
+    strands.back,
+    strands.french_knot,
+    strands.petite,
+    strands.special,
+    strands.straight,
+  ] {
+    writer.write_u16::<LittleEndian>(value.unwrap_or(0).into())?;
+  }
+  Ok(())
+}
+
 fn read_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> io::Result<Vec<Formats>> {
   tracing::debug!("Reading formats");
 
@@ -948,6 +1626,21 @@ fn read_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> io::Resu
   Ok(formats)
 }
 
+fn write_formats<W: Write>(writer: &mut W, formats: &[Formats]) -> io::Result<()> {
+  tracing::debug!("Writing formats");
+
+  write_symbol_formats(writer, formats.iter().map(|format| &format.symbol))?;
+  write_line_formats(writer, formats.iter().map(|format| &format.back_stitch))?;
+  writer.write_padding(FORMAT_LENGTH * 4)?; // Fill unknown formats.
+  write_line_formats(writer, formats.iter().map(|format| &format.special_stitch))?;
+  write_line_formats(writer, formats.iter().map(|format| &format.straight_stitch))?;
+  write_node_formats(writer, formats.iter().map(|format| &format.french_knot))?;
+  write_node_formats(writer, formats.iter().map(|format| &format.bead))?;
+  write_font_formats(writer, formats.iter().map(|format| &format.font))?;
+
+  Ok(())
+}
+
 fn read_symbol_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> io::Result<Vec<SymbolFormat>> {
   let mut formats = Vec::with_capacity(palette_size);
   for _ in 0..palette_size {
@@ -966,6 +1659,22 @@ fn read_symbol_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> i
   Ok(formats)
 }
 
+fn write_symbol_formats<'a, W: Write>(
+  writer: &mut W,
+  formats: impl ExactSizeIterator<Item = &'a SymbolFormat>,
+) -> io::Result<()> {
+  let palette_size = formats.len();
+  for format in formats {
+    writer.write_u16::<LittleEndian>(format.use_alt_bg_color.into())?;
+    writer.write_hex_color(&format.bg_color)?;
+    writer.write_padding(1)?;
+    writer.write_hex_color(&format.fg_color)?;
+    writer.write_padding(1)?;
+  }
+  writer.write_padding((FORMAT_LENGTH - palette_size) * 10)?;
+  Ok(())
+}
+
 fn read_line_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> io::Result<Vec<LineStitchFormat>> {
   let mut formats = Vec::with_capacity(palette_size);
   for _ in 0..palette_size {
@@ -985,6 +1694,22 @@ fn read_line_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> io:
   Ok(formats)
 }
 
+fn write_line_formats<'a, W: Write>(
+  writer: &mut W,
+  formats: impl ExactSizeIterator<Item = &'a LineStitchFormat>,
+) -> io::Result<()> {
+  let palette_size = formats.len();
+  for format in formats {
+    writer.write_u16::<LittleEndian>(format.use_alt_color.into())?;
+    writer.write_hex_color(&format.color)?;
+    writer.write_padding(1)?;
+    writer.write_u16::<LittleEndian>(format.style)?;
+    writer.write_u16::<LittleEndian>((format.thickness * 10.0).round() as u16)?;
+  }
+  writer.write_padding((FORMAT_LENGTH - palette_size) * 10)?;
+  Ok(())
+}
+
 fn read_node_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> io::Result<Vec<NodeStitchFormat>> {
   let mut formats = Vec::with_capacity(palette_size);
   for _ in 0..palette_size {
@@ -1004,6 +1729,22 @@ fn read_node_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> io:
   Ok(formats)
 }
 
+fn write_node_formats<'a, W: Write>(
+  writer: &mut W,
+  formats: impl ExactSizeIterator<Item = &'a NodeStitchFormat>,
+) -> io::Result<()> {
+  let palette_size = formats.len();
+  for format in formats {
+    writer.write_u16::<LittleEndian>(format.use_dot_style.into())?;
+    writer.write_hex_color(&format.color)?;
+    writer.write_padding(1)?;
+    writer.write_u16::<LittleEndian>(format.use_alt_color.into())?;
+    writer.write_u16::<LittleEndian>((format.thickness * 10.0).round() as u16)?;
+  }
+  writer.write_padding((FORMAT_LENGTH - palette_size) * 10)?;
+  Ok(())
+}
+
 fn read_font_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> io::Result<Vec<FontFormat>> {
   let mut formats = Vec::with_capacity(palette_size);
   for _ in 0..palette_size {
@@ -1027,6 +1768,24 @@ fn read_font_formats<R: Read + Seek>(reader: &mut R, palette_size: usize) -> io:
   Ok(formats)
 }
 
+fn write_font_formats<'a, W: Write>(
+  writer: &mut W,
+  formats: impl ExactSizeIterator<Item = &'a FontFormat>,
+) -> io::Result<()> {
+  let palette_size = formats.len();
+  for format in formats {
+    writer.write_cstring(format.font_name.as_deref().unwrap_or("default"), FONT_NAME_LENGTH)?;
+    writer.write_padding(2)?;
+    writer.write_u16::<LittleEndian>(if format.bold { 700 } else { 400 })?;
+    writer.write_u8(format.italic.into())?;
+    writer.write_padding(11)?;
+    writer.write_u16::<LittleEndian>(format.stitch_size.into())?;
+    writer.write_u16::<LittleEndian>(format.small_stitch_size.into())?;
+  }
+  writer.write_padding((FORMAT_LENGTH - palette_size) * 53)?;
+  Ok(())
+}
+
 fn read_symbols<R: Read>(reader: &mut R, palette_size: usize) -> io::Result<Vec<Symbols>> {
   const fn map_symbol(value: u16) -> Option<u16> {
     if value == 0xFFFF { None } else { Some(value) }
@@ -1049,6 +1808,25 @@ fn read_symbols<R: Read>(reader: &mut R, palette_size: usize) -> io::Result<Vec<
   Ok(symbols)
 }
 
+fn write_symbols<W: Write>(writer: &mut W, symbols: &[Symbols]) -> io::Result<()> {
+  tracing::debug!("Writing symbols");
+
+  for symbols in symbols {
+    for symbol in [
+      symbols.full,
+      symbols.petite,
+      symbols.half,
+      symbols.quarter,
+      symbols.french_knot,
+      symbols.bead,
+    ] {
+      writer.write_u16::<LittleEndian>(symbol.unwrap_or(0xFFFF))?;
+    }
+  }
+
+  Ok(())
+}
+
 fn read_pattern_and_print_settings<R: Read + Seek>(reader: &mut R) -> io::Result<(PatternSettings, PrintSettings)> {
   tracing::debug!("Reading pattern and print settings");
 
@@ -1111,6 +1889,53 @@ fn read_pattern_and_print_settings<R: Read + Seek>(reader: &mut R) -> io::Result
   ))
 }
 
+fn write_pattern_and_print_settings<W: Write>(
+  writer: &mut W,
+  pattern_settings: &PatternSettings,
+  print_settings: &PrintSettings,
+) -> io::Result<()> {
+  tracing::debug!("Writing pattern and print settings");
+
+  writer.write_cstring(&pattern_settings.default_stitch_font, FONT_NAME_LENGTH)?;
+  writer.write_padding(20)?;
+
+  writer.write_cstring(&print_settings.font.name, FONT_NAME_LENGTH)?;
+  writer.write_u16::<LittleEndian>(print_settings.font.size)?;
+  writer.write_u16::<LittleEndian>(print_settings.font.weight)?;
+  writer.write_u16::<LittleEndian>(print_settings.font.italic.into())?;
+  writer.write_padding(10)?;
+
+  writer.write_u16::<LittleEndian>(pattern_settings.view)?;
+  writer.write_u16::<LittleEndian>(pattern_settings.zoom)?;
+
+  writer.write_u16::<LittleEndian>(pattern_settings.show_grid.into())?;
+  writer.write_u16::<LittleEndian>(pattern_settings.show_rulers.into())?;
+  writer.write_u16::<LittleEndian>(pattern_settings.show_centering_marks.into())?;
+  writer.write_u16::<LittleEndian>(pattern_settings.show_fabric_colors_with_symbols.into())?;
+  writer.write_padding(4)?;
+  writer.write_u16::<LittleEndian>(pattern_settings.gaps_between_stitches.into())?;
+
+  writer.write_cstring(&print_settings.header, PAGE_HEADER_AND_FOOTER_LENGTH)?;
+  writer.write_cstring(&print_settings.footer, PAGE_HEADER_AND_FOOTER_LENGTH)?;
+  let margins = &print_settings.margins;
+  for margin in [
+    margins.left,
+    margins.right,
+    margins.top,
+    margins.bottom,
+    margins.header,
+    margins.footer,
+  ] {
+    writer.write_u16::<LittleEndian>((margin * 100.0).round() as u16)?;
+  }
+  writer.write_u16::<LittleEndian>(print_settings.show_page_numbers.into())?;
+  writer.write_u16::<LittleEndian>(print_settings.show_adjacent_page_numbers.into())?;
+  writer.write_u16::<LittleEndian>(print_settings.center_chart_on_pages.into())?;
+  writer.write_padding(2)?;
+
+  Ok(())
+}
+
 fn read_grid<R: Read + Seek>(reader: &mut R) -> io::Result<Grid> {
   fn read_grid_line_style<R: Read + Seek>(reader: &mut R) -> io::Result<GridLineStyle> {
     let thickness = reader.read_u16::<LittleEndian>()? as f32;
@@ -1139,6 +1964,28 @@ fn read_grid<R: Read + Seek>(reader: &mut R) -> io::Result<Grid> {
   })
 }
 
+fn write_grid<W: Write>(writer: &mut W, grid: &Grid) -> io::Result<()> {
+  fn write_grid_line_style<W: Write>(writer: &mut W, line: &GridLineStyle) -> io::Result<()> {
+    writer.write_u16::<LittleEndian>(line.thickness.round() as u16)?;
+    writer.write_padding(2)?;
+    writer.write_hex_color(&line.color)?;
+    writer.write_padding(3)?;
+    Ok(())
+  }
+
+  tracing::debug!("Writing grid");
+
+  writer.write_u16::<LittleEndian>(grid.major_lines_interval)?;
+  writer.write_padding(2)?;
+  write_grid_line_style(writer, &grid.minor_screen_lines)?;
+  write_grid_line_style(writer, &grid.major_screen_lines)?;
+  write_grid_line_style(writer, &grid.minor_printer_lines)?;
+  write_grid_line_style(writer, &grid.major_printer_lines)?;
+  writer.write_padding(12)?;
+
+  Ok(())
+}
+
 fn read_pattern_info<R: Read + Seek>(reader: &mut R) -> io::Result<PatternInfo> {
   tracing::debug!("Reading pattern info");
   Ok(PatternInfo {
@@ -1150,6 +1997,16 @@ fn read_pattern_info<R: Read + Seek>(reader: &mut R) -> io::Result<PatternInfo>
   })
 }
 
+fn write_pattern_info<W: Write>(writer: &mut W, info: &PatternInfo) -> io::Result<()> {
+  tracing::debug!("Writing pattern info");
+  writer.write_cstring(&info.title, PATTERN_NAME_LENGTH)?;
+  writer.write_cstring(&info.author, AUTHOR_NAME_LENGTH)?;
+  writer.write_cstring(&info.company, COMPANY_NAME_LENGTH)?;
+  writer.write_cstring(&info.copyright, COPYRIGHT_LENGTH)?;
+  writer.write_cstring(&info.description, PATTERN_NOTES_LENGTH)?;
+  Ok(())
+}
+
 fn read_stitch_settings<R: Read + Seek>(reader: &mut R) -> io::Result<StitchSettings> {
   tracing::debug!("Reading stitch settings");
 
@@ -1194,6 +2051,40 @@ fn read_stitch_settings<R: Read + Seek>(reader: &mut R) -> io::Result<StitchSett
   })
 }
 
+fn write_stitch_settings<W: Write>(writer: &mut W, stitch_settings: &StitchSettings) -> io::Result<()> {
+  tracing::debug!("Writing stitch settings");
+
+  let strands = &stitch_settings.default_strands;
+  for value in [
+    strands.full,
+    strands.half,
+    strands.quarter,
+    strands.back,
+    strands.petite,
+    strands.special,
+    strands.straight,
+  ] {
+    writer.write_u16::<LittleEndian>(value.into())?;
+  }
+  for thickness in stitch_settings.display_thickness {
+    writer.write_u16::<LittleEndian>((thickness * 10.0).round() as u16)?;
+  }
+
+  let outline = &stitch_settings.stitch_outline;
+  writer.write_u16::<LittleEndian>(stitch_settings.outlined_stitches.into())?;
+  writer.write_u16::<LittleEndian>(outline.color.is_some().into())?;
+  writer.write_u16::<LittleEndian>(outline.color_percentage.into())?;
+  if let Some(color) = &outline.color {
+    writer.write_hex_color(color)?;
+    writer.write_padding(1)?;
+  } else {
+    writer.write_padding(4)?;
+  }
+  writer.write_u16::<LittleEndian>((outline.thickness * 10.0).round() as u16)?;
+
+  Ok(())
+}
+
 fn read_symbol_settings<R: Read + Seek>(reader: &mut R) -> io::Result<SymbolSettings> {
   tracing::debug!("Reading symbol settings");
   Ok(SymbolSettings {
@@ -1218,6 +2109,25 @@ fn read_symbol_settings<R: Read + Seek>(reader: &mut R) -> io::Result<SymbolSett
   })
 }
 
+fn write_symbol_settings<W: Write>(writer: &mut W, symbol_settings: &SymbolSettings) -> io::Result<()> {
+  tracing::debug!("Writing symbol settings");
+  writer.write_u16::<LittleEndian>(symbol_settings.screen_spacing.0)?;
+  writer.write_u16::<LittleEndian>(symbol_settings.screen_spacing.1)?;
+  writer.write_u16::<LittleEndian>(symbol_settings.printer_spacing.0)?;
+  writer.write_u16::<LittleEndian>(symbol_settings.printer_spacing.1)?;
+  writer.write_u16::<LittleEndian>(symbol_settings.scale_using_maximum_font_width.into())?;
+  writer.write_u16::<LittleEndian>(symbol_settings.scale_using_font_height.into())?;
+  writer.write_u16::<LittleEndian>(symbol_settings.small_stitch_size.into())?;
+  writer.write_u16::<LittleEndian>(symbol_settings.show_stitch_color.into())?;
+  writer.write_u16::<LittleEndian>(symbol_settings.use_large_half_stitch_symbol.into())?;
+  writer.write_padding(6)?;
+  writer.write_u16::<LittleEndian>(symbol_settings.stitch_size.into())?;
+  writer.write_u16::<LittleEndian>(symbol_settings.use_triangles_behind_quarter_stitches.into())?;
+  writer.write_u16::<LittleEndian>(symbol_settings.draw_symbols_over_backstitches.into())?;
+  writer.write_padding(2)?;
+  Ok(())
+}
+
 fn read_stitches<R: Read>(
   reader: &mut R,
   coord_factor: usize,
@@ -1287,6 +2197,41 @@ fn read_stitches_data<R: Read>(reader: &mut R, total_stitches_count: usize) -> i
   Ok(stitches_data)
 }
 
+/// Writes the bytes buffer that contains the encoded stitches data.
+fn write_stitches_data<W: Write>(writer: &mut W, stitches_data: &[i32]) -> io::Result<()> {
+  tracing::debug!("Writing stitches");
+
+  let mut xsd_random_numbers = XSD_RANDOM_NUMBERS;
+  for number in xsd_random_numbers {
+    writer.write_i32::<LittleEndian>(number)?;
+  }
+  let (mut encoding_key, encoding_numbers) = reproduce_decoding_values(&xsd_random_numbers)?;
+  let mut encoding_number_index = 0;
+
+  for chunk in stitches_data.chunks(STITCHES_DATA_CHUNK_LENGTH) {
+    // Compressing.
+    let mut compressed_stitches_data = Vec::new();
+    for same_stitches_data in chunk.chunk_by(|a, b| a == b) {
+      if same_stitches_data.len() > 1 {
+        compressed_stitches_data.push(COPY_STITCH_DATA_FLAG | ((same_stitches_data.len() as i32) << 16));
+      }
+      compressed_stitches_data.push(same_stitches_data[0]);
+    }
+
+    writer.write_u32::<LittleEndian>(compressed_stitches_data.len() as u32)?;
+
+    // Encoding.
+    for stitch_data in compressed_stitches_data {
+      writer.write_i32::<LittleEndian>(stitch_data ^ encoding_key ^ xsd_random_numbers[0])?;
+      encoding_key = encoding_key.rotate_left(encoding_numbers[encoding_number_index]);
+      xsd_random_numbers[0] = xsd_random_numbers[0].wrapping_add(xsd_random_numbers[1]);
+      encoding_number_index = (encoding_number_index + 1) % 16;
+    }
+  }
+
+  Ok(())
+}
+
 /// Reproduces the decoding values that are used for decoding the stitches data.
 fn reproduce_decoding_values(xsd_random_numbers: &[i32; 4]) -> io::Result<(i32, [u32; 16])> {
   let val1 = xsd_random_numbers[1].to_le_bytes()[1] as i32;
@@ -1330,6 +2275,14 @@ fn read_small_stitch_buffers<R: Read>(reader: &mut R, small_stitches_count: usiz
   Ok(small_stitch_buffers)
 }
 
+/// Writes the small stitch buffers that contain the small stitches data.
+fn write_small_stitch_buffers<W: Write>(writer: &mut W, small_stitch_buffers: &[[u8; 10]]) -> io::Result<()> {
+  for buf in small_stitch_buffers {
+    writer.write_all(buf)?;
+  }
+  Ok(())
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum XsdSmallStitchKind {
   HalfTop,
@@ -1344,6 +2297,24 @@ enum XsdSmallStitchKind {
   PetiteBottomRight,
 }
 
+impl XsdSmallStitchKind {
+  /// Returns the index of the byte with the stitch flag, the flag itself and the index of the palindex byte in the small stitch buffer.
+  const fn buffer_layout(self) -> (usize, u8, usize) {
+    match self {
+      Self::HalfTop => (0, 1, 2),
+      Self::HalfBottom => (0, 2, 3),
+      Self::QuarterTopLeft => (0, 4, 4),
+      Self::QuarterBottomLeft => (0, 8, 5),
+      Self::QuarterTopRight => (0, 16, 6),
+      Self::QuarterBottomRight => (0, 32, 7),
+      Self::PetiteTopLeft => (1, 1, 4),
+      Self::PetiteBottomLeft => (1, 2, 5),
+      Self::PetiteTopRight => (1, 4, 6),
+      Self::PetiteBottomRight => (1, 8, 7),
+    }
+  }
+}
+
 /// Maps the stitches data into the full- and partstitches .
 fn map_stitches_data_into_stitches(
   stitches_data: Vec<i32>,
@@ -1428,6 +2399,87 @@ fn map_stitches_data_into_stitches(
   (fullstitches, partstitches)
 }
 
+/// Maps the full- and partstitches into the stitches data and small stitch buffers.
+fn map_stitches_into_stitches_data(
+  fullstitches: &[FullStitch],
+  partstitches: &[PartStitch],
+  coord_factor: usize,
+  total_stitches_count: usize,
+) -> Result<(Vec<i32>, Vec<[u8; 10]>)> {
+  let mut stitches_data = vec![EMPTY_STITCH_DATA; total_stitches_count];
+  let mut small_stitch_buffers: Vec<[u8; 10]> = Vec::new();
+
+  let cell_index = |x: f32, y: f32| {
+    let index = (y as usize) * coord_factor + (x as usize);
+    (x >= 0.0 && y >= 0.0 && (x as usize) < coord_factor && index < total_stitches_count).then_some(index)
+  };
+
+  for fs in fullstitches.iter().filter(|fs| fs.kind == FullStitchKind::Full) {
+    if let Some(index) = cell_index(fs.x, fs.y) {
+      stitches_data[index] = (fs.palindex as i32) << 16;
+    }
+  }
+
+  let small_stitches = fullstitches
+    .iter()
+    .filter(|fs| fs.kind == FullStitchKind::Petite)
+    .map(|fs| {
+      let kind = match (fs.x.fract() >= 0.5, fs.y.fract() >= 0.5) {
+        (false, false) => XsdSmallStitchKind::PetiteTopLeft,
+        (false, true) => XsdSmallStitchKind::PetiteBottomLeft,
+        (true, false) => XsdSmallStitchKind::PetiteTopRight,
+        (true, true) => XsdSmallStitchKind::PetiteBottomRight,
+      };
+      (fs.x, fs.y, fs.palindex, kind)
+    })
+    .chain(partstitches.iter().map(|ps| {
+      let kind = match ps.kind {
+        PartStitchKind::Half => match ps.direction {
+          PartStitchDirection::Backward => XsdSmallStitchKind::HalfTop,
+          PartStitchDirection::Forward => XsdSmallStitchKind::HalfBottom,
+        },
+        PartStitchKind::Quarter => match (ps.x.fract() >= 0.5, ps.y.fract() >= 0.5) {
+          (false, false) => XsdSmallStitchKind::QuarterTopLeft,
+          (false, true) => XsdSmallStitchKind::QuarterBottomLeft,
+          (true, false) => XsdSmallStitchKind::QuarterTopRight,
+          (true, true) => XsdSmallStitchKind::QuarterBottomRight,
+        },
+      };
+      (ps.x, ps.y, ps.palindex, kind)
+    }));
+
+  for (x, y, palindex, kind) in small_stitches {
+    let Some(index) = cell_index(x, y) else {
+      continue;
+    };
+
+    let stitch_data = stitches_data[index];
+    let position = match stitch_data.to_le_bytes()[3] {
+      // Empty cell.
+      15 => {
+        let position = small_stitch_buffers.len();
+        if position == MAX_SMALL_STITCH_BUFFERS {
+          anyhow::bail!("Pattern Maker supports up to {MAX_SMALL_STITCH_BUFFERS} cells with small stitches");
+        }
+        // The meaning of this flag is unknown, but Pattern Maker always sets it.
+        small_stitch_buffers.push([0x40, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        stitches_data[index] = i32::MIN | ((position as i32) << 16);
+        position
+      }
+      // The cell is already occupied by a full stitch.
+      0 => continue,
+      _ => ((stitch_data >> 16) & ((u16::MAX / 2) as i32)) as usize,
+    };
+
+    let (significant_byte_index, flag, palindex_index) = kind.buffer_layout();
+    let small_stitch_buffer = &mut small_stitch_buffers[position];
+    small_stitch_buffer[significant_byte_index] |= flag;
+    small_stitch_buffer[palindex_index] = palindex;
+  }
+
+  Ok((stitches_data, small_stitch_buffers))
+}
+
 /// Adjusts the coordinates of the small stitch.
 /// The XSD format contains coordinates without additional offsets relative to the cell.
 /// But this is important for us.
@@ -1513,6 +2565,70 @@ fn read_special_stitch_models<R: Read + Seek>(reader: &mut R) -> io::Result<Vec<
   Ok(special_stitch_models)
 }
 
+fn write_special_stitch_models<W: Write>(writer: &mut W, special_stitch_models: &[SpecialStitchModel]) -> Result<()> {
+  tracing::debug!("Writing special stitch models");
+
+  writer.write_padding(2)?;
+  writer.write_u16::<LittleEndian>(u16::try_from(special_stitch_models.len())?)?;
+
+  for special_stitch_model in special_stitch_models {
+    writer.write_u16::<LittleEndian>(4)?;
+    writer.write_padding(2)?;
+    writer.write_all(b"sps1")?;
+
+    writer.write_cstring(&special_stitch_model.unique_name, SPECIAL_STITCH_NAME_LENGTH)?;
+    writer.write_cstring(&special_stitch_model.name, SPECIAL_STITCH_NAME_LENGTH)?;
+    writer.write_padding(2)?;
+
+    // The curvedstitches coordinates are shifted so they are not negative.
+    let shift = special_stitch_model
+      .curvedstitches
+      .iter()
+      .flat_map(|curve| curve.points.iter())
+      .fold((0.0_f32, 0.0_f32), |shift, (x, y)| {
+        (
+          shift.0.max((-x * 2.0).ceil() / 2.0),
+          shift.1.max((-y * 2.0).ceil() / 2.0),
+        )
+      });
+    let curvedstitches = special_stitch_model
+      .curvedstitches
+      .iter()
+      .map(|curve| CurvedStitch {
+        points: curve.points.iter().map(|(x, y)| (x + shift.0, y + shift.1)).collect(),
+      })
+      .collect::<Vec<_>>();
+
+    // All the stitches are written into the first block, and the rest ones are left empty.
+    writer.write_padding(2)?;
+    writer.write_u16::<LittleEndian>((shift.0 * 2.0) as u16)?;
+    writer.write_u16::<LittleEndian>((shift.1 * 2.0) as u16)?;
+    writer.write_u16::<LittleEndian>((special_stitch_model.width * 2.0).round() as u16)?;
+    writer.write_u16::<LittleEndian>((special_stitch_model.height * 2.0).round() as u16)?;
+    write_signature(writer)?;
+
+    let joints_count = special_stitch_model.linestitches.len()
+      + special_stitch_model.nodestitches.len()
+      + special_stitch_model.curvedstitches.len();
+    writer.write_u16::<LittleEndian>(u16::try_from(joints_count)?)?;
+    write_joints(
+      writer,
+      &special_stitch_model.linestitches,
+      &special_stitch_model.nodestitches,
+      &[],
+      &curvedstitches,
+    )?;
+
+    for _ in 1..3 {
+      writer.write_padding(10)?;
+      write_signature(writer)?;
+      writer.write_u16::<LittleEndian>(0)?;
+    }
+  }
+
+  Ok(())
+}
+
 #[derive(Debug, PartialEq)]
 enum XsdJointKind {
   FrenchKnot,
@@ -1537,6 +2653,19 @@ impl From<u16> for XsdJointKind {
   }
 }
 
+impl From<XsdJointKind> for u16 {
+  fn from(kind: XsdJointKind) -> Self {
+    match kind {
+      XsdJointKind::FrenchKnot => 1,
+      XsdJointKind::Back => 2,
+      XsdJointKind::Curve => 3,
+      XsdJointKind::Special => 4,
+      XsdJointKind::Straight => 5,
+      XsdJointKind::Bead => 6,
+    }
+  }
+}
+
 type Joints = (Vec<LineStitch>, Vec<NodeStitch>, Vec<SpecialStitch>, Vec<CurvedStitch>);
 
 /// Reads the french knots, beads, back, straight and special stitches and curved stitches used in the pattern.
@@ -1673,3 +2802,98 @@ fn read_joints<R: Read + Seek>(reader: &mut R, joints_count: u16) -> io::Result<
 
   Ok((linestitches, nodestitches, specialstitches, curvedstitches))
 }
+
+/// Writes the french knots, beads, back, straight and special stitches and curved stitches.
+fn write_joints<W: Write>(
+  writer: &mut W,
+  linestitches: &[LineStitch],
+  nodestitches: &[NodeStitch],
+  specialstitches: &[SpecialStitch],
+  curvedstitches: &[CurvedStitch],
+) -> io::Result<()> {
+  tracing::debug!("Writing joints");
+
+  for linestitch in linestitches {
+    let joint_kind = match linestitch.kind {
+      LineStitchKind::Back => XsdJointKind::Back,
+      LineStitchKind::Straight => XsdJointKind::Straight,
+    };
+    writer.write_u16::<LittleEndian>(joint_kind.into())?;
+    writer.write_padding(2)?;
+    writer.write_u16::<LittleEndian>((linestitch.x.0 * 2.0).round() as u16)?;
+    writer.write_u16::<LittleEndian>((linestitch.y.0 * 2.0).round() as u16)?;
+    writer.write_u16::<LittleEndian>((linestitch.x.1 * 2.0).round() as u16)?;
+    writer.write_u16::<LittleEndian>((linestitch.y.1 * 2.0).round() as u16)?;
+    writer.write_u8(linestitch.palindex)?;
+    writer.write_padding(1)?;
+  }
+
+  for nodestitch in nodestitches {
+    match nodestitch.kind {
+      NodeStitchKind::FrenchKnot => {
+        writer.write_u16::<LittleEndian>(XsdJointKind::FrenchKnot.into())?;
+        writer.write_padding(2)?;
+        writer.write_u16::<LittleEndian>((nodestitch.x * 2.0).round() as u16)?;
+        writer.write_u16::<LittleEndian>((nodestitch.y * 2.0).round() as u16)?;
+        writer.write_padding(4)?;
+        writer.write_u8(nodestitch.palindex)?;
+        writer.write_padding(1)?;
+      }
+      NodeStitchKind::Bead => {
+        writer.write_u16::<LittleEndian>(XsdJointKind::Bead.into())?;
+        writer.write_padding(2)?;
+        writer.write_u16::<LittleEndian>((nodestitch.x * 2.0).round() as u16)?;
+        writer.write_u16::<LittleEndian>((nodestitch.y * 2.0).round() as u16)?;
+        writer.write_u8(nodestitch.palindex)?;
+        writer.write_padding(1)?;
+        writer.write_u16::<LittleEndian>(if nodestitch.rotated { 90 } else { 0 })?;
+      }
+    }
+  }
+
+  for specialstitch in specialstitches {
+    writer.write_u16::<LittleEndian>(XsdJointKind::Special.into())?;
+    writer.write_padding(2)?;
+    writer.write_u8(specialstitch.palindex)?;
+    writer.write_padding(4)?;
+    writer.write_u16::<LittleEndian>((specialstitch.x * 2.0).round() as u16)?;
+    writer.write_u16::<LittleEndian>((specialstitch.y * 2.0).round() as u16)?;
+
+    // Rotating by 180 degrees is the same as flipping along both axes.
+    let (rotation, flip) = if specialstitch.rotation >= 180 {
+      (
+        specialstitch.rotation - 180,
+        (!specialstitch.flip.0, !specialstitch.flip.1),
+      )
+    } else {
+      (specialstitch.rotation, specialstitch.flip)
+    };
+    let params: [u16; 4] = match (rotation, flip) {
+      (90, (false, false)) => [0, 0xFFFF, 1, 0],
+      (90, (false, true)) => [0, 1, 1, 0],
+      (90, (true, false)) => [0, 0xFFFF, 0xFFFF, 0],
+      (90, (true, true)) => [0, 1, 0xFFFF, 0],
+      (_, (true, false)) => [0xFFFF, 0, 0, 1],
+      (_, (false, true)) => [1, 0, 0, 0xFFFF],
+      (_, (true, true)) => [0xFFFF, 0, 0, 0xFFFF],
+      (_, (false, false)) => [1, 0, 0, 1],
+    };
+    for param in params {
+      writer.write_u16::<LittleEndian>(param)?;
+    }
+    writer.write_padding(2)?;
+    writer.write_u16::<LittleEndian>(specialstitch.modindex.into())?;
+  }
+
+  for curvedstitch in curvedstitches {
+    writer.write_u16::<LittleEndian>(XsdJointKind::Curve.into())?;
+    writer.write_padding(3)?;
+    writer.write_u16::<LittleEndian>(curvedstitch.points.len() as u16)?;
+    for (x, y) in &curvedstitch.points {
+      writer.write_u16::<LittleEndian>((x * 15.0 * 2.0).round() as u16)?;
+      writer.write_u16::<LittleEndian>((y * 15.0 * 2.0).round() as u16)?;
+    }
+  }
+
+  Ok(())
+}
@@ -702,3 +702,210 @@ fn reads_joints() {
     assert_eq!(loaded, expected);
   }
 }
+
+#[test]
+fn writes_stitches() {
+  let (fullstitches, partstitches) = read_stitches(&mut load_fixture("stitches"), 10, 10 * 10, 8).unwrap();
+
+  let (stitches_data, small_stitch_buffers) =
+    map_stitches_into_stitches_data(&fullstitches, &partstitches, 10, 10 * 10).unwrap();
+  assert_eq!(small_stitch_buffers.len(), 8);
+
+  let mut buf = Vec::new();
+  write_stitches_data(&mut buf, &stitches_data).unwrap();
+  write_small_stitch_buffers(&mut buf, &small_stitch_buffers).unwrap();
+
+  let (written_fullstitches, written_partstitches) = read_stitches(&mut Cursor::new(buf), 10, 10 * 10, 8).unwrap();
+  assert_eq!(written_fullstitches, fullstitches);
+  assert_eq!(written_partstitches, partstitches);
+}
+
+#[test]
+fn writes_stitches_data_in_chunks() {
+  let stitches_data = (0..10_000).map(|i| (i % 3) << 16).collect::<Vec<_>>();
+
+  let mut buf = Vec::new();
+  write_stitches_data(&mut buf, &stitches_data).unwrap();
+
+  assert_eq!(
+    read_stitches_data(&mut Cursor::new(buf), stitches_data.len()).unwrap(),
+    stitches_data
+  );
+}
+
+/// Rounds the curved stitches points to the resolution of the XSD format.
+/// The saved points may differ in the last digit, since they are shifted back and forth when being read.
+fn round_curved_stitches(special_stitch_models: &mut [SpecialStitchModel]) {
+  for curve in special_stitch_models
+    .iter_mut()
+    .flat_map(|model| model.curvedstitches.iter_mut())
+  {
+    for (x, y) in curve.points.iter_mut() {
+      *x = (*x * 30.0).round() / 30.0;
+      *y = (*y * 30.0).round() / 30.0;
+    }
+  }
+}
+
+#[test]
+fn writes_special_stitch_models() {
+  let mut special_stitch_models = read_special_stitch_models(&mut load_fixture("special_stitch_models")).unwrap();
+
+  let mut buf = Vec::new();
+  write_special_stitch_models(&mut buf, &special_stitch_models).unwrap();
+
+  let mut written_special_stitch_models = read_special_stitch_models(&mut Cursor::new(buf)).unwrap();
+  round_curved_stitches(&mut special_stitch_models);
+  round_curved_stitches(&mut written_special_stitch_models);
+  assert_eq!(written_special_stitch_models, special_stitch_models);
+}
+
+#[test]
+fn writes_joints() {
+  let (linestitches, nodestitches, specialstitches, _) = read_joints(&mut load_fixture("joints"), 16).unwrap();
+
+  let mut buf = Vec::new();
+  write_joints(&mut buf, &linestitches, &nodestitches, &specialstitches, &[]).unwrap();
+
+  let (written_linestitches, written_nodestitches, written_specialstitches, _) =
+    read_joints(&mut Cursor::new(buf), 16).unwrap();
+  assert_eq!(written_linestitches, linestitches);
+  assert_eq!(written_nodestitches, nodestitches);
+  assert_eq!(written_specialstitches, specialstitches);
+}
+
+#[test]
+fn writes_rotated_and_flipped_special_stitches() {
+  let specialstitches =
+    [(180, (false, false)), (180, (true, false)), (270, (true, false))].map(|(rotation, flip)| SpecialStitch {
+      x: 0.0,
+      y: 0.0,
+      rotation,
+      flip,
+      palindex: 0,
+      modindex: 0,
+    });
+
+  let mut buf = Vec::new();
+  write_joints(&mut buf, &[], &[], &specialstitches, &[]).unwrap();
+
+  let (_, _, written_specialstitches, _) = read_joints(&mut Cursor::new(buf), 3).unwrap();
+  let written = written_specialstitches
+    .iter()
+    .map(|specialstitch| (specialstitch.rotation, specialstitch.flip))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    written,
+    vec![(0, (true, true)), (0, (false, true)), (90, (false, true))]
+  );
+}
+
+#[test]
+fn writes_and_reads_back_patterns() {
+  for name in ["piggies.xsd", "specials.xsd"] {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("../../testdata/patterns/pmaker")
+      .join(name);
+    let mut pattern = parse_pattern_inner(&std::fs::read(path).unwrap()).unwrap();
+
+    let saved = save_pattern_inner(&pattern).unwrap();
+    let mut saved_pattern = parse_pattern_inner(&saved).unwrap();
+    round_curved_stitches(&mut pattern.special_stitch_models);
+    round_curved_stitches(&mut saved_pattern.special_stitch_models);
+    assert_eq!(saved_pattern, pattern, "Failed to round-trip {name}");
+  }
+}
+
+#[test]
+fn saves_and_parses_back_projects() {
+  for name in ["piggies.xsd", "specials.xsd"] {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("../../testdata/patterns/pmaker")
+      .join(name);
+    let embproj = parse_pattern(&std::fs::read(path).unwrap()).unwrap();
+    let saved_embproj = parse_pattern(&save_pattern(&embproj).unwrap()).unwrap();
+
+    let (pattern, saved_pattern) = (&embproj.pattern, &saved_embproj.pattern);
+    assert_eq!(saved_pattern.info, pattern.info);
+    assert_eq!(saved_pattern.fabric, pattern.fabric);
+
+    assert_eq!(saved_pattern.palette.len(), pattern.palette.len());
+    for (saved_palitem, palitem) in saved_pattern.palette.iter().zip(pattern.palette.iter()) {
+      assert_eq!(saved_palitem, palitem);
+      assert_eq!(saved_palitem.name, palitem.name);
+      assert_eq!(saved_palitem.color, palitem.color);
+      assert_eq!(saved_palitem.blends, palitem.blends);
+      assert_eq!(saved_palitem.symbol, palitem.symbol);
+    }
+
+    let (layer, saved_layer) = (pattern.flatten_visible_layers(), saved_pattern.flatten_visible_layers());
+    assert!(saved_layer.fullstitches.iter().eq(layer.fullstitches.iter()));
+    assert!(saved_layer.partstitches.iter().eq(layer.partstitches.iter()));
+    assert!(saved_layer.linestitches.iter().eq(layer.linestitches.iter()));
+    assert!(saved_layer.nodestitches.iter().eq(layer.nodestitches.iter()));
+    assert!(saved_layer.specialstitches.iter().eq(layer.specialstitches.iter()));
+
+    assert_eq!(
+      saved_pattern.special_stitch_models.len(),
+      pattern.special_stitch_models.len()
+    );
+
+    assert_eq!(saved_embproj.display_settings.grid, embproj.display_settings.grid);
+    assert_eq!(
+      saved_embproj.display_settings.display_mode,
+      embproj.display_settings.display_mode
+    );
+  }
+}
+
+#[test]
+fn fails_to_save_too_large_palette() {
+  let mut pattern = embroiderly_pattern::Pattern::default();
+  for number in 0..=FORMAT_LENGTH {
+    pattern.palette.push(embroiderly_pattern::PaletteItem {
+      brand: String::from("DMC"),
+      number: number.to_string(),
+      name: String::new(),
+      color: String::from("000000"),
+      blends: None,
+      symbol: None,
+    });
+  }
+  assert!(save_pattern(&embroiderly_pattern::EmbroiderlyProject::new(pattern)).is_err());
+}
+
+#[test]
+fn fails_to_save_too_many_special_stitch_models() {
+  let mut pattern = embroiderly_pattern::Pattern::default();
+  for index in 0..=MAX_SPECIAL_STITCH_MODELS {
+    pattern
+      .special_stitch_models
+      .push(embroiderly_pattern::SpecialStitchModel {
+        unique_name: index.to_string(),
+        name: index.to_string(),
+        width: 1.0,
+        height: 1.0,
+        nodestitches: Vec::new(),
+        linestitches: Vec::new(),
+        curvedstitches: Vec::new(),
+      });
+  }
+  assert!(save_pattern(&embroiderly_pattern::EmbroiderlyProject::new(pattern)).is_err());
+}
+
+#[test]
+fn fails_to_save_symbols_outside_bmp() {
+  let mut pattern = embroiderly_pattern::Pattern::default();
+  pattern.palette.push(embroiderly_pattern::PaletteItem {
+    brand: String::from("DMC"),
+    number: String::from("310"),
+    name: String::new(),
+    color: String::from("000000"),
+    blends: None,
+    symbol: Some(embroiderly_pattern::Symbol {
+      char: '\u{1F600}',
+      font: String::from(DEFAULT_STITCH_FONT),
+    }),
+  });
+  assert!(save_pattern(&embroiderly_pattern::EmbroiderlyProject::new(pattern)).is_err());
+}
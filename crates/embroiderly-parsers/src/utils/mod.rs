@@ -1,2 +1,3 @@
 pub mod read;
+pub mod write;
 pub mod xml;
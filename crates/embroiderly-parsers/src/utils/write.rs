@@ -0,0 +1,44 @@
+use std::io;
+
+#[cfg(test)]
+#[path = "write.test.rs"]
+mod tests;
+
+/// Provides additional methods for writing data in cross-stitch patterns.
+pub trait WriteXspExt: io::Write + byteorder::WriteBytesExt {
+  /// Writes a C-style string with a specified length.
+  /// The string is written in CP1251 encoding if possible, otherwise in UTF-8.
+  /// It is truncated to fit the length and padded with null bytes.
+  fn write_cstring(&mut self, string: &str, length: usize) -> io::Result<()> {
+    let (encoded, _, had_errors) = encoding_rs::WINDOWS_1251.encode(string);
+    let bytes = if had_errors {
+      // Truncate the string at the character boundary so it can be decoded back.
+      let mut end = string.len().min(length);
+      while !string.is_char_boundary(end) {
+        end -= 1;
+      }
+      &string.as_bytes()[..end]
+    } else {
+      &encoded[..encoded.len().min(length)]
+    };
+
+    let mut buf = vec![0; length + 1]; // +1 for the null terminator.
+    buf[..bytes.len()].copy_from_slice(bytes);
+    self.write_all(&buf)
+  }
+
+  /// Writes a hex color given as `String`.
+  fn write_hex_color(&mut self, color: &str) -> io::Result<()> {
+    let mut buf: [u8; 3] = [0; 3];
+    hex::decode_to_slice(color, &mut buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    self.write_all(&buf)
+  }
+
+  /// Writes the specified number of null bytes.
+  fn write_padding(&mut self, length: usize) -> io::Result<()> {
+    self.write_all(&vec![0; length])
+  }
+}
+
+/// All types that implement `Write` get methods defined in `WriteXspExt`.
+impl<W: io::Write + ?Sized> WriteXspExt for W {}
@@ -0,0 +1,39 @@
+use super::WriteXspExt as _;
+
+#[test]
+fn writes_cstring() {
+  let mut buf = Vec::new();
+  buf.write_cstring("White", 7).unwrap();
+  assert_eq!(buf, vec![0x57, 0x68, 0x69, 0x74, 0x65, 0x00, 0x00, 0x00]);
+
+  let mut buf = Vec::new();
+  buf.write_cstring("голубой", 7).unwrap();
+  assert_eq!(buf, vec![0xE3, 0xEE, 0xEB, 0xF3, 0xE1, 0xEE, 0xE9, 0x00]);
+}
+
+#[test]
+fn truncates_long_cstring() {
+  let mut buf = Vec::new();
+  buf.write_cstring("Coffee", 5).unwrap();
+  assert_eq!(buf, vec![0x43, 0x6F, 0x66, 0x66, 0x65, 0x00]);
+
+  // The last character does not fit, so it must not be split.
+  let mut buf = Vec::new();
+  buf.write_cstring("a€€", 5).unwrap();
+  assert_eq!(buf, vec![0x61, 0x88, 0x88, 0x00, 0x00, 0x00]);
+
+  let mut buf = Vec::new();
+  buf.write_cstring("a☺☺", 5).unwrap();
+  assert_eq!(buf, vec![0x61, 0xE2, 0x98, 0xBA, 0x00, 0x00]);
+}
+
+#[test]
+fn writes_hex_color() {
+  let mut buf = Vec::new();
+  buf.write_hex_color("000000").unwrap();
+  assert_eq!(buf, vec![0x00, 0x00, 0x00]);
+
+  let mut buf = Vec::new();
+  buf.write_hex_color("FFFFFF").unwrap();
+  assert_eq!(buf, vec![0xFF, 0xFF, 0xFF]);
+}
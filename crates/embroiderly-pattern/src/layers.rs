@@ -13,6 +13,11 @@
 //!   Stitch layers have a strict, predefined display order (top to bottom as defined in code) that users cannot change.
 //!   Users can only toggle the visibility of stitch layers — they cannot create, remove, or reorder them.
 //!
+//! - **Layer Group**: A [`LayerGroup`] that bundles custom layers under a common name and visibility.
+//!   Groups can be nested into other groups, and a layer or group is only visible when all its ancestor groups are visible.
+//!   Groups don't affect the visual order of layers; they only reference each other and are referenced by layers
+//!   through stable indexes, the same way as layers do.
+//!
 //! # Ordering Terminology
 //!
 //! This module uses specific terminology to distinguish between storage and display order for custom layers:
@@ -48,6 +53,8 @@ pub struct Layers {
   items: Vec<Layer>,
   /// Visual ordering of layers.
   positions: Vec<u32>,
+  /// The layer groups.
+  groups: Vec<LayerGroup>,
 }
 
 impl Layers {
@@ -57,6 +64,7 @@ impl Layers {
     Self {
       items: vec![],
       positions: vec![],
+      groups: vec![],
     }
   }
 
@@ -66,6 +74,7 @@ impl Layers {
     Self {
       items: vec![layer],
       positions: vec![0],
+      groups: vec![],
     }
   }
 
@@ -189,6 +198,106 @@ impl Layers {
 
     self.positions.clone()
   }
+
+  // === Group Methods ===
+
+  /// Returns the layer groups in actual order.
+  #[must_use]
+  pub fn groups(&self) -> &[LayerGroup] {
+    &self.groups
+  }
+
+  /// Returns a reference to a layer group by its actual index.
+  #[must_use]
+  pub fn get_group(&self, index: u32) -> Option<&LayerGroup> {
+    self.groups.get(index as usize)
+  }
+
+  /// Returns a mutable reference to a layer group by its actual index.
+  pub fn get_group_mut(&mut self, index: u32) -> Option<&mut LayerGroup> {
+    self.groups.get_mut(index as usize)
+  }
+
+  /// Adds a new layer group, returning its actual index.
+  pub fn push_group(&mut self, group: LayerGroup) -> u32 {
+    let index = self.groups.len() as u32;
+    self.groups.push(group);
+    index
+  }
+
+  /// Inserts a layer group at a specific actual index.
+  /// Updates the group references of layers and groups accordingly.
+  pub fn insert_group(&mut self, index: u32, group: LayerGroup) {
+    self.shift_group_references(|group| if group >= index { group + 1 } else { group });
+    self.groups.insert(index as usize, group);
+  }
+
+  /// Removes a layer group by its actual index, returning the removed group.
+  /// Layers and groups of the removed group are moved to its parent group.
+  pub fn remove_group(&mut self, index: u32) -> LayerGroup {
+    let removed = self.groups.remove(index as usize);
+
+    for layer in self.items.iter_mut().filter(|layer| layer.group == Some(index)) {
+      layer.group = removed.parent;
+    }
+    for group in self.groups.iter_mut().filter(|group| group.parent == Some(index)) {
+      group.parent = removed.parent;
+    }
+    self.shift_group_references(|group| if group > index { group - 1 } else { group });
+
+    removed
+  }
+
+  /// Returns `true` if the layer and all its ancestor groups are visible.
+  #[must_use]
+  pub fn is_layer_visible(&self, index: u32) -> bool {
+    let layer = &self[index];
+    layer.visible && layer.group.is_none_or(|group| self.is_group_visible(group))
+  }
+
+  /// Returns `true` if the layer group and all its ancestor groups are visible.
+  #[must_use]
+  pub fn is_group_visible(&self, index: u32) -> bool {
+    let mut current = Some(index);
+    // Bound the walk by the number of groups to guard against cyclic references.
+    for _ in 0..=self.groups.len() {
+      let Some(group) = current.and_then(|index| self.get_group(index)) else {
+        return true;
+      };
+      if !group.visible {
+        return false;
+      }
+      current = group.parent;
+    }
+    true
+  }
+
+  /// Returns `true` if the layer group is the given ancestor group or is nested into it at any depth.
+  #[must_use]
+  pub fn is_group_within(&self, index: u32, ancestor: u32) -> bool {
+    let mut current = Some(index);
+    // Bound the walk by the number of groups to guard against cyclic references.
+    for _ in 0..=self.groups.len() {
+      let Some(index) = current else {
+        return false;
+      };
+      if index == ancestor {
+        return true;
+      }
+      current = self.get_group(index).and_then(|group| group.parent);
+    }
+    false
+  }
+
+  /// Applies the given mapping to every group reference of layers and groups.
+  fn shift_group_references(&mut self, shift: impl Fn(u32) -> u32) {
+    for layer in self.items.iter_mut() {
+      layer.group = layer.group.map(&shift);
+    }
+    for group in self.groups.iter_mut() {
+      group.parent = group.parent.map(&shift);
+    }
+  }
 }
 
 impl Default for Layers {
@@ -200,7 +309,11 @@ impl Default for Layers {
 impl From<Vec<Layer>> for Layers {
   fn from(items: Vec<Layer>) -> Self {
     let positions = (0..items.len() as u32).collect();
-    Self { items, positions }
+    Self {
+      items,
+      positions,
+      groups: vec![],
+    }
   }
 }
 
@@ -218,21 +331,88 @@ impl std::ops::IndexMut<u32> for Layers {
   }
 }
 
+/// Serialized form of [`Layers`].
+/// Layers without groups are stored as a plain list, the same way as before groups were introduced.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum LayersRepr<L> {
+  Plain(Vec<L>),
+  Grouped { items: Vec<L>, groups: Vec<LayerGroup> },
+}
+
 #[cfg(feature = "serde")]
 impl serde::Serialize for Layers {
   fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-    self.items.serialize(serializer)
+    let items = self.items.iter().collect();
+    if self.groups.is_empty() {
+      LayersRepr::Plain(items).serialize(serializer)
+    } else {
+      let groups = self.groups.clone();
+      LayersRepr::Grouped { items, groups }.serialize(serializer)
+    }
   }
 }
 
 #[cfg(feature = "serde")]
 impl<'de> serde::Deserialize<'de> for Layers {
   fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-    let items = Vec::<Layer>::deserialize(deserializer)?;
-    Ok(Self::from(items))
+    match LayersRepr::<Layer>::deserialize(deserializer)? {
+      LayersRepr::Plain(items) => Ok(Self::from(items)),
+      LayersRepr::Grouped { items, groups } => Ok(Self {
+        groups,
+        ..Self::from(items)
+      }),
+    }
   }
 }
 
+/// Represents a named group of custom layers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct LayerGroup {
+  pub name: String,
+  pub visible: bool,
+  /// The actual index of the group this group is nested into, if any.
+  pub parent: Option<u32>,
+}
+
+impl Default for LayerGroup {
+  fn default() -> Self {
+    Self {
+      name: String::new(),
+      visible: true,
+      parent: None,
+    }
+  }
+}
+
+impl LayerGroup {
+  /// Creates a new top-level layer group with the given name.
+  pub fn new(name: impl ToString) -> Self {
+    Self {
+      name: name.to_string(),
+      ..Self::default()
+    }
+  }
+}
+
+/// Specifies how a layer is composed with the layers below it when they are flattened.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LayerBlendMode {
+  /// The stitches of the layer replace the conflicting stitches of the lower layers.
+  #[default]
+  Overwrite,
+  /// The stitches of the layer are placed only where they don't conflict with the stitches of the lower layers.
+  FillEmpty,
+  /// The stitches of the layer aren't placed themselves, but erase the conflicting stitches of the lower layers.
+  Mask,
+}
+
 /// Represent a _custom_ layer.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
@@ -241,6 +421,18 @@ impl<'de> serde::Deserialize<'de> for Layers {
 pub struct Layer {
   pub name: String,
   pub visible: bool,
+  /// Whether the layer is protected from drawing.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub locked: bool,
+  /// The opacity of the layer, from `0.0` (transparent) to `1.0` (opaque).
+  #[cfg_attr(feature = "serde", serde(default = "default_opacity"))]
+  pub opacity: f32,
+  /// The actual index of the group the layer belongs to, if any.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub group: Option<u32>,
+  /// How the layer is composed with the layers below it when they are flattened.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub blend_mode: LayerBlendMode,
 
   pub fullstitches: Stitches<FullStitch>,
   pub fullstitches_visible: bool,
@@ -267,6 +459,10 @@ impl Default for Layer {
     Self {
       name: String::new(),
       visible: true,
+      locked: false,
+      opacity: 1.0,
+      group: None,
+      blend_mode: LayerBlendMode::default(),
 
       fullstitches: Stitches::new(),
       fullstitches_visible: true,
@@ -290,6 +486,11 @@ impl Default for Layer {
   }
 }
 
+#[cfg(feature = "serde")]
+const fn default_opacity() -> f32 {
+  1.0
+}
+
 impl Layer {
   /// Creates a new layer with the given name.
   pub fn new(name: impl ToString) -> Self {
@@ -487,6 +688,27 @@ impl Layer {
     conflicts
   }
 
+  /// Composes a stitch of a higher layer into this one according to the blend mode of that layer.
+  pub fn compose_stitch(&mut self, stitch: Stitch, blend_mode: LayerBlendMode) {
+    match blend_mode {
+      LayerBlendMode::Overwrite => {
+        self.add_stitch(stitch);
+      }
+      LayerBlendMode::FillEmpty => {
+        let conflicts = self.add_stitch(stitch);
+        if !conflicts.is_empty() {
+          // The place is already taken, so put back what was there.
+          self.remove_stitch(stitch);
+          self.add_stitches(conflicts);
+        }
+      }
+      LayerBlendMode::Mask => {
+        self.add_stitch(stitch);
+        self.remove_stitch(stitch);
+      }
+    }
+  }
+
   /// Removes many stitches from the layer.
   pub fn remove_stitches(&mut self, stitches: Vec<Stitch>) {
     for stitch in stitches {
@@ -505,6 +727,63 @@ impl Layer {
     }
   }
 
+  /// Returns all stitches of the layer.
+  pub fn get_stitches(&self) -> Vec<Stitch> {
+    let mut stitches = Vec::new();
+    stitches.extend(self.fullstitches.iter().copied().map(Stitch::Full));
+    stitches.extend(self.partstitches.iter().copied().map(Stitch::Part));
+    stitches.extend(self.linestitches.iter().copied().map(Stitch::Line));
+    stitches.extend(self.nodestitches.iter().copied().map(Stitch::Node));
+    stitches.extend(self.specialstitches.iter().copied().map(Stitch::Special));
+    stitches
+  }
+
+  /// Returns all stitches of the layer that use a given palette item.
+  pub fn get_stitches_by_palindex(&self, palindex: u32) -> Vec<Stitch> {
+    let mut stitches = Vec::new();
+    stitches.extend(
+      self
+        .fullstitches
+        .iter()
+        .filter(|stitch| stitch.palindex == palindex)
+        .copied()
+        .map(Stitch::Full),
+    );
+    stitches.extend(
+      self
+        .partstitches
+        .iter()
+        .filter(|stitch| stitch.palindex == palindex)
+        .copied()
+        .map(Stitch::Part),
+    );
+    stitches.extend(
+      self
+        .linestitches
+        .iter()
+        .filter(|stitch| stitch.palindex == palindex)
+        .copied()
+        .map(Stitch::Line),
+    );
+    stitches.extend(
+      self
+        .nodestitches
+        .iter()
+        .filter(|stitch| stitch.palindex == palindex)
+        .copied()
+        .map(Stitch::Node),
+    );
+    stitches.extend(
+      self
+        .specialstitches
+        .iter()
+        .filter(|stitch| stitch.palindex == palindex)
+        .copied()
+        .map(Stitch::Special),
+    );
+    stitches
+  }
+
   /// Removes and returns all stitches with a given palette index from the layer.
   pub fn remove_stitches_by_palindexes(&mut self, palindexes: &[u32]) -> Vec<Stitch> {
     let mut removed = Vec::new();
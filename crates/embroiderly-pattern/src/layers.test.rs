@@ -205,6 +205,88 @@ mod layers {
     }
   }
 
+  mod groups {
+    use super::*;
+
+    fn create_grouped_layers() -> Layers {
+      // Groups: Outer (0) > Inner (1), Other (2).
+      let mut layers = Layers::default();
+      let outer = layers.push_group(LayerGroup::new("Outer"));
+      let inner = layers.push_group(LayerGroup {
+        parent: Some(outer),
+        ..LayerGroup::new("Inner")
+      });
+      layers.push_group(LayerGroup::new("Other"));
+      layers.push(Layer {
+        group: Some(inner),
+        ..Layer::new("A")
+      });
+      layers.push(Layer {
+        group: Some(2),
+        ..Layer::new("B")
+      });
+      layers
+    }
+
+    #[test]
+    fn adds_groups_in_actual_order() {
+      let layers = create_grouped_layers();
+
+      assert_eq!(layers.groups().len(), 3);
+      assert_eq!(layers.get_group(1).unwrap().name, "Inner");
+      assert_eq!(layers.get_group(1).unwrap().parent, Some(0));
+      assert!(layers.get_group(3).is_none());
+    }
+
+    #[test]
+    fn cascades_group_visibility() {
+      let mut layers = create_grouped_layers();
+      assert!(layers.is_layer_visible(1));
+
+      layers.get_group_mut(0).unwrap().visible = false;
+      assert!(!layers.is_group_visible(1));
+      assert!(!layers.is_layer_visible(1));
+      assert!(layers.is_layer_visible(2));
+      assert!(layers.is_layer_visible(0));
+    }
+
+    #[test]
+    fn checks_group_nesting() {
+      let layers = create_grouped_layers();
+
+      assert!(layers.is_group_within(1, 1));
+      assert!(layers.is_group_within(1, 0));
+      assert!(!layers.is_group_within(0, 1));
+      assert!(!layers.is_group_within(1, 2));
+    }
+
+    #[test]
+    fn removes_group_moving_its_members_to_parent() {
+      let mut layers = create_grouped_layers();
+      let removed = layers.remove_group(1);
+
+      assert_eq!(removed.name, "Inner");
+      assert_eq!(layers.groups().len(), 2);
+      assert_eq!(layers[1].group, Some(0));
+      // References to the groups after the removed one are shifted.
+      assert_eq!(layers[2].group, Some(1));
+    }
+
+    #[test]
+    fn inserts_group_shifting_references() {
+      let mut layers = create_grouped_layers();
+      let removed = layers.remove_group(0);
+      assert_eq!(layers.get_group(0).unwrap().parent, None);
+
+      layers.insert_group(0, removed);
+      layers.get_group_mut(1).unwrap().parent = Some(0);
+
+      assert_eq!(layers.get_group(0).unwrap().name, "Outer");
+      assert_eq!(layers[1].group, Some(1));
+      assert_eq!(layers[2].group, Some(2));
+    }
+  }
+
   mod iteration {
     use super::*;
 
@@ -1,3 +1,5 @@
+use std::collections::{HashSet, VecDeque};
+
 use ordered_float::NotNan;
 
 use super::layers::*;
@@ -154,6 +156,60 @@ impl Pattern {
       .collect()
   }
 
+  /// Fills the connected region that contains the given point with full stitches of the given palette item.
+  ///
+  /// The region consists of the 4-connected cells that hold a full stitch of the same palette item as the starting cell, or that are completely empty if the starting cell is empty.
+  /// Cells which are only partially stitched (petite, half or quarter stitches) act as boundaries.
+  ///
+  /// Returns the added stitches and the stitches they replaced.
+  pub fn fill_region(&mut self, layer_index: u32, x: f32, y: f32, palindex: u32) -> (Vec<Stitch>, Vec<Stitch>) {
+    let (width, height) = (self.fabric.width, self.fabric.height);
+    if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+      return (vec![], vec![]);
+    }
+
+    let layer = &mut self.layers[layer_index];
+    let (start_x, start_y) = (x.trunc() as u16, y.trunc() as u16);
+    let target = cell_content(layer, start_x, start_y);
+    if matches!(target, CellContent::Partial) || target == CellContent::Full(palindex) {
+      return (vec![], vec![]);
+    }
+
+    let mut visited = HashSet::from([(start_x, start_y)]);
+    let mut queue = VecDeque::from([(start_x, start_y)]);
+    let mut cells = Vec::new();
+    while let Some((cx, cy)) = queue.pop_front() {
+      cells.push((cx, cy));
+
+      let neighbours = [
+        cx.checked_sub(1).map(|nx| (nx, cy)),
+        (cx + 1 < width).then_some((cx + 1, cy)),
+        cy.checked_sub(1).map(|ny| (cx, ny)),
+        (cy + 1 < height).then_some((cx, cy + 1)),
+      ];
+      for (nx, ny) in neighbours.into_iter().flatten() {
+        if visited.insert((nx, ny)) && cell_content(layer, nx, ny) == target {
+          queue.push_back((nx, ny));
+        }
+      }
+    }
+
+    let mut added = Vec::with_capacity(cells.len());
+    let mut replaced = Vec::new();
+    for (cx, cy) in cells {
+      let stitch = Stitch::Full(FullStitch {
+        x: NotNan::from(cx),
+        y: NotNan::from(cy),
+        palindex,
+        kind: FullStitchKind::Full,
+      });
+      replaced.extend(layer.add_stitch(stitch));
+      added.push(stitch);
+    }
+
+    (added, replaced)
+  }
+
   /// Removes and returns all stitches with a given palette index from all layers.
   pub fn remove_stitches_by_palindexes(&mut self, palindexes: &[u32]) -> Vec<Stitch> {
     self
@@ -187,24 +243,38 @@ impl Pattern {
     self.layers[layer_index].restore_stitches(stitches, palindexes, palsize);
   }
 
-  /// Flattens all visible layers into a single layer, resolving stitch conflicts.
+  /// Flattens all visible layers into a single layer, resolving stitch conflicts according to the layers' blend modes.
+  /// Only the stitch kinds that are visible on their layers are included.
   #[must_use]
   pub fn flatten_visible_layers(&self) -> Layer {
+    self.compose_visible_layers(true)
+  }
+
+  /// Merges the complete stitch sets of all visible layers into a single layer, the same way as [`Pattern::flatten_visible_layers`] does.
+  /// Unlike it, the stitch kinds hidden on their layers are included as well.
+  #[must_use]
+  pub fn merge_visible_layers(&self) -> Layer {
+    self.compose_visible_layers(false)
+  }
+
+  fn compose_visible_layers(&self, respect_kind_visibility: bool) -> Layer {
     let mut result = Layer::new("Flattened");
     // Bottom-to-top: higher layers (lower visual positions) override lower ones via `add_stitch` conflict resolution.
     for &pos in self.layers.positions().iter().rev() {
       let layer = &self.layers[pos];
-      if !layer.visible {
+      if !self.layers.is_layer_visible(pos) {
         continue;
       }
 
+      let mut compose = |stitch: Stitch| result.compose_stitch(stitch, layer.blend_mode);
+
       for &stitch in layer.fullstitches.iter() {
         let visible = match stitch.kind {
           FullStitchKind::Full => layer.fullstitches_visible,
           FullStitchKind::Petite => layer.petitestitches_visible,
         };
-        if visible {
-          result.add_stitch(Stitch::Full(stitch));
+        if visible || !respect_kind_visibility {
+          compose(Stitch::Full(stitch));
         }
       }
 
@@ -213,8 +283,8 @@ impl Pattern {
           PartStitchKind::Half => layer.halfstitches_visible,
           PartStitchKind::Quarter => layer.quarterstitches_visible,
         };
-        if visible {
-          result.add_stitch(Stitch::Part(stitch));
+        if visible || !respect_kind_visibility {
+          compose(Stitch::Part(stitch));
         }
       }
 
@@ -223,8 +293,8 @@ impl Pattern {
           LineStitchKind::Back => layer.backstitches_visible,
           LineStitchKind::Straight => layer.straightstitches_visible,
         };
-        if visible {
-          result.add_stitch(Stitch::Line(stitch));
+        if visible || !respect_kind_visibility {
+          compose(Stitch::Line(stitch));
         }
       }
 
@@ -233,14 +303,14 @@ impl Pattern {
           NodeStitchKind::FrenchKnot => layer.frenchknots_visible,
           NodeStitchKind::Bead => layer.beads_visible,
         };
-        if visible {
-          result.add_stitch(Stitch::Node(stitch));
+        if visible || !respect_kind_visibility {
+          compose(Stitch::Node(stitch));
         }
       }
 
-      if layer.specialstitches_visible {
+      if layer.specialstitches_visible || !respect_kind_visibility {
         for &stitch in layer.specialstitches.iter() {
-          result.specialstitches.insert(stitch);
+          compose(Stitch::Special(stitch));
         }
       }
     }
@@ -248,6 +318,76 @@ impl Pattern {
   }
 }
 
+/// The content of a single cell as seen by the flood fill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellContent {
+  Empty,
+  Full(u32),
+  Partial,
+}
+
+/// Determines the content of the cell with the given top-left corner.
+fn cell_content(layer: &Layer, x: u16, y: u16) -> CellContent {
+  let (x, y) = (NotNan::from(x), NotNan::from(y));
+  let full = FullStitch {
+    x,
+    y,
+    palindex: 0,
+    kind: FullStitchKind::Full,
+  };
+  if let Some(fullstitch) = layer.fullstitches.get(&full) {
+    return CellContent::Full(fullstitch.palindex);
+  }
+
+  let half = NotNan::new(0.5).unwrap();
+  let quadrants = [(x, y), (x + half, y), (x, y + half), (x + half, y + half)];
+  let directions = [PartStitchDirection::Forward, PartStitchDirection::Backward];
+
+  let has_petite = quadrants.iter().any(|&(x, y)| {
+    layer
+      .fullstitches
+      .get(&FullStitch {
+        x,
+        y,
+        kind: FullStitchKind::Petite,
+        ..full
+      })
+      .is_some()
+  });
+  let has_half = directions.iter().any(|&direction| {
+    layer
+      .partstitches
+      .get(&PartStitch {
+        x,
+        y,
+        palindex: 0,
+        direction,
+        kind: PartStitchKind::Half,
+      })
+      .is_some()
+  });
+  let has_quarter = quadrants.iter().any(|&(x, y)| {
+    directions.iter().any(|&direction| {
+      layer
+        .partstitches
+        .get(&PartStitch {
+          x,
+          y,
+          palindex: 0,
+          direction,
+          kind: PartStitchKind::Quarter,
+        })
+        .is_some()
+    })
+  });
+
+  if has_petite || has_half || has_quarter {
+    CellContent::Partial
+  } else {
+    CellContent::Empty
+  }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
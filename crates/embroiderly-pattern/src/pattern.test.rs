@@ -197,6 +197,87 @@ fn removes_no_stitches_at_empty_point() {
   assert!(pattern.layers[0].fullstitches.is_empty());
 }
 
+#[test]
+fn fills_empty_region() {
+  let mut pattern = Pattern::new(Fabric {
+    width: 3,
+    height: 3,
+    ..Fabric::default()
+  });
+
+  let (added, replaced) = pattern.fill_region(0, 1.5, 1.5, 4);
+
+  assert_eq!(added.len(), 9);
+  assert!(replaced.is_empty());
+  assert_eq!(pattern.layers[0].fullstitches.len(), 9);
+  assert!(pattern.layers[0].fullstitches.iter().all(|s| s.palindex == 4));
+}
+
+#[test]
+fn fills_same_colored_region_bounded_by_other_colors() {
+  let mut pattern = Pattern::new(Fabric {
+    width: 3,
+    height: 3,
+    ..Fabric::default()
+  });
+  // A vertical wall of palindex 1 splits the palindex 0 cells into two regions.
+  for y in 0..3 {
+    pattern.add_stitch(0, full_stitch(0.0, y as f32, 0));
+    pattern.add_stitch(0, full_stitch(1.0, y as f32, 1));
+    pattern.add_stitch(0, full_stitch(2.0, y as f32, 0));
+  }
+
+  let (added, replaced) = pattern.fill_region(0, 0.5, 0.5, 2);
+
+  assert_eq!(added.len(), 3);
+  assert_eq!(replaced.len(), 3);
+  assert!(
+    replaced
+      .iter()
+      .all(|s| matches!(s, Stitch::Full(fs) if fs.palindex == 0))
+  );
+  assert!(pattern.contains_stitch(0, &full_stitch(0.0, 2.0, 2)));
+  assert!(pattern.contains_stitch(0, &full_stitch(1.0, 2.0, 1)));
+  assert!(pattern.contains_stitch(0, &full_stitch(2.0, 2.0, 0)));
+}
+
+#[test]
+fn fill_treats_partially_stitched_cells_as_boundaries() {
+  let mut pattern = Pattern::new(Fabric {
+    width: 3,
+    height: 1,
+    ..Fabric::default()
+  });
+  pattern.add_stitch(0, quarter_stitch(1.5, 0.5, 3, PartStitchDirection::Backward));
+
+  let (added, replaced) = pattern.fill_region(0, 0.0, 0.0, 2);
+
+  assert_eq!(added, vec![full_stitch(0.0, 0.0, 2)]);
+  assert!(replaced.is_empty());
+  assert_eq!(pattern.layers[0].partstitches.len(), 1);
+}
+
+#[test]
+fn fill_does_nothing_when_region_already_has_target_color() {
+  let mut pattern = Pattern::default();
+  pattern.add_stitch(0, full_stitch(0.0, 0.0, 2));
+
+  let (added, replaced) = pattern.fill_region(0, 0.0, 0.0, 2);
+
+  assert!(added.is_empty());
+  assert!(replaced.is_empty());
+}
+
+#[test]
+fn fill_does_nothing_outside_fabric() {
+  let mut pattern = Pattern::default();
+
+  let (added, _) = pattern.fill_region(0, -1.0, 200.0, 2);
+
+  assert!(added.is_empty());
+  assert!(pattern.layers[0].fullstitches.is_empty());
+}
+
 #[test]
 fn checks_stitch_containment_in_specified_layer() {
   let mut pattern = Pattern::default();
@@ -337,6 +418,73 @@ fn flatten_skips_hidden_layer() {
   assert_eq!(flat.fullstitches.len(), 1);
 }
 
+#[test]
+fn flatten_skips_layers_of_hidden_groups() {
+  let mut pattern = Pattern::default();
+  pattern.add_stitch(0, full_stitch(0.0, 0.0, 0)); // Default (visible, ungrouped)
+  let outer = pattern.layers.push_group(LayerGroup {
+    visible: false,
+    ..LayerGroup::new("Outer")
+  });
+  let inner = pattern.layers.push_group(LayerGroup {
+    parent: Some(outer),
+    ..LayerGroup::new("Inner")
+  });
+  let layer2_idx = pattern.layers.push(Layer {
+    group: Some(inner),
+    ..Layer::new("Layer 2")
+  }); // Layer 2 (visible, but nested into a hidden group)
+  pattern.add_stitch(layer2_idx, full_stitch(1.0, 0.0, 0));
+
+  let flat = pattern.flatten_visible_layers();
+  assert_eq!(flat.fullstitches.len(), 1);
+
+  pattern.layers.get_group_mut(outer).unwrap().visible = true;
+  let flat = pattern.flatten_visible_layers();
+  assert_eq!(flat.fullstitches.len(), 2);
+}
+
+#[test]
+fn flatten_fill_empty_layer_keeps_lower_stitches() {
+  let mut pattern = Pattern::default();
+  pattern.add_stitch(0, full_stitch(0.0, 0.0, 0)); // Default (visual bottom, foreground detail)
+  let fill_idx = pattern.layers.push(Layer {
+    blend_mode: LayerBlendMode::FillEmpty,
+    ..Layer::new("Background")
+  });
+  pattern.add_stitch(fill_idx, full_stitch(0.0, 0.0, 1));
+  pattern.add_stitch(fill_idx, full_stitch(1.0, 0.0, 1));
+
+  let flat = pattern.flatten_visible_layers();
+
+  assert_eq!(flat.fullstitches.len(), 2);
+  let palindexes: Vec<_> = flat
+    .fullstitches
+    .iter()
+    .map(|stitch| (stitch.x, stitch.palindex))
+    .collect();
+  assert!(palindexes.contains(&(NotNan::new(0.0).unwrap(), 0)));
+  assert!(palindexes.contains(&(NotNan::new(1.0).unwrap(), 1)));
+}
+
+#[test]
+fn flatten_mask_layer_erases_lower_stitches() {
+  let mut pattern = Pattern::default();
+  pattern.add_stitch(0, full_stitch(0.0, 0.0, 0));
+  pattern.add_stitch(0, full_stitch(1.0, 0.0, 0));
+  let mask_idx = pattern.layers.push(Layer {
+    blend_mode: LayerBlendMode::Mask,
+    ..Layer::new("Mask")
+  });
+  pattern.add_stitch(mask_idx, full_stitch(0.0, 0.0, 1));
+
+  let flat = pattern.flatten_visible_layers();
+
+  assert_eq!(flat.fullstitches.len(), 1);
+  let stitch = flat.fullstitches.iter().next().unwrap();
+  assert_eq!((stitch.x, stitch.palindex), (NotNan::new(1.0).unwrap(), 0));
+}
+
 #[test]
 fn flatten_respects_stitch_kind_visibility() {
   let mut pattern = Pattern::default();
@@ -352,6 +500,18 @@ fn flatten_respects_stitch_kind_visibility() {
   assert_eq!(flat.fullstitches.iter().next().unwrap().kind, FullStitchKind::Petite);
 }
 
+#[test]
+fn merge_includes_hidden_stitch_kinds() {
+  let mut pattern = Pattern::default();
+  pattern.layers[0].fullstitches_visible = false;
+  pattern.add_stitch(0, full_stitch(0.0, 0.0, 0));
+  pattern.add_stitch(0, petite_stitch(1.5, 0.0, 0));
+
+  let merged = pattern.merge_visible_layers();
+
+  assert_eq!(merged.fullstitches.len(), 2);
+}
+
 #[test]
 fn flatten_non_conflicting_stitches_from_multiple_layers_all_appear() {
   let mut pattern = Pattern::default();
@@ -1,5 +1,10 @@
 use super::display::DisplaySettings;
 use super::publish::PublishSettings;
+#[cfg(feature = "borsh")]
+use super::{
+  Fabric, FullStitch, Layer, Layers, LineStitch, NodeStitch, Palette, PartStitch, PatternInfo, SpecialStitch,
+  SpecialStitchModel, Stitches,
+};
 use super::{Pattern, ReferenceImage};
 
 #[cfg(test)]
@@ -68,6 +73,125 @@ impl EmbroiderlyProject {
   }
 }
 
+#[cfg(feature = "borsh")]
+impl EmbroiderlyProject {
+  /// Decodes a project stored with borsh before layers could be locked, grouped, or blended.
+  /// The new layer properties are set to their defaults.
+  pub fn from_borsh_v1(bytes: &[u8]) -> std::io::Result<Self> {
+    let project: EmbroiderlyProjectV1 = borsh::from_slice(bytes)?;
+    Ok(Self {
+      id: project.id,
+      reference_image: project.reference_image,
+      pattern: Pattern {
+        info: project.pattern.info,
+        fabric: project.pattern.fabric,
+        palette: project.pattern.palette,
+        layers: project.pattern.layers.into(),
+        special_stitch_models: project.pattern.special_stitch_models,
+      },
+      display_settings: project.display_settings,
+      publish_settings: project.publish_settings,
+    })
+  }
+}
+
+/// Borsh form of an [`EmbroiderlyProject`] stored before layers could be locked, grouped, or blended.
+#[cfg(feature = "borsh")]
+#[derive(borsh::BorshDeserialize)]
+struct EmbroiderlyProjectV1 {
+  id: EmbroiderlyProjectId,
+  reference_image: Option<ReferenceImage>,
+  pattern: PatternV1,
+  display_settings: DisplaySettings,
+  publish_settings: PublishSettings,
+}
+
+/// Borsh form of a [`Pattern`] stored before layers could be locked, grouped, or blended.
+#[cfg(feature = "borsh")]
+#[derive(borsh::BorshDeserialize)]
+struct PatternV1 {
+  info: PatternInfo,
+  fabric: Fabric,
+  palette: Palette,
+  layers: LayersV1,
+  special_stitch_models: Vec<SpecialStitchModel>,
+}
+
+/// Borsh form of [`Layers`] stored before layer groups were introduced.
+#[cfg(feature = "borsh")]
+#[derive(borsh::BorshDeserialize)]
+struct LayersV1 {
+  items: Vec<LayerV1>,
+  positions: Vec<u32>,
+}
+
+#[cfg(feature = "borsh")]
+impl From<LayersV1> for Layers {
+  fn from(layers: LayersV1) -> Self {
+    let mut items = Self::from(layers.items.into_iter().map(Layer::from).collect::<Vec<_>>());
+    items.set_positions(layers.positions);
+    items
+  }
+}
+
+/// Borsh form of a [`Layer`] stored before layers could be locked, grouped, or blended.
+#[cfg(feature = "borsh")]
+#[derive(borsh::BorshDeserialize)]
+struct LayerV1 {
+  name: String,
+  visible: bool,
+
+  fullstitches: Stitches<FullStitch>,
+  fullstitches_visible: bool,
+  petitestitches_visible: bool,
+
+  partstitches: Stitches<PartStitch>,
+  halfstitches_visible: bool,
+  quarterstitches_visible: bool,
+
+  linestitches: Stitches<LineStitch>,
+  backstitches_visible: bool,
+  straightstitches_visible: bool,
+
+  nodestitches: Stitches<NodeStitch>,
+  frenchknots_visible: bool,
+  beads_visible: bool,
+
+  specialstitches: Stitches<SpecialStitch>,
+  specialstitches_visible: bool,
+}
+
+#[cfg(feature = "borsh")]
+impl From<LayerV1> for Layer {
+  fn from(layer: LayerV1) -> Self {
+    Self {
+      name: layer.name,
+      visible: layer.visible,
+
+      fullstitches: layer.fullstitches,
+      fullstitches_visible: layer.fullstitches_visible,
+      petitestitches_visible: layer.petitestitches_visible,
+
+      partstitches: layer.partstitches,
+      halfstitches_visible: layer.halfstitches_visible,
+      quarterstitches_visible: layer.quarterstitches_visible,
+
+      linestitches: layer.linestitches,
+      backstitches_visible: layer.backstitches_visible,
+      straightstitches_visible: layer.straightstitches_visible,
+
+      nodestitches: layer.nodestitches,
+      frenchknots_visible: layer.frenchknots_visible,
+      beads_visible: layer.beads_visible,
+
+      specialstitches: layer.specialstitches,
+      specialstitches_visible: layer.specialstitches_visible,
+
+      ..Self::default()
+    }
+  }
+}
+
 /// A builder for creating `EmbroiderlyProject`s.
 #[derive(Debug)]
 pub struct EmbroiderlyProjectBuilder {
@@ -1,4 +1,6 @@
 use super::*;
+#[cfg(feature = "borsh")]
+use crate::{Coord, FullStitch, FullStitchKind, Layer, LayerBlendMode, Stitch};
 
 #[test]
 fn creates_new_embproj_with_defaults() {
@@ -57,3 +59,81 @@ fn from_pattern_creates_embproj() {
 
   assert!(!embproj.id.is_nil());
 }
+
+#[cfg(feature = "borsh")]
+#[test]
+fn decodes_v1_borsh_snapshot() {
+  let stitch = Stitch::Full(FullStitch {
+    x: Coord::new(1.0).unwrap(),
+    y: Coord::new(2.0).unwrap(),
+    palindex: 0,
+    kind: FullStitchKind::Full,
+  });
+  let mut top = Layer::new("Top");
+  top.visible = false;
+  top.add_stitch(stitch);
+  let bottom = Layer::new("Bottom");
+  let embproj = EmbroiderlyProject::new(Pattern::default());
+
+  // Layers were stored without the lock, opacity, group, and blend mode, and without the list of groups.
+  let layer_v1 = |layer: &Layer| {
+    [
+      borsh::to_vec(&layer.name).unwrap(),
+      borsh::to_vec(&layer.visible).unwrap(),
+      borsh::to_vec(&layer.fullstitches).unwrap(),
+      borsh::to_vec(&[layer.fullstitches_visible, layer.petitestitches_visible]).unwrap(),
+      borsh::to_vec(&layer.partstitches).unwrap(),
+      borsh::to_vec(&[layer.halfstitches_visible, layer.quarterstitches_visible]).unwrap(),
+      borsh::to_vec(&layer.linestitches).unwrap(),
+      borsh::to_vec(&[layer.backstitches_visible, layer.straightstitches_visible]).unwrap(),
+      borsh::to_vec(&layer.nodestitches).unwrap(),
+      borsh::to_vec(&[layer.frenchknots_visible, layer.beads_visible]).unwrap(),
+      borsh::to_vec(&layer.specialstitches).unwrap(),
+      borsh::to_vec(&layer.specialstitches_visible).unwrap(),
+    ]
+    .concat()
+  };
+  let bytes = [
+    borsh::to_vec(&embproj.id).unwrap(),
+    borsh::to_vec(&embproj.reference_image).unwrap(),
+    borsh::to_vec(&embproj.pattern.info).unwrap(),
+    borsh::to_vec(&embproj.pattern.fabric).unwrap(),
+    borsh::to_vec(&embproj.pattern.palette).unwrap(),
+    borsh::to_vec(&2u32).unwrap(),
+    layer_v1(&bottom),
+    layer_v1(&top),
+    borsh::to_vec(&vec![1u32, 0]).unwrap(),
+    borsh::to_vec(&embproj.pattern.special_stitch_models).unwrap(),
+    borsh::to_vec(&embproj.display_settings).unwrap(),
+    borsh::to_vec(&embproj.publish_settings).unwrap(),
+  ]
+  .concat();
+
+  // The current layout can't read the old snapshot.
+  assert!(borsh::from_slice::<EmbroiderlyProject>(&bytes).is_err());
+
+  let decoded = EmbroiderlyProject::from_borsh_v1(&bytes).unwrap();
+  assert_eq!(decoded.id, embproj.id);
+  assert_eq!(decoded.pattern.fabric, embproj.pattern.fabric);
+  assert_eq!(decoded.display_settings, embproj.display_settings);
+  assert_eq!(decoded.publish_settings, embproj.publish_settings);
+
+  let layers = &decoded.pattern.layers;
+  assert_eq!(layers.positions(), &[1, 0]);
+  assert!(layers.groups().is_empty());
+  assert_eq!(layers[0].name, "Bottom");
+  assert!(layers[0].get_stitches().is_empty());
+  assert_eq!(layers[1].name, "Top");
+  assert!(!layers[1].visible);
+  assert_eq!(layers[1].get_stitches(), vec![stitch]);
+  for layer in layers.iter() {
+    assert!(!layer.locked);
+    assert_eq!(layer.opacity, 1.0);
+    assert_eq!(layer.group, None);
+    assert_eq!(layer.blend_mode, LayerBlendMode::default());
+  }
+
+  // The migrated project round-trips through the current layout.
+  let reencoded: EmbroiderlyProject = borsh::from_slice(&borsh::to_vec(&decoded).unwrap()).unwrap();
+  assert_eq!(reencoded.pattern.layers[1].get_stitches(), vec![stitch]);
+}
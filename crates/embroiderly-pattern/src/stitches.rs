@@ -25,6 +25,7 @@ pub type Coord = ordered_float::NotNan<f32>;
 
 /// Represents the bounds of a pattern.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
 pub struct Bounds {
   pub x: u16,
   pub y: u16,
@@ -20,7 +20,14 @@ impl SpecialStitch {
     let (w, h) = (model.width, model.height);
     let corners = [(0.0f32, 0.0f32), (w, 0.0), (w, h), (0.0, h)];
 
-    let (sin, cos) = (self.rotation as f32).to_radians().sin_cos();
+    // Cardinal rotations are resolved exactly, so that the box of a rotated model stays on the cell grid.
+    let (sin, cos) = match self.rotation % 360 {
+      0 => (0.0, 1.0),
+      90 => (1.0, 0.0),
+      180 => (0.0, -1.0),
+      270 => (-1.0, 0.0),
+      rotation => (rotation as f32).to_radians().sin_cos(),
+    };
 
     let mut min_x = f32::MAX;
     let mut min_y = f32::MAX;